@@ -0,0 +1,104 @@
+//! Бенчмарки на больших синтетических графах (generator::bench_fixtures): обход, интернирование
+//! имён, рендеринг дерева и DOT, экспорт в JSON. Те же графы записывает `cargo-routes generate
+//! --bench-fixture DIR`, чтобы замерять и программу целиком.
+//! Запуск: `cargo bench`; сравнение с прошлым запуском criterion печатает сам
+use cargo_routes::dot::{self, DotOptions};
+use cargo_routes::graph::{self, Graph, TraversalOptions};
+use cargo_routes::intern::CompactGraph;
+use cargo_routes::metadata::Metadata;
+use cargo_routes::report::RunStats;
+use cargo_routes::tree::{self, TreeOptions};
+use cargo_routes::{export, generator, source};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+/// Корень сгенерированных графов
+const ROOT: &str = "A";
+
+/// Графы для замеров: имя, исходный текст в тестовом формате и построенный граф
+fn fixtures() -> Vec<(&'static str, String, Graph)> {
+    generator::bench_fixtures()
+        .into_iter()
+        .map(|(name, args)| {
+            let raw = generator::generate(&args);
+            let graph = build(&raw);
+            (name, raw, graph)
+        })
+        .collect()
+}
+
+/// Транзитивный обход тестового графа от корня
+fn build(raw: &str) -> Graph {
+    let mut source = source::parse_test_graph(raw).expect("сгенерированный граф корректен");
+    let opts = TraversalOptions::default();
+    graph::build_from_source(&mut source, ROOT, None, &opts, &mut RunStats::default(), &mut |_| {})
+        .expect("обход тестового графа не завершается ошибкой")
+}
+
+fn benches(c: &mut Criterion) {
+    let fixtures = fixtures();
+    let meta = Metadata::new();
+    let stats = RunStats::default();
+
+    let mut group = c.benchmark_group("construction");
+    for (name, raw, _) in &fixtures {
+        group.bench_with_input(BenchmarkId::from_parameter(name), raw, |b, raw| {
+            b.iter_batched(
+                || source::parse_test_graph(raw).expect("сгенерированный граф корректен"),
+                |mut source| {
+                    let opts = TraversalOptions::default();
+                    graph::build_from_source(&mut source, ROOT, None, &opts, &mut RunStats::default(), &mut |_| {})
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+
+    // Интернирование имён и требований, как при обходе больших графов, и обратное преобразование
+    let mut group = c.benchmark_group("dedupe");
+    for (name, _, graph) in &fixtures {
+        group.bench_with_input(BenchmarkId::from_parameter(name), graph, |b, graph| {
+            b.iter(|| {
+                let mut compact = CompactGraph::default();
+                for (node, deps) in graph {
+                    compact.insert(node, deps);
+                }
+                compact.into_graph()
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("render_tree");
+    for (name, _, graph) in &fixtures {
+        group.bench_with_input(BenchmarkId::from_parameter(name), graph, |b, graph| {
+            b.iter(|| tree::render_tree(black_box(graph), ROOT, &TreeOptions::default()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("render_dot");
+    for (name, _, graph) in &fixtures {
+        group.bench_with_input(BenchmarkId::from_parameter(name), graph, |b, graph| {
+            b.iter(|| dot::render_dot(black_box(graph), ROOT, "1.0", &meta, &DotOptions::default()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("export_json");
+    for (name, _, graph) in &fixtures {
+        group.bench_with_input(BenchmarkId::from_parameter(name), graph, |b, graph| {
+            b.iter(|| export::to_json(black_box(graph), ROOT, "1.0", &stats, &meta, None, None))
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = graph_benches;
+    // Графы большие: меньше выборок, чтобы полный прогон занимал минуты, а не десятки минут
+    config = Criterion::default().sample_size(20);
+    targets = benches
+}
+criterion_main!(graph_benches);
@@ -0,0 +1,460 @@
+use crate::graph::Graph;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/// Все узлы графа, включая нераскрытые зависимости, в отсортированном порядке
+pub fn all_nodes(graph: &Graph) -> Vec<&str> {
+    let mut nodes: BTreeSet<&str> = graph.keys().map(String::as_str).collect();
+    for deps in graph.values() {
+        nodes.extend(deps.iter().map(|d| d.name.as_str()));
+    }
+    nodes.into_iter().collect()
+}
+
+/// Компоненты сильной связности (итеративный алгоритм Тарьяна).
+/// Компоненты возвращаются так, что зависимости идут раньше зависимых пакетов,
+/// то есть в порядке сборки; имена внутри компоненты отсортированы
+pub fn strongly_connected_components(graph: &Graph) -> Vec<Vec<String>> {
+    let nodes = all_nodes(graph);
+    let index_of: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let adjacency: Vec<Vec<usize>> = nodes
+        .iter()
+        .map(|n| {
+            graph
+                .get(*n)
+                .into_iter()
+                .flatten()
+                .map(|d| index_of[d.name.as_str()])
+                .collect()
+        })
+        .collect();
+
+    let n = nodes.len();
+    let mut index = vec![usize::MAX; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+
+    for start in 0..n {
+        if index[start] != usize::MAX {
+            continue;
+        }
+        // Стек вызовов: (узел, позиция следующего ребра)
+        let mut call_stack: Vec<(usize, usize)> = vec![(start, 0)];
+        while let Some(&mut (v, ref mut edge)) = call_stack.last_mut() {
+            if *edge == 0 && index[v] == usize::MAX {
+                index[v] = next_index;
+                lowlink[v] = next_index;
+                next_index += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+            if let Some(&w) = adjacency[v].get(*edge) {
+                *edge += 1;
+                if index[w] == usize::MAX {
+                    call_stack.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            // Все рёбра v просмотрены
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+            if lowlink[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(nodes[w].to_string());
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+/// Является ли компонента циклом: несколько узлов или узел, зависящий от самого себя
+pub fn is_cycle(graph: &Graph, component: &[String]) -> bool {
+    component.len() > 1
+        || graph
+            .get(&component[0])
+            .is_some_and(|deps| deps.iter().any(|d| d.name == component[0]))
+}
+
+/// Рендерит граф в топологическом порядке: зависимости раньше зависимых.
+/// Пакеты, входящие в цикл, выводятся одной группой и дополнительно перечисляются отдельно
+pub fn render_topological_order(graph: &Graph, root: &str, version: &str) -> String {
+    let components = strongly_connected_components(graph);
+    let mut out = format!(
+        "Порядок сборки для {} v{} (зависимости раньше зависимых):\n",
+        root, version
+    );
+    let mut cycles = Vec::new();
+    for (i, component) in components.iter().enumerate() {
+        if is_cycle(graph, component) {
+            out.push_str(&format!("{}. [цикл] {}\n", i + 1, component.join(", ")));
+            cycles.push(component);
+        } else {
+            out.push_str(&format!("{}. {}\n", i + 1, component[0]));
+        }
+    }
+
+    if cycles.is_empty() {
+        out.push_str("Циклических зависимостей нет\n");
+    } else {
+        out.push_str(&format!("Циклические зависимости ({}):\n", cycles.len()));
+        for component in cycles {
+            out.push_str(&format!("- {}\n", component.join(", ")));
+        }
+    }
+    out
+}
+
+/// Самые длинные цепочки зависимостей от корня (не больше limit): они задают глубину конвейера сборки.
+/// Цепочка — последовательность компонент сильной связности, каждая из которых — пакет или цикл;
+/// длина — число рёбер между компонентами
+pub fn longest_chains(graph: &Graph, root: &str, limit: usize) -> Vec<Vec<Vec<String>>> {
+    let components = strongly_connected_components(graph);
+    let component_of: HashMap<&str, usize> = components
+        .iter()
+        .enumerate()
+        .flat_map(|(i, names)| names.iter().map(move |name| (name.as_str(), i)))
+        .collect();
+    let Some(&start) = component_of.get(root) else { return Vec::new() };
+
+    // Компоненты упорядочены от зависимостей к зависимым: длина цепочки ниже каждой компоненты
+    // известна для всех её зависимостей к моменту её обработки
+    let mut length = vec![0usize; components.len()];
+    let mut next: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); components.len()];
+    for (i, names) in components.iter().enumerate() {
+        for dep in names.iter().flat_map(|name| graph.get(name).into_iter().flatten()) {
+            let target = component_of[dep.name.as_str()];
+            if target == i {
+                continue;
+            }
+            if length[target] + 1 > length[i] {
+                length[i] = length[target] + 1;
+                next[i].clear();
+            }
+            if length[target] + 1 == length[i] {
+                next[i].insert(target);
+            }
+        }
+    }
+
+    // Перебор равных по длине продолжений в глубину, пока не набрано limit цепочек
+    let mut chains = Vec::new();
+    let mut stack = vec![vec![start]];
+    while let Some(chain) = stack.pop() {
+        if chains.len() >= limit {
+            break;
+        }
+        let last = *chain.last().expect("цепочка не пуста");
+        if next[last].is_empty() {
+            chains.push(chain.iter().map(|&c| components[c].clone()).collect());
+            continue;
+        }
+        for &target in next[last].iter().rev() {
+            let mut longer = chain.clone();
+            longer.push(target);
+            stack.push(longer);
+        }
+    }
+    chains
+}
+
+/// Рендерит самые длинные цепочки зависимостей от корня в виде `a -> b -> c`; циклы выводятся группой
+pub fn render_longest_chains(graph: &Graph, root: &str, version: &str) -> String {
+    let chains = longest_chains(graph, root, LONGEST_CHAINS_LIMIT);
+    let length = chains.first().map(|chain| chain.len() - 1).unwrap_or(0);
+    let mut out = format!("Самые длинные цепочки зависимостей для {} v{} (длина {}):\n", root, version, length);
+    for (i, chain) in chains.iter().enumerate() {
+        let steps: Vec<String> = chain
+            .iter()
+            .map(|component| match component.as_slice() {
+                [name] if !is_cycle(graph, component) => name.clone(),
+                _ => format!("[цикл: {}]", component.join(", ")),
+            })
+            .collect();
+        out.push_str(&format!("{}. {}\n", i + 1, steps.join(" -> ")));
+    }
+    out
+}
+
+/// Сколько самых длинных цепочек перечислять в отчёте
+const LONGEST_CHAINS_LIMIT: usize = 5;
+
+/// Вклад прямой зависимости корня в граф
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub name: String,
+    /// Пакетов в поддереве: сама зависимость и её замыкание (без корня)
+    pub total: usize,
+    /// Пакетов, которые достижимы от корня только через эту зависимость и исчезнут вместе с ней
+    pub unique: usize,
+    /// Другие прямые зависимости с общими пакетами и число общих пакетов, от большего к меньшему
+    pub shared_with: Vec<(String, usize)>,
+}
+
+impl Route {
+    /// Пакетов поддерева, которые остаются в графе через другие прямые зависимости
+    pub fn shared(&self) -> usize {
+        self.total - self.unique
+    }
+}
+
+/// Вклад каждой прямой зависимости корня: сколько пакетов она приносит в граф единолично
+/// и сколько делит с другими прямыми зависимостями. Отсортировано по убыванию уникальных пакетов
+pub fn routes(graph: &Graph, root: &str) -> Vec<Route> {
+    let direct: BTreeSet<&str> = graph.get(root).into_iter().flatten().map(|dep| dep.name.as_str()).filter(|name| *name != root).collect();
+    let subtrees: Vec<(&str, HashSet<&str>)> = direct
+        .iter()
+        .map(|name| {
+            let mut subtree = closure(graph, name);
+            subtree.insert(name);
+            subtree.remove(root);
+            (*name, subtree)
+        })
+        .collect();
+    let mut owners: HashMap<&str, usize> = HashMap::new();
+    for (_, subtree) in &subtrees {
+        for node in subtree {
+            *owners.entry(node).or_default() += 1;
+        }
+    }
+
+    let mut routes: Vec<Route> = subtrees
+        .iter()
+        .map(|(name, subtree)| {
+            let mut shared_with: Vec<(String, usize)> = subtrees
+                .iter()
+                .filter(|(other, _)| other != name)
+                .map(|(other, other_subtree)| (other.to_string(), subtree.intersection(other_subtree).count()))
+                .filter(|(_, common)| *common > 0)
+                .collect();
+            shared_with.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            Route {
+                name: name.to_string(),
+                total: subtree.len(),
+                unique: subtree.iter().filter(|node| owners[*node] == 1).count(),
+                shared_with,
+            }
+        })
+        .collect();
+    routes.sort_by(|a, b| b.unique.cmp(&a.unique).then(b.total.cmp(&a.total)).then_with(|| a.name.cmp(&b.name)));
+    routes
+}
+
+/// Сколько прямых зависимостей с общими пакетами называть в строке отчёта
+const SHARED_WITH_LIMIT: usize = 3;
+
+/// Рендерит сводку маршрутов: для каждой прямой зависимости корня — размер поддерева, уникальные
+/// и общие пакеты. Отвечает на вопрос, удаление какой прямой зависимости сильнее всего уменьшит граф
+pub fn render_routes_summary(graph: &Graph, root: &str, version: &str) -> String {
+    let routes = routes(graph, root);
+    let total = all_nodes(graph).len().saturating_sub(1);
+    let mut out = format!("Вклад прямых зависимостей для {} v{} (пакетов в графе: {}):\n", root, version, total);
+    if routes.is_empty() {
+        out.push_str("У корня нет зависимостей\n");
+        return out;
+    }
+    let width = routes.iter().map(|r| r.name.chars().count()).max().unwrap_or(0).max("зависимость".chars().count());
+    out.push_str(&format!("{:<width$}  {:>6}  {:>10}  {:>6}\n", "зависимость", "всего", "уникальных", "общих"));
+    for route in &routes {
+        out.push_str(&format!("{:<width$}  {:>6}  {:>10}  {:>6}", route.name, route.total, route.unique, route.shared()));
+        if !route.shared_with.is_empty() {
+            let partners: Vec<String> =
+                route.shared_with.iter().take(SHARED_WITH_LIMIT).map(|(name, common)| format!("{} ({})", name, common)).collect();
+            let more = route.shared_with.len().saturating_sub(SHARED_WITH_LIMIT);
+            out.push_str(&format!("  общие с: {}", partners.join(", ")));
+            if more > 0 {
+                out.push_str(&format!(" и ещё {}", more));
+            }
+        }
+        out.push('\n');
+    }
+    let unique: usize = routes.iter().map(|r| r.unique).sum();
+    out.push_str(&format!(
+        "\nУникальные пакеты исчезнут из графа вместе с прямой зависимостью, общие останутся через другие.\n\
+         Пакетов с единственным маршрутом: {}, общих для нескольких прямых зависимостей: {}\n",
+        unique,
+        total - unique
+    ));
+    out
+}
+
+/// Рендерит отчёт о компонентах сильной связности: группы взаимозависимых пакетов и их размеры.
+/// Компоненты из одного пакета без петли не перечисляются, а только подсчитываются
+pub fn render_scc_report(graph: &Graph, root: &str, version: &str) -> String {
+    let components = strongly_connected_components(graph);
+    let mut cyclic: Vec<&Vec<String>> = components.iter().filter(|c| is_cycle(graph, c)).collect();
+    // Сначала крупные компоненты, при равном размере — по имени первого пакета
+    cyclic.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+
+    let in_cycles: usize = cyclic.iter().map(|c| c.len()).sum();
+    let mut out = format!("Компоненты сильной связности для {} v{}:\n", root, version);
+    out.push_str(&format!(
+        "Всего компонент: {}, циклических: {}, пакетов в циклах: {} из {}\n",
+        components.len(),
+        cyclic.len(),
+        in_cycles,
+        all_nodes(graph).len()
+    ));
+    for (i, component) in cyclic.iter().enumerate() {
+        out.push_str(&format!("{}. размер {}: {}\n", i + 1, component.len(), component.join(", ")));
+    }
+    out
+}
+
+/// Размер транзитивного замыкания каждого узла: сколько различных пакетов
+/// (не считая самого узла) он подтягивает прямо или транзитивно
+pub fn transitive_weights(graph: &Graph) -> HashMap<String, usize> {
+    all_nodes(graph)
+        .into_iter()
+        .map(|node| (node.to_string(), closure(graph, node).len()))
+        .collect()
+}
+
+/// Транзитивное замыкание узла: все пакеты, которые он подтягивает прямо или транзитивно (без самого узла)
+pub fn closure<'a>(graph: &'a Graph, node: &'a str) -> HashSet<&'a str> {
+    let mut closure: HashSet<&str> = HashSet::new();
+    let mut stack = vec![node];
+    while let Some(current) = stack.pop() {
+        for dep in graph.get(current).into_iter().flatten() {
+            if closure.insert(&dep.name) {
+                stack.push(&dep.name);
+            }
+        }
+    }
+    closure.remove(node);
+    closure
+}
+
+/// Положение узла относительно корня
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Reach {
+    /// Минимальная глубина (у корня 0)
+    pub depth: usize,
+    /// Число различных путей от корня; пути внутри циклов не различаются,
+    /// при переполнении число ограничивается u64::MAX
+    pub paths: u64,
+}
+
+/// Глубина и число путей от корня для каждого достижимого узла.
+/// Пути считаются по графу компонент сильной связности: узлы одного цикла получают общее число путей
+pub fn reach(graph: &Graph, root: &str) -> HashMap<String, Reach> {
+    let components = strongly_connected_components(graph);
+    let component_of: HashMap<&str, usize> = components
+        .iter()
+        .enumerate()
+        .flat_map(|(i, names)| names.iter().map(move |name| (name.as_str(), i)))
+        .collect();
+    // Компоненты упорядочены от зависимостей к зависимым, поэтому обратный порядок топологический
+    let mut paths = vec![0u64; components.len()];
+    if let Some(&root_component) = component_of.get(root) {
+        paths[root_component] = 1;
+    }
+    for (i, names) in components.iter().enumerate().rev() {
+        for name in names {
+            // Рёбра разных видов к одному пакету (normal и build) дают один и тот же путь
+            let targets: HashSet<usize> =
+                graph.get(name).into_iter().flatten().map(|dep| component_of[dep.name.as_str()]).collect();
+            for target in targets.into_iter().filter(|&target| target != i) {
+                paths[target] = paths[target].saturating_add(paths[i]);
+            }
+        }
+    }
+    node_depths(graph, root)
+        .into_iter()
+        .map(|(name, depth)| (name.to_string(), Reach { depth, paths: paths[component_of[name]] }))
+        .collect()
+}
+
+/// Выборка путей от корня: узел -> до limit различных простых путей к нему, от коротких к длинным.
+/// Пути наращиваются в ширину и продолжаются только из узлов, в выборку которых попали,
+/// поэтому у каждого узла оказываются кратчайшие пути через выборки его родителей, а работа
+/// ограничена числом узлов, limit и числом рёбер, а не числом всех путей в графе
+pub fn sample_paths<'a>(graph: &'a Graph, root: &'a str, limit: usize) -> HashMap<&'a str, Vec<Vec<&'a str>>> {
+    let mut samples: HashMap<&str, Vec<Vec<&str>>> = HashMap::new();
+    if limit == 0 {
+        return samples;
+    }
+    let mut queue = VecDeque::from([vec![root]]);
+    while let Some(path) = queue.pop_front() {
+        let node = *path.last().expect("путь не пуст");
+        let paths = samples.entry(node).or_default();
+        // Рёбра разных видов к одному пакету дают одинаковые пути
+        if paths.len() >= limit || paths.contains(&path) {
+            continue;
+        }
+        paths.push(path.clone());
+        for dep in graph.get(node).into_iter().flatten() {
+            if !path.contains(&dep.name.as_str()) {
+                let mut next = path.clone();
+                next.push(&dep.name);
+                queue.push_back(next);
+            }
+        }
+    }
+    samples
+}
+
+/// Глубина каждого достижимого узла — длина кратчайшего пути от корня
+pub fn node_depths<'a>(graph: &'a Graph, root: &'a str) -> HashMap<&'a str, usize> {
+    let mut depths = HashMap::from([(root, 0)]);
+    let mut queue = VecDeque::from([root]);
+    while let Some(node) = queue.pop_front() {
+        let depth = depths[node];
+        for dep in graph.get(node).into_iter().flatten() {
+            if !depths.contains_key(dep.name.as_str()) {
+                depths.insert(&dep.name, depth + 1);
+                queue.push_back(&dep.name);
+            }
+        }
+    }
+    depths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{self, TraversalOptions};
+    use crate::report::RunStats;
+    use crate::source;
+
+    /// Граф, построенный обходом тестового графа raw от корня A
+    fn build(raw: &str) -> Graph {
+        let mut source = source::parse_test_graph(raw).unwrap();
+        let opts = TraversalOptions::default();
+        graph::build_from_source(&mut source, "A", None, &opts, &mut RunStats::default(), &mut |_| {}).unwrap()
+    }
+
+    #[test]
+    fn routes_split_unique_and_shared_packages() {
+        // B и C оба приводят к C, D, E; у B нет ничего своего, кроме самого B
+        let graph = build("A: C B\nB: C\nC: D\nD: E\nE:\n");
+        let routes = routes(&graph, "A");
+        let summary: Vec<(&str, usize, usize, usize)> =
+            routes.iter().map(|r| (r.name.as_str(), r.total, r.unique, r.shared())).collect();
+        assert_eq!(summary, [("B", 4, 1, 3), ("C", 3, 0, 3)]);
+        assert_eq!(routes[0].shared_with, [("C".to_string(), 3)]);
+    }
+
+    #[test]
+    fn reach_counts_each_target_once_per_node() {
+        // B зависит от C и как от обычной, и как от build-зависимости: путь к C через B один
+        let graph = build("A: B C\nB: C C [build]\nC:\n");
+        let reach = reach(&graph, "A");
+        assert_eq!((reach["B"].paths, reach["C"].paths), (1, 2));
+    }
+}
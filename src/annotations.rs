@@ -0,0 +1,135 @@
+use crate::config::Config;
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+use crate::policy;
+use crate::report::RunStats;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Находка для CI: нарушение политики (ошибка) или предупреждение построения графа
+struct Finding {
+    error: bool,
+    /// Правило политики или вид предупреждения: "warning", "truncated"
+    check: String,
+    message: String,
+}
+
+/// Нарушения политики, предупреждения и сведения об усечении графа
+fn collect_findings(config: &Config, graph: &Graph, stats: &RunStats, meta: &Metadata) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = policy::check(&config.policy, graph, &config.name, meta)
+        .into_iter()
+        .map(|v| Finding { error: true, check: v.rule.to_string(), message: format!("{}: {}", v.package, v.message) })
+        .collect();
+    for warning in &stats.warnings {
+        findings.push(Finding { error: false, check: "warning".to_string(), message: warning.clone() });
+    }
+    for node in &stats.unresolved {
+        findings.push(Finding {
+            error: true,
+            check: "unresolved".to_string(),
+            message: format!("не удалось разрешить '{}': {}", node.name, node.error),
+        });
+    }
+    if !stats.truncated_by_depth.is_empty() {
+        findings.push(Finding {
+            error: false,
+            check: "truncated".to_string(),
+            message: format!(
+                "граф усечён ограничением max_depth, не раскрыты зависимости: {}",
+                stats.truncated_by_depth.join(", ")
+            ),
+        });
+    }
+    if !stats.truncated_by_max_nodes.is_empty() {
+        findings.push(Finding {
+            error: false,
+            check: "truncated".to_string(),
+            message: format!(
+                "граф усечён лимитом max_nodes, не раскрыто узлов: {}",
+                stats.truncated_by_max_nodes.len()
+            ),
+        });
+    }
+    if !stats.truncated_by_interrupt.is_empty() {
+        findings.push(Finding {
+            error: false,
+            check: "truncated".to_string(),
+            message: format!("обход прерван (Ctrl-C), не раскрыто узлов: {}", stats.truncated_by_interrupt.len()),
+        });
+    }
+    if !stats.unexpanded_in_source.is_empty() {
+        findings.push(Finding {
+            error: false,
+            check: "truncated".to_string(),
+            message: format!("импортированный граф неполный, не раскрыто узлов: {}", stats.unexpanded_in_source.len()),
+        });
+    }
+    findings
+}
+
+/// Команды аннотаций GitHub Actions (`::error::`, `::warning::`): находки видны прямо в PR
+pub fn render_github(config: &Config, graph: &Graph, stats: &RunStats, meta: &Metadata) -> String {
+    let mut out = String::new();
+    for finding in collect_findings(config, graph, stats, meta) {
+        let command = if finding.error { "error" } else { "warning" };
+        out.push_str(&format!(
+            "::{} title={}::{}\n",
+            command,
+            escape_property(&format!("cargo-routes {}", finding.check)),
+            escape_data(&finding.message)
+        ));
+    }
+    out
+}
+
+/// Экранирование текста аннотации GitHub
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Экранирование значения параметра аннотации GitHub
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Запись отчёта GitLab Code Quality
+#[derive(Serialize)]
+struct CodeQualityIssue {
+    description: String,
+    check_name: String,
+    fingerprint: String,
+    severity: &'static str,
+    location: CodeQualityLocation,
+}
+
+#[derive(Serialize)]
+struct CodeQualityLocation {
+    path: &'static str,
+    lines: CodeQualityLines,
+}
+
+#[derive(Serialize)]
+struct CodeQualityLines {
+    begin: usize,
+}
+
+/// Отчёт GitLab Code Quality (artifacts:reports:codequality): находки видны в merge request.
+/// Отпечаток — SHA-256 правила и текста: он не меняется между запусками и версиями Rust,
+/// поэтому GitLab сопоставляет находки между сборками
+pub fn render_gitlab(config: &Config, graph: &Graph, stats: &RunStats, meta: &Metadata) -> String {
+    let issues: Vec<CodeQualityIssue> = collect_findings(config, graph, stats, meta)
+        .into_iter()
+        .map(|finding| {
+            // Нулевой байт разделяет поля, чтобы разные пары не склеивались в одну строку
+            let digest = Sha256::new().chain_update(&finding.check).chain_update([0]).chain_update(&finding.message).finalize();
+            CodeQualityIssue {
+                fingerprint: digest.iter().map(|b| format!("{:02x}", b)).collect(),
+                severity: if finding.error { "major" } else { "info" },
+                description: finding.message,
+                check_name: finding.check,
+                location: CodeQualityLocation { path: "Cargo.toml", lines: CodeQualityLines { begin: 1 } },
+            }
+        })
+        .collect();
+    serde_json::to_string_pretty(&issues).expect("отчёт всегда сериализуется в JSON") + "\n"
+}
@@ -0,0 +1,156 @@
+use crate::config::{CacheKind, Config};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+/// Хранилище ответов crates.io: ключ -> сериализованное значение.
+/// Ключи имеют вид "latest:serde" (последняя версия) и "deps:serde:1.0.219" (зависимости версии;
+/// "index-deps:serde:1.0.219" для sparse-индекса)
+pub trait Cache {
+    /// Значение по ключу, если оно сохранено
+    fn get(&mut self, key: &str) -> Result<Option<String>, String>;
+
+    /// Сохраняет значение по ключу
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String>;
+
+    /// Записывает накопленные изменения в постоянное хранилище
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Кэш в памяти: живёт только в течение одного запуска
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: HashMap<String, String>,
+}
+
+impl Cache for MemoryCache {
+    fn get(&mut self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.entries.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+/// Кэш в JSON-файле: читается целиком при открытии и перезаписывается при flush
+pub struct FileCache {
+    path: String,
+    entries: BTreeMap<String, String>,
+    dirty: bool,
+}
+
+impl FileCache {
+    /// Открывает файл кэша; отсутствующий файл означает пустой кэш
+    pub fn open(path: &str) -> Result<Self, String> {
+        let entries = match fs::read_to_string(path) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map_err(|e| format!("Ошибка разбора файла кэша '{}': {}", path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("Ошибка чтения файла кэша '{}': {}", path, e)),
+        };
+        Ok(FileCache { path: path.to_string(), entries, dirty: false })
+    }
+}
+
+impl Cache for FileCache {
+    fn get(&mut self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.entries.insert(key.to_string(), value.to_string());
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let raw = serde_json::to_string_pretty(&self.entries).expect("кэш всегда сериализуется в JSON");
+        fs::write(&self.path, raw).map_err(|e| format!("Ошибка записи файла кэша '{}': {}", self.path, e))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Кэш в базе SQLite: каждое значение записывается сразу, файл можно делить между запусками
+pub struct SqliteCache {
+    conn: rusqlite::Connection,
+}
+
+impl SqliteCache {
+    /// Открывает (или создаёт) базу кэша
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| format!("Ошибка открытия базы кэша '{}': {}", path, e))?;
+        conn.execute("CREATE TABLE IF NOT EXISTS cache (key TEXT PRIMARY KEY, value TEXT NOT NULL)", [])
+            .map_err(|e| format!("Ошибка создания таблицы кэша: {}", e))?;
+        Ok(SqliteCache { conn })
+    }
+}
+
+impl Cache for SqliteCache {
+    fn get(&mut self, key: &str) -> Result<Option<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT value FROM cache WHERE key = ?1")
+            .map_err(|e| format!("Ошибка чтения кэша: {}", e))?;
+        let mut rows = stmt.query([key]).map_err(|e| format!("Ошибка чтения кэша: {}", e))?;
+        match rows.next().map_err(|e| format!("Ошибка чтения кэша: {}", e))? {
+            Some(row) => row.get(0).map(Some).map_err(|e| format!("Ошибка чтения кэша: {}", e)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.conn
+            .execute("INSERT OR REPLACE INTO cache (key, value) VALUES (?1, ?2)", [key, value])
+            .map(|_| ())
+            .map_err(|e| format!("Ошибка записи кэша: {}", e))
+    }
+}
+
+/// Открывает кэш, выбранный в конфигурации
+pub fn open(config: &Config) -> Result<Box<dyn Cache + Send>, String> {
+    Ok(match config.cache {
+        CacheKind::Memory => Box::new(MemoryCache::default()),
+        CacheKind::File => Box::new(FileCache::open(config.cache_path.as_deref().unwrap_or(".cargo-routes-cache.json"))?),
+        CacheKind::Sqlite => {
+            Box::new(SqliteCache::open(config.cache_path.as_deref().unwrap_or(".cargo-routes-cache.sqlite"))?)
+        }
+    })
+}
+
+/// Кэш, общий для нескольких обходов в разных потоках (см. поле roots): ответ crates.io,
+/// полученный при обходе одного корня, используется и при обходе остальных
+#[derive(Clone)]
+pub struct SharedCache(Arc<Mutex<Box<dyn Cache + Send>>>);
+
+impl SharedCache {
+    pub fn new(cache: Box<dyn Cache + Send>) -> Self {
+        SharedCache(Arc::new(Mutex::new(cache)))
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Box<dyn Cache + Send>>, String> {
+        self.0.lock().map_err(|_| "Общий кэш недоступен: обход другого корня завершился аварийно".to_string())
+    }
+}
+
+impl Cache for SharedCache {
+    fn get(&mut self, key: &str) -> Result<Option<String>, String> {
+        self.lock()?.get(key)
+    }
+
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.lock()?.put(key, value)
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.lock()?.flush()
+    }
+}
@@ -0,0 +1,53 @@
+use crate::graph::Graph;
+use crate::report::SkippedNode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Состояние прерванного обхода, из которого его можно продолжить (--resume).
+/// При записи граф G — компактный граф обхода, при чтении — обычный Graph
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Checkpoint<G = Graph> {
+    /// Корень обхода и его версия: контрольная точка другого корня не подходит
+    pub root: String,
+    pub version: Option<String>,
+    /// Уже раскрытые узлы и их зависимости
+    pub graph: G,
+    /// Узел -> глубина, на которой он раскрыт
+    pub visited: BTreeMap<String, usize>,
+    /// Каноническое имя -> имя узла в графе
+    pub names: BTreeMap<String, String>,
+    /// Рабочий список обхода: (пакет, версия, глубина)
+    pub pending: Vec<(String, Option<String>, usize)>,
+    pub truncated_by_depth: Vec<String>,
+    pub skipped_on_error: Vec<SkippedNode>,
+    #[serde(default)]
+    pub unresolved: Vec<SkippedNode>,
+}
+
+impl<G: Serialize> Checkpoint<G> {
+    /// Записывает контрольную точку: сначала во временный файл, затем переименованием,
+    /// чтобы прерывание во время записи не испортило предыдущую точку
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let tmp = format!("{}.tmp", path);
+        let raw = serde_json::to_string(self).map_err(|e| format!("Ошибка сериализации контрольной точки: {}", e))?;
+        fs::write(&tmp, raw).map_err(|e| format!("Ошибка записи контрольной точки '{}': {}", tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Ошибка записи контрольной точки '{}': {}", path, e))
+    }
+}
+
+/// Читает контрольную точку из файла path
+pub fn load(path: &str) -> Result<Checkpoint, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("Ошибка чтения контрольной точки '{}': {}", path, e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Ошибка разбора контрольной точки '{}': {}", path, e))
+}
+
+/// Удаляет контрольную точку после успешного завершения обхода
+pub fn remove(path: &str) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Ошибка удаления контрольной точки '{}': {}", path, e))
+        }
+        _ => Ok(()),
+    }
+}
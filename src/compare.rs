@@ -0,0 +1,100 @@
+use crate::analysis;
+use crate::cache::SharedCache;
+use crate::config::{Config, Root};
+use crate::graph::{self, Graph};
+use crate::prune::{Pattern, Prune};
+use crate::report::RunStats;
+use crate::roots;
+use std::collections::BTreeSet;
+use std::thread;
+
+/// Разбирает корень для сравнения в виде `имя` или `имя@версия`; без версии берётся последняя
+pub fn parse_root(spec: &str) -> Root {
+    match spec.split_once('@') {
+        Some((name, version)) => Root { name: name.to_string(), version: version.to_string() },
+        None => Root { name: spec.to_string(), version: "latest".to_string() },
+    }
+}
+
+/// Граф одного из сравниваемых корней
+struct Side {
+    name: String,
+    version: String,
+    graph: Graph,
+}
+
+impl Side {
+    /// Транзитивное замыкание корня
+    fn closure(&self) -> BTreeSet<&str> {
+        analysis::closure(&self.graph, &self.name).into_iter().collect()
+    }
+
+    fn direct(&self) -> usize {
+        self.graph.get(&self.name).map_or(0, Vec::len)
+    }
+}
+
+/// Строит графы корня из конфигурации и пакета other параллельно (с общим кэшем crates.io)
+/// и сравнивает их замыкания: общие зависимости, зависимости только одного из пакетов и размеры
+pub fn run(config: &Config, other: &Root, prune_patterns: &[Pattern]) -> Result<String, String> {
+    let shared = roots::shared_cache(config)?;
+    let first = Root { name: config.name.clone(), version: config.version.clone() };
+    let results: Vec<Result<Side, String>> = thread::scope(|scope| {
+        let handles: Vec<_> = [&first, other]
+            .into_iter()
+            .map(|root| scope.spawn(|| build(config, root, shared.as_ref(), prune_patterns)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err("обход завершился аварийно".to_string())))
+            .collect()
+    });
+    let mut sides = Vec::new();
+    for (root, result) in [&first, other].into_iter().zip(results) {
+        sides.push(result.map_err(|e| format!("{} v{}: {}", root.name, root.version, e))?);
+    }
+    Ok(render_comparison(&sides[0], &sides[1]))
+}
+
+/// Обход одного корня с удалением пакетов по шаблонам
+fn build(config: &Config, root: &Root, shared: Option<&SharedCache>, prune_patterns: &[Pattern]) -> Result<Side, String> {
+    let config = roots::root_config(config, root)?;
+    let mut stats = RunStats::default();
+    let graph = graph::build_graph_with_cache(&config, shared, &mut stats, &mut |_| {})?;
+    let graph = if prune_patterns.is_empty() { graph } else { graph.pruned(&config.name, prune_patterns) };
+    Ok(Side { name: config.name, version: config.version, graph })
+}
+
+/// Сводка сравнения двух замыканий: размеры рядом и списки общих и уникальных зависимостей
+fn render_comparison(a: &Side, b: &Side) -> String {
+    let (closure_a, closure_b) = (a.closure(), b.closure());
+    let shared: Vec<&str> = closure_a.intersection(&closure_b).copied().collect();
+    let only_a: Vec<&str> = closure_a.difference(&closure_b).copied().collect();
+    let only_b: Vec<&str> = closure_b.difference(&closure_a).copied().collect();
+
+    let label_a = format!("{} v{}", a.name, a.version);
+    let label_b = format!("{} v{}", b.name, b.version);
+    let width = ["прямых зависимостей".chars().count(), label_a.chars().count(), label_b.chars().count()]
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+    let row = |title: &str, x: &str, y: &str| format!("{:<width$}  {:<width$}  {}\n", title, x, y, width = width);
+
+    let mut out = format!("Сравнение {} и {}:\n", label_a, label_b);
+    out.push_str(&row("", &label_a, &label_b));
+    out.push_str(&row("пакетов в замыкании", &closure_a.len().to_string(), &closure_b.len().to_string()));
+    out.push_str(&row("прямых зависимостей", &a.direct().to_string(), &b.direct().to_string()));
+    out.push_str(&row("только у пакета", &only_a.len().to_string(), &only_b.len().to_string()));
+    out.push_str(&format!("общих пакетов: {}\n", shared.len()));
+    for (title, names) in [
+        ("Общие зависимости".to_string(), &shared),
+        (format!("Только у {}", a.name), &only_a),
+        (format!("Только у {}", b.name), &only_b),
+    ] {
+        out.push_str(&format!("\n{} ({}):\n", title, names.len()));
+        for name in names.iter() {
+            out.push_str(&format!("    {}\n", name));
+        }
+    }
+    out
+}
@@ -0,0 +1,394 @@
+use crate::validate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Конфигурация приложения
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Config {
+    pub name: String,
+    pub repository: String,
+    pub test_repo_mode: String, // "test" или "remote"
+    /// Источник графа: "crawl" (по умолчанию) — обход репозитория, "json" — ранее экспортированный
+    /// JSON-граф по пути repository, "manifest" — локальный Cargo.toml по пути repository
+    #[serde(default)]
+    pub source: SourceKind,
+    /// Версия корня: точная, "latest" или диапазон semver ("^1", "1.*"), который перед обходом
+    /// заменяется наибольшей подходящей версией (см. graph::resolve_root_version)
+    pub version: String,
+    /// Несколько корней: граф каждого строится параллельно (с общим кэшем) и выводится в свой файл
+    /// по шаблону output_filename. name и version при этом не используются
+    #[serde(default)]
+    pub roots: Vec<Root>,
+    /// Объединение графов нескольких источников (например рабочего пространства и внешнего пакета):
+    /// name и version задают общий корень, к которому подключаются корни источников
+    #[serde(default)]
+    pub merge: Vec<MergeSource>,
+    /// Файл вывода; при нескольких корнях — шаблон, в котором `{crate}` и `{version}`
+    /// заменяются именем и версией корня, например "{crate}-{version}.txt"
+    pub output_filename: String,
+    /// Заголовок графа вместо «Граф зависимостей для …»; `{crate}` и `{version}` заменяются корнем.
+    /// Выводится в шапке ASCII и Markdown, в подписи DOT и Mermaid и на веб-странице
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Подпись корня в дереве, DOT и на веб-странице вместо имени пакета (например "сервис оплаты")
+    #[serde(default)]
+    pub root_label: Option<String>,
+    /// Текст под графом (легенда, источник данных): последняя строка ASCII и Markdown,
+    /// вторая строка подписи DOT и подвал веб-страницы
+    #[serde(default)]
+    pub footer: Option<String>,
+    /// Печатать под графом легенду: значения отметок ([build], (optional), (цикл: …) и т.д.), число циклов
+    /// и нераскрытых ветвей. Выводится в ASCII и Markdown (перед footer) и на веб-странице --serve
+    #[serde(default)]
+    pub legend: bool,
+    #[allow(dead_code)]
+    pub ascii_tree_mode: bool,
+    /// Ограничение глубины (у корня 0). Глубина пакета — длина кратчайшего пути к нему от корня,
+    /// одинаково при обходе и при печати дерева
+    pub max_depth: Option<usize>,
+    /// Прежняя семантика глубины: пакет раскрывается только на глубине, на которой встречен впервые,
+    /// а дерево ограничивается глубиной в каждой ветви отдельно
+    #[serde(default)]
+    pub depth_per_branch: bool,
+    /// Глубина отображения дерева: поддеревья глубже неё сворачиваются в строку `... ещё пакетов: N`,
+    /// а граф для остальных форматов и анализа остаётся полным
+    #[serde(default)]
+    pub display_depth: Option<usize>,
+    /// Ограничение числа пакетов: после N раскрытых узлов обход останавливается
+    #[serde(default)]
+    pub max_nodes: Option<usize>,
+    /// Порядок обхода: "dfs" (по умолчанию) или "bfs"
+    #[serde(default)]
+    pub traversal: Strategy,
+    /// Файл контрольной точки: состояние долгого обхода периодически сохраняется в него,
+    /// и прерванный обход можно продолжить с флагом --resume
+    #[serde(default)]
+    pub checkpoint_path: Option<String>,
+    /// Через сколько раскрытых узлов обновлять контрольную точку (0 — только при ошибке обхода)
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: usize,
+    /// Продолжить обход с контрольной точки checkpoint_path
+    #[serde(default)]
+    pub resume: bool,
+    /// Печатать в stderr ветви дерева по мере обхода, не дожидаясь его окончания
+    #[serde(default)]
+    pub stream_tree: bool,
+    /// Формат вывода: "ascii" (по умолчанию), "json", "ndjson", "markdown", "dot", "mermaid", "graphml",
+    /// "gexf", "matrix", "topo", "scc", "routes", "longest", "duplicates", "outdated", "footprint", "owners", "features",
+    /// "unification", "deny" (заготовка deny.toml для cargo-deny), "github" или "gitlab" (аннотации CI)
+    #[serde(default = "default_output_format")]
+    pub output_format: String,
+    /// Печатать рядом с пакетом размер его транзитивного замыкания: `clap (+34)`
+    #[serde(default)]
+    pub show_weights: bool,
+    /// Печатать рядом с пакетом минимальную глубину и число различных путей к нему от корня
+    /// (в JSON-экспорте — поля depth и paths)
+    #[serde(default)]
+    pub show_paths: bool,
+    /// Сколько различных путей от корня к каждому пакету выводить в JSON-экспорте (поле sample_paths),
+    /// от коротких к длинным: «как пакет сюда попал» без повторного поиска путей
+    #[serde(default)]
+    pub path_samples: Option<usize>,
+    /// Печатать на рёбрах требование родителя и версию, до которой оно разрешилось:
+    /// `serde (^1.0.150) -> v1.0.210` в дереве, подписи рёбер в DOT, поле version в JSON
+    #[serde(default)]
+    pub show_requirements: bool,
+    /// Сортировать зависимости в дереве по убыванию размера замыкания
+    #[serde(default)]
+    pub sort_by_weight: bool,
+    /// Группировать пакеты в DOT-экспорте в кластеры по прямой зависимости корня
+    #[serde(default)]
+    pub dot_clusters: bool,
+    /// Метрика, по которой масштабируются узлы в DOT-экспорте и на веб-странице:
+    /// "closure" (размер транзитивного замыкания), "downloads" (число загрузок) или "depth" (глубина)
+    #[serde(default)]
+    pub node_size_metric: Option<NodeMetric>,
+    /// Динамический GEXF-экспорт: интервалы присутствия пакетов и рёбер берутся из истории запусков (history_path)
+    #[serde(default)]
+    pub gexf_dynamic: bool,
+    /// Запрашивать владельцев пакетов на crates.io (для JSON-экспорта и отчёта "owners")
+    #[serde(default)]
+    pub fetch_owners: bool,
+    /// Запрашивать на crates.io число пакетов, зависящих от каждого пакета графа
+    #[serde(default)]
+    pub fetch_dependents: bool,
+    /// Запрашивать описание, репозиторий и дату последнего релиза пакетов на crates.io
+    #[serde(default)]
+    pub fetch_metadata: bool,
+    /// Добавлять к пакетам ссылку на документацию на docs.rs (в JSON — поле docs_url)
+    #[serde(default)]
+    pub docs_links: bool,
+    /// Запрашивать на docs.rs, собралась ли документация пакетов; пакеты с несобранной документацией
+    /// помечаются в дереве и перечисляются под ним. Включает docs_links
+    #[serde(default)]
+    pub fetch_docs_status: bool,
+    /// Помечать пакеты, последний релиз которых старше указанного числа дней (например 1095 — три года),
+    /// как возможно не поддерживаемые: в дереве и отдельным разделом под ним. Включает запрос дат релизов
+    #[serde(default)]
+    pub stale_after_days: Option<u64>,
+    /// Категории или ключевые слова crates.io (`cryptography`, `ffi`): подходящие пакеты выделяются
+    /// в дереве и в DOT. Категория подходит и по любой своей части (`ffi` — `development-tools::ffi`)
+    #[serde(default)]
+    pub highlight_tags: Vec<String>,
+    /// Категории или ключевые слова, пакеты с которыми печатаются в дереве без поддеревьев
+    #[serde(default)]
+    pub fold_tags: Vec<String>,
+    /// Категории или ключевые слова, пакеты с которыми удаляются из графа, как по шаблонам prune
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+    /// Путь к JSON-файлу заметок о пакетах: {"имя пакета": "заметка"} (команда-владелец, обоснование,
+    /// "к удалению"). Заметки печатаются рядом с пакетами в дереве и попадают в экспорт
+    #[serde(default)]
+    pub notes_path: Option<String>,
+    /// Гиперссылки на crates.io в ASCII-дереве: "auto" (по умолчанию), "always" или "never"
+    #[serde(default = "default_hyperlinks")]
+    pub hyperlinks: String,
+    /// Не прерывать обход из-за ошибок запросов: пакеты, которые не удалось получить, остаются в графе
+    /// неразрешёнными узлами, перечисляются в отчёте, а запуск завершается с кодом 2
+    #[serde(default)]
+    pub keep_going: bool,
+    /// Дата YYYY-MM-DD, на которую восстанавливается граф: "latest" и диапазоны разрешаются в наибольшую
+    /// версию, опубликованную не позже этой даты. Даты публикации берутся из API crates.io
+    #[serde(default)]
+    pub as_of: Option<String>,
+    /// Путь к отчёту о запуске в формате JSON (не создаётся, если не задан)
+    #[serde(default)]
+    pub report_path: Option<String>,
+    /// Записывать в отчёт время запуска и длительность (по умолчанию отчёт воспроизводим)
+    #[serde(default)]
+    pub report_timestamps: bool,
+    /// Таймаут установки соединения с crates.io в секундах
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u64>,
+    /// Общий таймаут HTTP-запроса от соединения до конца чтения ответа, в секундах (по умолчанию 30)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Прокси для запросов к crates.io, например "http://proxy.corp:3128"
+    /// (без него используются переменные окружения HTTP_PROXY/HTTPS_PROXY)
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// User-Agent запросов к crates.io; правила crates.io требуют указывать в нём контакты
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    /// Дополнительные заголовки запросов (например, токен реестра); значение "env:NAME"
+    /// берётся из переменной окружения NAME. В отчёт и историю не записываются, чтобы не раскрывать токены
+    #[serde(default, skip_serializing)]
+    pub headers: BTreeMap<String, String>,
+    /// Откуда брать версии и зависимости: "api" (по умолчанию, два запроса на пакет)
+    /// или "sparse" (sparse-индекс index.crates.io, один запрос на пакет)
+    #[serde(default)]
+    pub registry_protocol: RegistryProtocol,
+    /// Сверять контрольные суммы из sparse-индекса со скачанными архивами .crate; при расхождении
+    /// пакет не разрешается. Сверенные суммы запоминаются в кэше (только registry_protocol: "sparse")
+    #[serde(default)]
+    pub verify_checksums: bool,
+    /// Число потоков, параллельно запрашивающих crates.io (по умолчанию 1 — последовательно)
+    #[serde(default = "default_jobs")]
+    pub jobs: usize,
+    /// Кэш ответов crates.io: "memory" (по умолчанию), "file" (JSON-файл) или "sqlite"
+    #[serde(default)]
+    pub cache: CacheKind,
+    /// Путь к файлу кэша для "file" и "sqlite"
+    /// (по умолчанию .cargo-routes-cache.json и .cargo-routes-cache.sqlite)
+    #[serde(default)]
+    pub cache_path: Option<String>,
+    /// Инкрементальный обход: последние версии пакетов из постоянного кэша перепроверяются,
+    /// а зависимости заново запрашиваются только для изменившихся версий
+    #[serde(default)]
+    pub incremental: bool,
+    /// База SQLite, в которую записывается граф каждого запуска (см. подкоманду history)
+    #[serde(default)]
+    pub history_path: Option<String>,
+    /// Перед записью в историю (history_path) печатать в stderr сводку изменений по сравнению
+    /// с прошлым запуском того же корня: новые и удалённые пакеты, изменения версий
+    #[serde(default)]
+    pub change_alerts: bool,
+    /// Не обходить опциональные зависимости (подключаемые через features)
+    #[serde(default)]
+    pub exclude_optional: bool,
+    /// Включать dev-зависимости корня и участников рабочего пространства (source = "manifest").
+    /// Как и в Cargo, dev-зависимости зависимостей не включаются никогда
+    #[serde(default)]
+    pub include_dev: bool,
+    /// Цель сборки корня при source = "manifest": "lib", "bin:имя", "example:имя", "bench:имя" или "test:имя"
+    /// (имя можно опустить, если цель этого вида одна). В граф попадают только зависимости, нужные цели:
+    /// dev-зависимости корня — только у примеров, бенчмарков и тестов (независимо от include_dev),
+    /// опциональные зависимости, включаемые required-features цели, — всегда
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Объединять features участников рабочего пространства, как Cargo при сборке всего рабочего
+    /// пространства (source = "manifest"): опциональные зависимости локальных пакетов, которые включает
+    /// хотя бы один участник, становятся обязательными для всех. Что добавило объединение и каким
+    /// участником — в формате вывода "unification"
+    #[serde(default)]
+    pub unify_features: bool,
+    /// Шаблоны пакетов, удаляемых из построенного графа вместе со ставшими недостижимыми
+    /// зависимостями, например ["windows-*", "*-sys"]
+    #[serde(default)]
+    pub prune: Vec<String>,
+    /// Шаблоны семейств пакетов, сворачиваемых при выводе в один групповой узел,
+    /// например ["tokio-*", "windows-*", "serde*"]; участники группы перечисляются в JSON (members)
+    #[serde(default)]
+    pub group: Vec<String>,
+    /// Выражение фильтрации узлов, например `depth<=3 && kind!=build && name~"^tokio"`
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Правила гигиены зависимостей, проверяемые по построенному графу
+    #[serde(default)]
+    pub policy: Policy,
+    /// Путь к файлу SARIF с нарушениями политики (для GitHub code scanning; не создаётся, если не задан)
+    #[serde(default)]
+    pub sarif_path: Option<String>,
+}
+
+/// Корень графа в списке roots
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Root {
+    pub name: String,
+    pub version: String,
+}
+
+/// Источник в списке merge: корень и откуда строится его граф. Не заданные поля берутся из конфигурации
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MergeSource {
+    /// Метка источника, которой помечаются пакеты графа (по умолчанию — имя корня)
+    #[serde(default)]
+    pub label: Option<String>,
+    pub name: String,
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub source: SourceKind,
+    #[serde(default)]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub test_repo_mode: Option<String>,
+}
+
+impl MergeSource {
+    /// Метка источника в графе
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Правила гигиены зависимостей: при нарушениях они перечисляются в stderr,
+/// а программа завершается с ненулевым кодом
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Policy {
+    /// Запрещённые пакеты
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Запрещённые лицензии (идентификаторы SPDX, например "GPL-3.0")
+    #[serde(default)]
+    pub deny_licenses: Vec<String>,
+    /// Наибольшая допустимая глубина отдельных пакетов: пакет -> глубина
+    #[serde(default)]
+    pub max_depth: BTreeMap<String, usize>,
+    /// Запретить несовместимые по semver требования к одному пакету (см. формат "duplicates")
+    #[serde(default)]
+    pub deny_duplicate_majors: bool,
+}
+
+impl Policy {
+    /// Не задано ни одного правила
+    pub fn is_empty(&self) -> bool {
+        self.deny.is_empty()
+            && self.deny_licenses.is_empty()
+            && self.max_depth.is_empty()
+            && !self.deny_duplicate_majors
+    }
+}
+
+/// Порядок обхода графа
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// В глубину (стек)
+    #[default]
+    Dfs,
+    /// В ширину (очередь): узлы раскрываются в порядке возрастания глубины
+    Bfs,
+}
+
+/// Откуда берётся граф зависимостей
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Обход тестового репозитория или crates.io (в зависимости от test_repo_mode)
+    #[default]
+    Crawl,
+    /// Импорт графа, ранее сохранённого с output_format = "json"
+    Json,
+    /// Локальный Cargo.toml (пакета или рабочего пространства) по пути repository: path-зависимости
+    /// читаются из своих манифестов, внешние запрашиваются у crates.io
+    Manifest,
+}
+
+/// Где хранится кэш ответов crates.io
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKind {
+    /// В памяти, только на время запуска
+    #[default]
+    Memory,
+    /// В JSON-файле
+    File,
+    /// В базе SQLite
+    Sqlite,
+}
+
+/// Способ получения данных о пакетах с crates.io
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistryProtocol {
+    /// Web API: версии и зависимости запрашиваются отдельно
+    #[default]
+    Api,
+    /// Sparse-индекс: все версии пакета вместе с зависимостями одним запросом
+    Sparse,
+}
+
+/// Метрика для масштабирования узлов в визуальных экспортах
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeMetric {
+    /// Размер транзитивного замыкания
+    Closure,
+    /// Число загрузок с crates.io (требует запроса сведений о пакетах)
+    Downloads,
+    /// Глубина узла от корня
+    Depth,
+}
+
+fn default_jobs() -> usize {
+    1
+}
+
+fn default_checkpoint_interval() -> usize {
+    100
+}
+
+fn default_user_agent() -> String {
+    "dep-visualizer (edu)".to_string()
+}
+
+fn default_version() -> String {
+    "latest".to_string()
+}
+
+fn default_hyperlinks() -> String {
+    "auto".to_string()
+}
+
+fn default_output_format() -> String {
+    "ascii".to_string()
+}
+
+/// Читает, проверяет (см. validate::load) и разбирает конфигурационный файл JSON
+pub fn load_config(path: &str) -> Result<Config, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Ошибка чтения конфигурации '{}': {}", path, e))?;
+    validate::load(&raw, path)
+}
@@ -0,0 +1,97 @@
+use crate::analysis;
+use crate::config::Config;
+use crate::duplicates;
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Отдельные лицензии выражения SPDX: `MIT OR Apache-2.0` -> MIT, Apache-2.0.
+/// Исключения остаются при своей лицензии (`Apache-2.0 WITH LLVM-exception`),
+/// устаревшая запись через `/` разбирается как OR
+fn license_ids(expression: &str) -> Vec<String> {
+    let spaced = expression.replace(['(', ')'], " ").replace('/', " OR ");
+    let mut ids: Vec<String> = Vec::new();
+    let mut tokens = spaced.split_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "OR" | "AND" => {}
+            "WITH" => {
+                if let (Some(last), Some(exception)) = (ids.last_mut(), tokens.next()) {
+                    last.push_str(&format!(" WITH {}", exception));
+                }
+            }
+            _ => ids.push(token.to_string()),
+        }
+    }
+    ids
+}
+
+/// Строка TOML в кавычках
+fn quoted(s: &str) -> String {
+    serde_json::to_string(s).expect("строка всегда сериализуется")
+}
+
+/// Рендерит заготовку deny.toml для cargo-deny по построенному графу: встреченные лицензии,
+/// пакеты в нескольких несовместимых версиях и используемые источники.
+/// Лицензии известны, только если собраны сведения о пакетах (см. metadata::collect)
+pub fn render_deny_toml(config: &Config, graph: &Graph, meta: &Metadata) -> String {
+    let nodes = analysis::all_nodes(graph);
+    let local: BTreeSet<&str> = graph.values().flatten().filter(|dep| dep.local).map(|dep| dep.name.as_str()).collect();
+
+    let mut out = format!("# Заготовка конфигурации cargo-deny для {} v{}, созданная cargo-routes.\n", config.name, config.version);
+    out.push_str("# Списки отражают текущий граф: проверьте их перед тем, как включать проверку в CI.\n");
+
+    let mut licenses: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unlicensed = Vec::new();
+    for name in nodes.iter().filter(|name| **name != config.name && !local.contains(*name)) {
+        match meta.get(*name).and_then(|m| m.license.as_deref()) {
+            Some(expression) => license_ids(expression).into_iter().for_each(|id| *licenses.entry(id).or_default() += 1),
+            None => unlicensed.push(*name),
+        }
+    }
+    out.push_str("\n[licenses]\n");
+    out.push_str("# Лицензии, встреченные в графе (число пакетов)\n");
+    out.push_str("allow = [\n");
+    for (id, count) in &licenses {
+        out.push_str(&format!("    {}, # {}\n", quoted(id), count));
+    }
+    out.push_str("]\n");
+    out.push_str("confidence-threshold = 0.8\n");
+    if !unlicensed.is_empty() {
+        out.push_str(&format!("# Лицензия неизвестна ({}): {}\n", unlicensed.len(), unlicensed.join(", ")));
+    }
+
+    out.push_str("\n[bans]\n");
+    out.push_str("# Новые дубликаты запрещены; уже существующие разрешены списком skip.\n");
+    out.push_str("# Удалите запись из skip, чтобы запретить и этот дубликат\n");
+    out.push_str("multiple-versions = \"deny\"\n");
+    out.push_str("skip = [\n");
+    for duplicate in duplicates::find_duplicates(graph) {
+        let classes: Vec<String> = duplicate
+            .classes
+            .iter()
+            .map(|(class, parents)| {
+                let parents: Vec<&str> = parents.iter().map(|(parent, _)| *parent).collect();
+                format!("{} ({})", class, parents.join(", "))
+            })
+            .collect();
+        out.push_str(&format!(
+            "    {{ crate = {}, reason = {} }},\n",
+            quoted(duplicate.name),
+            quoted(&format!("версии {}", classes.join("; ")))
+        ));
+    }
+    out.push_str("]\n");
+    out.push_str("deny = []\n");
+
+    out.push_str("\n[sources]\n");
+    out.push_str("unknown-registry = \"deny\"\n");
+    out.push_str("unknown-git = \"deny\"\n");
+    out.push_str("allow-registry = [\"https://github.com/rust-lang/crates.io-index\"]\n");
+    out.push_str("allow-git = []\n");
+    if !local.is_empty() {
+        let local: Vec<&str> = local.into_iter().collect();
+        out.push_str(&format!("# Локальные пакеты (path), cargo-deny их не проверяет: {}\n", local.join(", ")));
+    }
+    out
+}
@@ -0,0 +1,67 @@
+/// Сколько строк контекста показывать вокруг изменений
+const CONTEXT: usize = 3;
+
+/// Размер таблицы LCS, после которого построчный diff не строится
+const MAX_LCS_CELLS: usize = 25_000_000;
+
+/// Строит построчный diff в стиле unified: строки эталона помечаются `-`, фактические — `+`.
+/// Для очень больших файлов выводится только первая различающаяся строка
+pub fn unified_diff(expected: &str, actual: &str, expected_name: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+    let mut out = format!("--- {}\n+++ фактический вывод\n", expected_name);
+
+    if a.len().saturating_mul(b.len()) > MAX_LCS_CELLS {
+        let line = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+        out.push_str(&format!("@@ первое различие в строке {} @@\n", line + 1));
+        if let Some(l) = a.get(line) {
+            out.push_str(&format!("-{}\n", l));
+        }
+        if let Some(l) = b.get(line) {
+            out.push_str(&format!("+{}\n", l));
+        }
+        return out;
+    }
+
+    // lcs[i][j] — длина наибольшей общей подпоследовательности a[i..] и b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    // Последовательность правок: (' ' | '-' | '+', строка)
+    let mut ops: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', b[j]));
+            j += 1;
+        }
+    }
+
+    // Оставляем изменённые строки и CONTEXT строк вокруг них
+    let changed: Vec<usize> = ops.iter().enumerate().filter(|(_, (op, _))| *op != ' ').map(|(k, _)| k).collect();
+    let mut last_printed: Option<usize> = None;
+    for &k in &changed {
+        let from = k.saturating_sub(CONTEXT).max(last_printed.map_or(0, |l| l + 1));
+        let to = (k + CONTEXT).min(ops.len() - 1);
+        if last_printed.is_none_or(|l| from > l + 1) {
+            let line = ops[..from].iter().filter(|(op, _)| *op != '+').count() + 1;
+            out.push_str(&format!("@@ строка {} @@\n", line));
+        }
+        for (op, text) in &ops[from..=to] {
+            out.push_str(&format!("{}{}\n", op, text));
+        }
+        last_printed = Some(to);
+    }
+    out
+}
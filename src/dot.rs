@@ -0,0 +1,180 @@
+use crate::analysis;
+use crate::export;
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// Параметры DOT-экспорта
+#[derive(Default)]
+pub struct DotOptions {
+    /// Группировать пакеты в кластеры по прямой зависимости корня, через которую они подключены
+    pub clusters: bool,
+    /// Относительный масштаб узлов (см. export::node_scale): крупнее и краснее — больше значение метрики
+    pub node_scale: BTreeMap<String, f64>,
+    /// Пакеты с категориями или ключевыми словами из highlight_tags: обводятся толстой оранжевой рамкой
+    pub highlighted: HashSet<String>,
+    /// Подпись графа вместо «имя vверсия»
+    pub title: Option<String>,
+    /// Подпись узла корня вместо его имени
+    pub root_label: Option<String>,
+    /// Вторая строка подписи графа
+    pub footer: Option<String>,
+    /// Подписывать рёбра требованием к версии, а узлы — разрешённой версией (show_requirements)
+    pub requirements: bool,
+}
+
+/// Рендерит граф в формате Graphviz DOT.
+/// Описание пакета попадает во всплывающую подсказку узла, репозиторий — в ссылку
+pub fn render_dot(graph: &Graph, root: &str, version: &str, meta: &Metadata, opts: &DotOptions) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph deps {{");
+    let mut label = opts.title.clone().unwrap_or_else(|| format!("{} v{}", root, version));
+    if let Some(footer) = &opts.footer {
+        label = format!("{}\n{}", label, footer);
+    }
+    let _ = writeln!(out, "    label={};", quote(&label));
+    let _ = writeln!(out, "    rankdir=LR;");
+    let _ = writeln!(out, "    node [shape=box];");
+
+    let mut lines: Vec<(&str, String)> = Vec::new();
+    for name in analysis::all_nodes(graph) {
+        let mut attrs = Vec::new();
+        let mut styles = Vec::new();
+        let version = meta.get(name).and_then(|m| m.version.as_ref()).filter(|_| opts.requirements);
+        match (&opts.root_label, version) {
+            (Some(label), _) if name == root => attrs.push(format!("label={}", quote(label))),
+            (_, Some(version)) => attrs.push(format!("label={}", quote(&format!("{}\nv{}", name, version)))),
+            _ => {}
+        }
+        if name == root {
+            styles.push("bold");
+        }
+        if let Some(scale) = opts.node_scale.get(name) {
+            // Цвет в HSV: насыщенность красного растёт вместе с метрикой
+            styles.push("filled");
+            attrs.push(format!("fillcolor=\"0.0 {:.3} 1.0\"", scale));
+            attrs.push(format!("fontsize={:.1}", 10.0 + 10.0 * scale));
+        }
+        if opts.highlighted.contains(name) {
+            attrs.push("color=darkorange".to_string());
+            attrs.push("penwidth=3".to_string());
+        }
+        // Неразрешённый пакет (keep_going) выделяется красным пунктиром, ошибка — в подсказке
+        if meta.get(name).is_some_and(|m| m.unresolved.is_some()) {
+            styles.push("dashed");
+            attrs.push("color=red".to_string());
+        }
+        match styles.as_slice() {
+            [] => {}
+            [style] => attrs.insert(0, format!("style={}", style)),
+            _ => attrs.insert(0, format!("style={}", quote(&styles.join(",")))),
+        }
+        if let Some(m) = meta.get(name) {
+            let mut tooltip = m.description.clone().unwrap_or_default();
+            if let Some(date) = &m.latest_release {
+                tooltip = format!("{}\nпоследний релиз: {}", tooltip, date).trim().to_string();
+            }
+            if let Some(count) = m.dependents {
+                tooltip = format!("{}\nзависимых пакетов: {}", tooltip, count).trim().to_string();
+            }
+            if let Some(error) = &m.unresolved {
+                tooltip = format!("{}\nне разрешён: {}", tooltip, error).trim().to_string();
+            }
+            if let Some(note) = &m.note {
+                tooltip = format!("{}\nзаметка: {}", tooltip, note).trim().to_string();
+            }
+            if !tooltip.is_empty() {
+                attrs.push(format!("tooltip={}", quote(&tooltip)));
+            }
+            if let Some(repo) = &m.repository {
+                attrs.push(format!("URL={}", quote(repo)));
+            }
+        }
+        if attrs.is_empty() {
+            lines.push((name, format!("{};", quote(name))));
+        } else {
+            lines.push((name, format!("{} [{}];", quote(name), attrs.join(", "))));
+        }
+    }
+
+    // Пакеты, подключённые только через одну прямую зависимость, попадают в её кластер;
+    // общие для нескольких прямых зависимостей остаются вне кластеров
+    let owners = if opts.clusters { cluster_owners(graph, root) } else { HashMap::new() };
+    let mut clusters: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (name, line) in &lines {
+        match owners.get(*name) {
+            Some(owner) => clusters.entry(owner.as_str()).or_default().push(line),
+            None => {
+                let _ = writeln!(out, "    {}", line);
+            }
+        }
+    }
+    for (i, (owner, members)) in clusters.iter().enumerate() {
+        let _ = writeln!(out, "    subgraph {} {{", quote(&format!("cluster_{}", i)));
+        let _ = writeln!(out, "        label={};", quote(owner));
+        for line in members {
+            let _ = writeln!(out, "        {}", line);
+        }
+        let _ = writeln!(out, "    }}");
+    }
+
+    for name in analysis::all_nodes(graph) {
+        for dep in graph.get(name).into_iter().flatten() {
+            // Обычные рёбра выводятся без атрибутов; остальные окрашиваются и подписываются
+            let style = export::edge_style(dep);
+            let mut attrs = Vec::new();
+            if style.color != "black" {
+                attrs.push(format!("color={}", style.color));
+            }
+            if style.line != "solid" {
+                attrs.push(format!("style={}", style.line));
+            }
+            let req = dep.req.as_ref().filter(|_| opts.requirements);
+            let label = match (req, &style.label) {
+                (Some(req), Some(label)) => Some(format!("{} {}", req, label)),
+                (Some(req), None) => Some(req.clone()),
+                (None, label) => label.clone(),
+            };
+            if let Some(label) = label {
+                attrs.push(format!("label={}", quote(&label)));
+            }
+            if attrs.is_empty() {
+                let _ = writeln!(out, "    {} -> {};", quote(name), quote(&dep.name));
+            } else {
+                let _ = writeln!(out, "    {} -> {} [{}];", quote(name), quote(&dep.name), attrs.join(", "));
+            }
+        }
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// Для каждого пакета — прямая зависимость корня, через которую он подключён,
+/// если такая зависимость единственная
+fn cluster_owners(graph: &Graph, root: &str) -> HashMap<String, String> {
+    let mut reached_from: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for direct in graph.get(root).into_iter().flatten() {
+        // Обход от прямой зависимости, не проходящий через корень
+        let mut stack = vec![direct.name.as_str()];
+        let mut seen = HashSet::new();
+        while let Some(node) = stack.pop() {
+            if node == root || !seen.insert(node) {
+                continue;
+            }
+            reached_from.entry(node).or_default().insert(&direct.name);
+            stack.extend(graph.get(node).into_iter().flatten().map(|d| d.name.as_str()));
+        }
+    }
+    // Прямая зависимость, достижимая из другой прямой зависимости, тоже считается общей
+    reached_from
+        .into_iter()
+        .filter(|(_, owners)| owners.len() == 1)
+        .flat_map(|(node, owners)| owners.into_iter().map(move |owner| (node.to_string(), owner.to_string())))
+        .collect()
+}
+
+/// Строка в кавычках с экранированием для DOT
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
@@ -0,0 +1,118 @@
+use crate::analysis;
+use crate::graph::Graph;
+use semver::{Op, VersionReq};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Класс semver-совместимости требования: версии одного класса Cargo объединяет в одну,
+/// версии разных классов попадают в сборку по отдельности.
+/// `^1.2` и `~1.5` — класс `1`, `^0.3` — класс `0.3`, `=0.0.4` — класс `0.0.4`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CompatClass {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl CompatClass {
+    /// Класс требования; None, если требование не привязано к одному классу (`*`, `>=1`, `<2`)
+    pub fn of(req: &str) -> Option<CompatClass> {
+        let req = VersionReq::parse(req).ok()?;
+        let comparator = req
+            .comparators
+            .iter()
+            .find(|c| matches!(c.op, Op::Caret | Op::Tilde | Op::Exact | Op::Wildcard))?;
+        let (major, minor, patch) = (comparator.major, comparator.minor, comparator.patch);
+        Some(match (major, minor) {
+            (0, Some(0)) => CompatClass { major, minor, patch },
+            (0, Some(_)) => CompatClass { major, minor, patch: None },
+            // `0.*` и `^0` допускают любые версии 0.x — это не один класс
+            (0, None) => return None,
+            _ => CompatClass { major, minor: None, patch: None },
+        })
+    }
+}
+
+impl fmt::Display for CompatClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.major)?;
+        if let Some(minor) = self.minor {
+            write!(f, ".{}", minor)?;
+        }
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Требования к пакету по классам: класс -> родители и их требования (по возрастанию класса)
+pub type ClassRequirements<'a> = BTreeMap<CompatClass, Vec<(&'a str, &'a str)>>;
+
+/// Пакет, к которому родители предъявляют требования нескольких несовместимых классов
+pub struct Duplicate<'a> {
+    pub name: &'a str,
+    pub classes: ClassRequirements<'a>,
+}
+
+impl Duplicate<'_> {
+    /// Класс, к которому предлагается привести остальных: самый новый
+    pub fn target(&self) -> CompatClass {
+        *self.classes.keys().next_back().expect("у дубликата не меньше двух классов")
+    }
+}
+
+/// Находит пакеты, которые в настоящей сборке попали бы в граф в нескольких версиях:
+/// граф хранит один узел на пакет, но требования родителей к нему несовместимы между собой
+pub fn find_duplicates(graph: &Graph) -> Vec<Duplicate<'_>> {
+    let mut by_crate: BTreeMap<&str, ClassRequirements> = BTreeMap::new();
+    for parent in analysis::all_nodes(graph) {
+        for dep in graph.get(parent).into_iter().flatten() {
+            let Some(req) = dep.req.as_deref() else { continue };
+            if let Some(class) = CompatClass::of(req) {
+                by_crate.entry(&dep.name).or_default().entry(class).or_default().push((parent, req));
+            }
+        }
+    }
+    by_crate
+        .into_iter()
+        .filter(|(_, classes)| classes.len() > 1)
+        .map(|(name, classes)| Duplicate { name, classes })
+        .collect()
+}
+
+/// Рендерит отчёт о дубликатах с предложениями, каких родителей обновить,
+/// чтобы все требования к пакету свелись к одной (самой новой) совместимой версии
+pub fn render_duplicates_report(graph: &Graph, root: &str, version: &str) -> String {
+    let duplicates = find_duplicates(graph);
+    let mut out = format!("Дублирующиеся пакеты для {} v{}:\n", root, version);
+    if duplicates.is_empty() {
+        out.push_str("Несовместимых требований к версиям нет\n");
+        return out;
+    }
+    for duplicate in &duplicates {
+        out.push_str(&format!("{}: несовместимых версий: {}\n", duplicate.name, duplicate.classes.len()));
+        for (class, parents) in &duplicate.classes {
+            let parents: Vec<String> = parents.iter().map(|(parent, req)| format!("{} ({})", parent, req)).collect();
+            out.push_str(&format!("  {}: {}\n", class, parents.join(", ")));
+        }
+    }
+
+    out.push_str("\nПредложения:\n");
+    for duplicate in &duplicates {
+        let target = duplicate.target();
+        let outdated: Vec<&str> = duplicate
+            .classes
+            .iter()
+            .filter(|(class, _)| **class != target)
+            .flat_map(|(_, parents)| parents.iter().map(|(parent, _)| *parent))
+            .collect();
+        out.push_str(&format!(
+            "- обновить {} до версий, требующих {} {}\n",
+            outdated.join(", "),
+            duplicate.name,
+            target
+        ));
+    }
+    out
+}
@@ -0,0 +1,127 @@
+use crate::analysis;
+use crate::cache;
+use crate::config::{Config, RegistryProtocol, SourceKind};
+use crate::graph::{self, Graph};
+use crate::manifest::ManifestSource;
+use crate::registry::Registry;
+use crate::source::{self, Candidate, Source};
+use semver::{Version, VersionReq};
+
+/// Источник, из которого строился граф, для повторных запросов версий
+fn open_source(config: &Config) -> Result<Box<dyn Source>, String> {
+    Ok(if config.source == SourceKind::Json {
+        Box::new(source::load_json_graph(&config.repository)?)
+    } else if config.source == SourceKind::Manifest {
+        let registry = Registry::new(config, cache::open(config)?)?;
+        Box::new(ManifestSource::open(&config.repository, registry)?)
+    } else if config.test_repo_mode == "test" {
+        Box::new(source::load_test_graph(&config.repository)?)
+    } else {
+        Box::new(Registry::new(config, cache::open(config)?)?)
+    })
+}
+
+/// Правило, по которому источник выбрал версию пакета.
+/// fixed — версия не выбиралась из кандидатов (корень обхода crates.io или локальный пакет)
+fn resolution_rule(config: &Config, fixed: bool) -> &'static str {
+    match (config.source, config.test_repo_mode.as_str(), config.registry_protocol) {
+        (SourceKind::Json, _, _) => "версия сохранена в импортированном графе",
+        (SourceKind::Manifest, _, _) if fixed => "версия указана в локальном манифесте",
+        (SourceKind::Crawl, "test", _) => "версия указана в тестовом графе",
+        _ if fixed => "версия корня задана в конфигурации",
+        (_, _, RegistryProtocol::Sparse) => "последняя неотозванная версия индекса, пререлиз — только если нет стабильных",
+        (_, _, RegistryProtocol::Api) => "первая (самая новая) версия в списке crates.io API",
+    }
+}
+
+/// Положение версии-кандидата относительно выбранной
+enum Status {
+    Chosen,
+    /// Пропущена с указанной причиной или новее выбранной
+    Listed(&'static str),
+    /// Старше выбранной (в том числе отозванные): такие версии только подсчитываются
+    Older,
+}
+
+fn candidate_status(candidate: &Candidate, chosen: Option<&Version>) -> Status {
+    let version = Version::parse(&candidate.num).ok();
+    match version {
+        Some(v) if Some(&v) == chosen => Status::Chosen,
+        Some(v) if chosen.is_some_and(|c| &v < c) => Status::Older,
+        _ if candidate.yanked => Status::Listed("пропущена: отозвана (yanked)"),
+        None => Status::Listed("пропущена: версия не разбирается"),
+        Some(v) if !v.pre.is_empty() => Status::Listed("пропущена: пререлиз"),
+        Some(_) => Status::Listed("новее выбранной"),
+    }
+}
+
+/// Объясняет, как в граф попал пакет name: какие родители его требуют и с какими требованиями,
+/// какие версии существуют и почему выбрана именно эта
+pub fn explain(config: &Config, graph: &Graph, name: &str) -> Result<String, String> {
+    let canonical = graph::canonical_name(name);
+    let node = analysis::all_nodes(graph)
+        .into_iter()
+        .find(|node| graph::canonical_name(node) == canonical)
+        .ok_or_else(|| format!("Пакет '{}' отсутствует в графе", name))?;
+    // Версия корня обхода crates.io задаётся конфигурацией, локальных пакетов — манифестом
+    let crawl_root = node == config.name && config.source == SourceKind::Crawl && config.test_repo_mode != "test";
+    let local = config.source == SourceKind::Manifest
+        && (node == config.name || graph.values().flatten().any(|dep| dep.name == node && dep.local));
+
+    let mut source = open_source(config)?;
+    let chosen = if crawl_root { Some(config.version.clone()) } else { source.resolve_version(node)? };
+    let chosen_version = chosen.as_deref().and_then(|v| Version::parse(v).ok());
+    let candidates = source.candidates(node)?;
+
+    let mut out = format!("Пакет {}:\n", node);
+    let mut parents = Vec::new();
+    for parent in analysis::all_nodes(graph) {
+        for dep in graph.get(parent).into_iter().flatten().filter(|dep| dep.name == node) {
+            let req = dep.req.as_deref().unwrap_or("*");
+            let mut marks = Vec::new();
+            if dep.kind != "normal" {
+                marks.push(dep.kind.clone());
+            }
+            if dep.optional {
+                marks.push("optional".to_string());
+            }
+            if let Some(alias) = &dep.alias {
+                marks.push(format!("как {}", alias));
+            }
+            let marks = if marks.is_empty() { String::new() } else { format!(" [{}]", marks.join(", ")) };
+            let fit = match (VersionReq::parse(req), &chosen_version) {
+                (Ok(req), Some(version)) if req.matches(version) => ", допускает выбранную версию",
+                (Ok(_), Some(_)) => ", не допускает выбранную версию",
+                _ => "",
+            };
+            parents.push(format!("  {}: требование {}{}{}\n", parent, req, marks, fit));
+        }
+    }
+    if parents.is_empty() {
+        out.push_str("Корень графа, родителей нет\n");
+    } else {
+        out.push_str(&format!("Требуется родителями ({}):\n", parents.len()));
+        parents.iter().for_each(|line| out.push_str(line));
+    }
+
+    match &chosen {
+        Some(version) => {
+            out.push_str(&format!("Выбрана версия {}: {}\n", version, resolution_rule(config, crawl_root || local)))
+        }
+        None => out.push_str("Версия неизвестна\n"),
+    }
+
+    let mut older = 0;
+    out.push_str(&format!("Версии-кандидаты ({}):\n", candidates.len()));
+    for candidate in &candidates {
+        match candidate_status(candidate, chosen_version.as_ref()) {
+            Status::Chosen => out.push_str(&format!("  {}: выбрана\n", candidate.num)),
+            Status::Listed(reason) => out.push_str(&format!("  {}: {}\n", candidate.num, reason)),
+            Status::Older => older += 1,
+        }
+    }
+    if older > 0 {
+        out.push_str(&format!("  ... и более старых версий: {}\n", older));
+    }
+    Ok(out)
+}
@@ -0,0 +1,236 @@
+use crate::analysis::{self, Reach};
+use crate::config::NodeMetric;
+use crate::graph::{Dep, Graph};
+use crate::metadata::{Metadata, NodeMeta};
+use crate::report::{RunStats, SkippedNode};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Представление графа для экспорта в JSON
+#[derive(Serialize, Debug)]
+pub struct JsonGraph<'a> {
+    pub root: &'a str,
+    pub version: &'a str,
+    pub nodes: Vec<JsonNode<'a>>,
+    /// Узлы, не раскрытые из-за лимита max_nodes
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    pub unexpanded: &'a [String],
+    /// Узлы, не раскрытые из-за прерывания обхода (Ctrl-C)
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    pub interrupted: &'a [String],
+    /// Узлы, не раскрытые ещё в исходном обходе импортированного графа
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    pub unexpanded_in_source: &'a [String],
+    /// Пакеты, которые не удалось разрешить при keep_going, с ошибкой: в nodes их нет,
+    /// потому что их зависимости неизвестны
+    #[serde(skip_serializing_if = "<[SkippedNode]>::is_empty")]
+    pub unresolved: &'a [SkippedNode],
+}
+
+#[derive(Serialize, Debug)]
+pub struct JsonNode<'a> {
+    pub name: &'a str,
+    pub dependencies: &'a [Dep],
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<&'a NodeMeta>,
+    /// Глубина и число путей от корня (show_paths)
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub reach: Option<Reach>,
+    /// Несколько различных путей от корня к пакету, от коротких к длинным (path_samples)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_paths: Option<&'a [Vec<&'a str>]>,
+}
+
+/// Подписи графа для Mermaid, GraphML и GEXF (title, root_label и footer из конфигурации)
+#[derive(Default)]
+pub struct Captions {
+    /// Заголовок графа; без него — «имя vверсия»
+    pub title: Option<String>,
+    /// Подпись узла корня вместо его имени
+    pub root_label: Option<String>,
+    /// Строка под графом
+    pub footer: Option<String>,
+}
+
+impl Captions {
+    /// Заголовок графа корня root версии version
+    pub fn title(&self, root: &str, version: &str) -> String {
+        self.title.clone().unwrap_or_else(|| format!("{} v{}", root, version))
+    }
+
+    /// Подпись узла name: root_label у корня, имя у остальных
+    pub fn label<'a>(&'a self, name: &'a str, root: &str) -> &'a str {
+        match &self.root_label {
+            Some(label) if name == root => label,
+            _ => name,
+        }
+    }
+}
+
+/// Нумерация узлов для экспортов, которым нужны числовые идентификаторы
+/// (Mermaid, GraphML, матрица смежности): узлы нумеруются в порядке analysis::all_nodes
+pub struct NodeIndex<'a> {
+    pub nodes: Vec<&'a str>,
+    ids: HashMap<&'a str, usize>,
+}
+
+impl<'a> NodeIndex<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        NodeIndex::with_extra(graph, std::iter::empty())
+    }
+
+    /// Нумерация узлов графа вместе с узлами extra, которых в графе нет (например, пакетов
+    /// из истории запусков); порядок тот же, что у analysis::all_nodes
+    pub fn with_extra(graph: &'a Graph, extra: impl Iterator<Item = &'a str>) -> Self {
+        let mut nodes: BTreeSet<&str> = analysis::all_nodes(graph).into_iter().collect();
+        nodes.extend(extra);
+        let nodes: Vec<&str> = nodes.into_iter().collect();
+        let ids = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        NodeIndex { nodes, ids }
+    }
+
+    /// Номер узла; все узлы и цели рёбер графа пронумерованы
+    pub fn id(&self, name: &str) -> usize {
+        self.ids[name]
+    }
+}
+
+/// Оформление ребра в визуальных экспортах (DOT, Mermaid, GraphML) в зависимости от вида зависимости:
+/// build — синие, dev — серые, опциональные — пунктирные; у не-normal рёбер есть подпись
+pub struct EdgeStyle {
+    /// Цвет линии (имя цвета, понятное Graphviz и CSS)
+    pub color: &'static str,
+    /// "solid", "dashed" или "dotted"
+    pub line: &'static str,
+    /// Подпись: вид зависимости, опциональность и имя переименованной зависимости
+    pub label: Option<String>,
+}
+
+pub fn edge_style(dep: &Dep) -> EdgeStyle {
+    let color = match dep.kind.as_str() {
+        "build" => "blue",
+        "dev" => "gray",
+        _ => "black",
+    };
+    let line = if dep.optional {
+        "dashed"
+    } else if dep.kind == "dev" {
+        "dotted"
+    } else {
+        "solid"
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    if let Some(alias) = &dep.alias {
+        parts.push(alias);
+    }
+    if dep.kind != "normal" {
+        parts.push(&dep.kind);
+    }
+    if dep.optional {
+        parts.push("optional");
+    }
+    let label = (!parts.is_empty()).then(|| parts.join(", "));
+    EdgeStyle { color, line, label }
+}
+
+/// Экранирование текста для XML (GraphML, GEXF)
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Относительный масштаб узлов по метрике: от 0 (минимум метрики в графе) до 1 (максимум).
+/// Число загрузок масштабируется логарифмически, иначе несколько самых популярных пакетов
+/// затмевают все остальные. Узлы без значения метрики (загрузки неизвестны) в результат не попадают
+pub fn node_scale(graph: &Graph, root: &str, meta: &Metadata, metric: NodeMetric) -> BTreeMap<String, f64> {
+    let values: Vec<(&str, f64)> = match metric {
+        NodeMetric::Closure => {
+            let weights = analysis::transitive_weights(graph);
+            analysis::all_nodes(graph)
+                .into_iter()
+                .map(|name| (name, weights.get(name).copied().unwrap_or(0) as f64))
+                .collect()
+        }
+        NodeMetric::Downloads => analysis::all_nodes(graph)
+            .into_iter()
+            .filter_map(|name| meta.get(name)?.downloads.map(|d| (name, (d as f64).ln_1p())))
+            .collect(),
+        NodeMetric::Depth => analysis::node_depths(graph, root).into_iter().map(|(name, d)| (name, d as f64)).collect(),
+    };
+    let min = values.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+    let max = values.iter().map(|(_, v)| *v).fold(f64::NEG_INFINITY, f64::max);
+    values
+        .into_iter()
+        .map(|(name, v)| {
+            let scale = if max > min { (v - min) / (max - min) } else { 0.0 };
+            (name.to_string(), scale)
+        })
+        .collect()
+}
+
+/// Матрица смежности в CSV: строки и столбцы — пакеты в порядке NodeIndex,
+/// на пересечении строки A и столбца B стоит 1, если A напрямую зависит от B
+pub fn to_adjacency_csv(graph: &Graph) -> String {
+    let index = NodeIndex::new(graph);
+    let mut out = String::new();
+    for name in &index.nodes {
+        out.push(',');
+        out.push_str(&csv_field(name));
+    }
+    out.push('\n');
+    for name in &index.nodes {
+        let mut row = vec!['0'; index.nodes.len()];
+        for dep in graph.get(*name).into_iter().flatten() {
+            row[index.id(&dep.name)] = '1';
+        }
+        out.push_str(&csv_field(name));
+        for cell in row {
+            out.push(',');
+            out.push(cell);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Поле CSV; кавычки нужны, только если в значении есть разделители
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Сериализует граф в JSON; из stats берутся нераскрытые и неразрешённые узлы,
+/// meta — дополнительные сведения о пакетах (владельцы и т.п.), reach — глубины и числа путей узлов,
+/// samples — выборки путей от корня (см. analysis::sample_paths)
+pub fn to_json(
+    graph: &Graph,
+    root: &str,
+    version: &str,
+    stats: &RunStats,
+    meta: &Metadata,
+    reach: Option<&HashMap<String, Reach>>,
+    samples: Option<&HashMap<&str, Vec<Vec<&str>>>>,
+) -> String {
+    let nodes = graph
+        .iter()
+        .map(|(name, deps)| JsonNode {
+            name,
+            dependencies: deps,
+            meta: meta.get(name),
+            reach: reach.and_then(|r| r.get(name).copied()),
+            sample_paths: samples.and_then(|s| s.get(name.as_str())).map(Vec::as_slice),
+        })
+        .collect();
+    let doc = JsonGraph {
+        root,
+        version,
+        nodes,
+        unexpanded: &stats.truncated_by_max_nodes,
+        interrupted: &stats.truncated_by_interrupt,
+        unexpanded_in_source: &stats.unexpanded_in_source,
+        unresolved: &stats.unresolved,
+    };
+    serde_json::to_string_pretty(&doc).expect("граф всегда сериализуется в JSON")
+}
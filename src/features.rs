@@ -0,0 +1,142 @@
+use crate::analysis;
+use crate::config::{Config, SourceKind};
+use crate::graph::{Dep, Graph};
+use crate::metadata::Metadata;
+use crate::report::RunStats;
+use crate::source::Features;
+use crate::tree::{self, TreeStyle};
+use std::collections::{BTreeSet, HashSet};
+
+/// Рендерит представление «feature -> опциональные зависимости» для корневого пакета:
+/// для каждой feature перечисляются включаемые ею features и опциональные зависимости
+/// вместе с их поддеревьями и размером транзитивного замыкания `(+N)`
+pub fn render_features(graph: &Graph, config: &Config, meta: &Metadata) -> String {
+    let root = &config.name;
+    let mut out = format!("Features пакета {} v{}:\n", root, config.version);
+    let Some(table) = meta.get(root).and_then(|m| m.features.as_ref()) else {
+        out.push_str("Сведения о features недоступны\n");
+        return out;
+    };
+
+    let direct: Vec<&Dep> = graph.get(root).into_iter().flatten().collect();
+    let optional: Vec<&Dep> = direct.iter().copied().filter(|d| d.optional).collect();
+    let features = with_implicit_features(table, &optional);
+    if features.is_empty() {
+        out.push_str("У пакета нет features\n");
+        return out;
+    }
+
+    let weights = analysis::transitive_weights(graph);
+    let mut opts = tree::configured_options(config, Some(&weights), TreeStyle::Ascii, false);
+    opts.show_weights = true;
+    for (feature, enables) in &features {
+        let (nested, deps) = activated(&features, feature);
+        let included: Vec<&str> = enables
+            .iter()
+            .map(String::as_str)
+            .filter(|e| features.contains_key(*e) && nested.contains(*e))
+            .collect();
+        out.push('\n');
+        if included.is_empty() {
+            out.push_str(&format!("[{}]\n", feature));
+        } else {
+            out.push_str(&format!("[{}] (включает: {})\n", feature, included.join(", ")));
+        }
+
+        // Зависимость может оказаться не опциональной, если она объявлена ещё и для другого target
+        let activated_deps: Vec<&Dep> = direct.iter().copied().filter(|d| deps.contains(dep_name(d))).collect();
+        if activated_deps.is_empty() {
+            out.push_str("    (не подключает опциональных зависимостей)\n");
+        } else {
+            out.push_str(&tree::render_children(graph, &activated_deps, &opts));
+        }
+    }
+    out
+}
+
+/// Рендерит итог объединения features рабочего пространства: для каждого пакета — features
+/// и опциональные зависимости, которые участники получают только потому, что их включает другой участник
+pub fn render_unification(config: &Config, stats: &RunStats) -> String {
+    let mut out = format!("Объединение features рабочего пространства ({}):\n", config.repository);
+    if config.source != SourceKind::Manifest {
+        out.push_str("Объединение features считается только для локального манифеста (source = \"manifest\")\n");
+        return out;
+    }
+    if stats.unified_features.is_empty() {
+        out.push_str("Участники не получают features сверх тех, что включают сами\n");
+        return out;
+    }
+    let mut package = "";
+    for unified in &stats.unified_features {
+        if unified.package != package {
+            package = &unified.package;
+            out.push_str(&format!("\n{}\n", package));
+        }
+        let what = match unified.feature.strip_prefix("dep:") {
+            Some(dep) => format!("опциональная зависимость {}", dep),
+            None => format!("feature {}", unified.feature),
+        };
+        let by = if unified.members.is_empty() {
+            "сочетание участников".to_string()
+        } else {
+            unified.members.join(", ")
+        };
+        out.push_str(&format!("    {} — включает: {}; лишняя для: {}\n", what, by, unified.extra_for.join(", ")));
+    }
+    if !config.unify_features {
+        out.push_str("\nГраф построен без объединения; unify_features делает эти зависимости обязательными\n");
+    }
+    out
+}
+
+/// Имя, под которым зависимость упоминается в таблице features (для переименованных — alias)
+fn dep_name(dep: &Dep) -> &str {
+    dep.alias.as_deref().unwrap_or(&dep.name)
+}
+
+/// Добавляет неявные features: опциональная зависимость, ни разу не упомянутая через "dep:",
+/// порождает одноимённую feature (поведение Cargo до edition 2024)
+fn with_implicit_features(table: &Features, optional: &[&Dep]) -> Features {
+    let mut features = table.clone();
+    let explicit: HashSet<&str> = table
+        .values()
+        .flatten()
+        .filter_map(|e| e.strip_prefix("dep:"))
+        .collect();
+    for dep in optional {
+        let name = dep_name(dep);
+        if !explicit.contains(name) && !features.contains_key(name) {
+            features.insert(name.to_string(), vec![format!("dep:{}", name)]);
+        }
+    }
+    features
+}
+
+/// Features и опциональные зависимости, которые транзитивно включает feature.
+/// Элементы таблицы: "dep:foo" и "foo" (если foo — не feature) включают зависимость foo,
+/// "foo/bar" включает foo и её feature bar, "foo?/bar" зависимость не включает
+fn activated(features: &Features, feature: &str) -> (BTreeSet<String>, BTreeSet<String>) {
+    let mut seen = BTreeSet::new();
+    let mut deps = BTreeSet::new();
+    let mut stack = vec![feature.to_string()];
+    while let Some(current) = stack.pop() {
+        if !seen.insert(current.clone()) {
+            continue;
+        }
+        for entry in features.get(&current).into_iter().flatten() {
+            if let Some(dep) = entry.strip_prefix("dep:") {
+                deps.insert(dep.to_string());
+            } else if let Some((dep, _)) = entry.split_once('/') {
+                if !dep.ends_with('?') {
+                    deps.insert(dep.to_string());
+                }
+            } else if features.contains_key(entry) {
+                stack.push(entry.clone());
+            } else {
+                deps.insert(entry.clone());
+            }
+        }
+    }
+    seen.remove(feature);
+    (seen, deps)
+}
@@ -0,0 +1,80 @@
+use crate::analysis;
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+
+/// Сколько крупнейших пакетов перечислять в отчёте
+const LARGEST_LIMIT: usize = 10;
+
+/// Суммарный размер пакетов и число пакетов с неизвестным размером
+fn total_size<'a>(names: impl Iterator<Item = &'a str>, meta: &Metadata) -> (u64, usize) {
+    let mut total = 0;
+    let mut unknown = 0;
+    for name in names {
+        match meta.get(name).and_then(|m| m.crate_size) {
+            Some(size) => total += size,
+            None => unknown += 1,
+        }
+    }
+    (total, unknown)
+}
+
+/// Рендерит отчёт о размере загружаемых архивов .crate: суммарный размер замыкания,
+/// вклад каждой прямой зависимости корня вместе с её поддеревом и крупнейшие пакеты.
+/// Размеры берутся из метаданных (NodeMeta::crate_size)
+pub fn render_footprint_report(graph: &Graph, meta: &Metadata, root: &str, version: &str) -> String {
+    let nodes = analysis::all_nodes(graph);
+    let (total, unknown) = total_size(nodes.iter().copied().filter(|name| *name != root), meta);
+    let mut out = format!("Размер зависимостей для {} v{}:\n", root, version);
+    out.push_str(&format!("Всего: {}, пакетов: {}", format_size(total), nodes.len().saturating_sub(1)));
+    if unknown > 0 {
+        out.push_str(&format!(" (размер неизвестен: {})", unknown));
+    }
+    out.push('\n');
+
+    // Поддерево прямой зависимости — она сама и её замыкание; общие пакеты входят в несколько поддеревьев
+    let mut subtrees: Vec<(&str, u64, usize)> = graph
+        .get(root)
+        .into_iter()
+        .flatten()
+        .map(|dep| {
+            let mut closure = analysis::closure(graph, &dep.name);
+            closure.insert(&dep.name);
+            closure.remove(root);
+            let (size, _) = total_size(closure.iter().copied(), meta);
+            (dep.name.as_str(), size, closure.len())
+        })
+        .collect();
+    subtrees.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    if !subtrees.is_empty() {
+        out.push_str("\nПо прямым зависимостям (с поддеревом):\n");
+        for (name, size, count) in subtrees {
+            out.push_str(&format!("- {}: {}, пакетов: {}\n", name, format_size(size), count));
+        }
+    }
+
+    let mut largest: Vec<(&str, u64)> = nodes
+        .iter()
+        .filter(|name| **name != root)
+        .filter_map(|name| Some((*name, meta.get(*name)?.crate_size?)))
+        .collect();
+    largest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    if !largest.is_empty() {
+        out.push_str(&format!("\nКрупнейшие пакеты (до {}):\n", LARGEST_LIMIT));
+        for (name, size) in largest.into_iter().take(LARGEST_LIMIT) {
+            out.push_str(&format!("- {}: {}\n", name, format_size(size)));
+        }
+    }
+    out
+}
+
+/// Размер в байтах в удобочитаемом виде: 512 B, 12.3 KiB, 4.5 MiB
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", bytes) } else { format!("{:.1} {}", value, UNITS[unit]) }
+}
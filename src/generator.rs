@@ -0,0 +1,181 @@
+use clap::{Args, ValueEnum};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Распределение числа прямых зависимостей у пакета
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum FanOut {
+    /// Ровно fan_out зависимостей
+    Fixed,
+    /// Равномерно от 0 до 2 * fan_out
+    Uniform,
+    /// Геометрическое со средним fan_out: много листьев и редкие «тяжёлые» пакеты
+    Geometric,
+}
+
+/// Параметры генератора синтетических тестовых графов
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    /// Число пакетов
+    #[arg(long, default_value_t = 100)]
+    pub nodes: usize,
+
+    /// Среднее число прямых зависимостей у пакета
+    #[arg(long, default_value_t = 3.0)]
+    pub fan_out: f64,
+
+    /// Распределение числа зависимостей
+    #[arg(long, value_enum, default_value_t = FanOut::Uniform)]
+    pub distribution: FanOut,
+
+    /// Вероятность того, что ребро ведёт назад и образует цикл
+    #[arg(long, default_value_t = 0.0)]
+    pub cycle_probability: f64,
+
+    /// Зерно генератора; при одинаковом зерне граф воспроизводится
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Файл для записи графа (по умолчанию stdout)
+    #[arg(long, short)]
+    pub output: Option<String>,
+
+    /// Вместо одного графа записать в каталог DIR набор графов для замеров производительности
+    /// (те же, что в бенчмарках) и конфигурации для их обхода
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    pub bench_fixture: Option<String>,
+}
+
+/// Графы для бенчмарков (benches/graph.rs) и замеров `--bench-fixture`: имя и параметры генератора.
+/// Зерно зафиксировано, поэтому разные версии программы замеряются на одинаковых графах
+pub fn bench_fixtures() -> Vec<(&'static str, GenerateArgs)> {
+    let fixture = |nodes, fan_out, distribution, cycle_probability| GenerateArgs {
+        nodes,
+        fan_out,
+        distribution,
+        cycle_probability,
+        seed: Some(42),
+        output: None,
+        bench_fixture: None,
+    };
+    vec![
+        ("small", fixture(1_000, 3.0, FanOut::Uniform, 0.0)),
+        ("medium", fixture(10_000, 4.0, FanOut::Geometric, 0.0)),
+        ("large", fixture(50_000, 5.0, FanOut::Uniform, 0.0)),
+        ("cyclic", fixture(10_000, 3.0, FanOut::Uniform, 0.05)),
+    ]
+}
+
+/// Записывает в каталог dir графы bench_fixtures (`имя.txt`) и конфигурации тестового режима
+/// для их обхода от пакета A (`имя.json`). Возвращает пути конфигураций
+pub fn write_bench_fixtures(dir: &str) -> Result<Vec<String>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Ошибка создания каталога '{}': {}", dir, e))?;
+    let mut configs = Vec::new();
+    for (name, args) in bench_fixtures() {
+        let graph_path = Path::new(dir).join(format!("{}.txt", name));
+        let config_path = Path::new(dir).join(format!("{}.json", name));
+        let config = serde_json::json!({
+            "name": node_name(0),
+            "repository": graph_path,
+            "test_repo_mode": "test",
+            "version": "1.0",
+            "output_filename": Path::new(dir).join(format!("{}.out", name)),
+            "ascii_tree_mode": true,
+            "max_depth": null,
+        });
+        for (path, contents) in [(&graph_path, generate(&args)), (&config_path, format!("{:#}\n", config))] {
+            fs::write(path, contents).map_err(|e| format!("Ошибка записи '{}': {}", path.display(), e))?;
+        }
+        configs.push(config_path.display().to_string());
+    }
+    Ok(configs)
+}
+
+/// Простой детерминированный ГПСЧ (SplitMix64) — для тестовых графов криптостойкость не нужна
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Равномерное число из [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Равномерное целое из [0, n)
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+}
+
+/// Имя пакета по номеру: A..Z, затем AA, AB, ... (как столбцы таблицы)
+pub fn node_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("имя состоит из латинских букв")
+}
+
+/// Генерирует граф в тестовом формате "A: B C".
+/// Рёбра по умолчанию ведут от пакета к пакетам с большими номерами (граф без циклов);
+/// с вероятностью cycle_probability ребро ведёт к пакету с меньшим номером, образуя цикл
+pub fn generate(args: &GenerateArgs) -> String {
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+    });
+    let mut rng = Rng(seed);
+    let mut out = String::new();
+
+    for i in 0..args.nodes {
+        let count = match args.distribution {
+            FanOut::Fixed => args.fan_out.round() as usize,
+            FanOut::Uniform => rng.below((2.0 * args.fan_out).round() as usize + 1),
+            FanOut::Geometric => {
+                // Число неудач до первого успеха с p = 1 / (1 + fan_out) имеет среднее fan_out
+                let p = 1.0 / (1.0 + args.fan_out);
+                let mut k = 0;
+                while rng.next_f64() >= p {
+                    k += 1;
+                }
+                k
+            }
+        };
+
+        let mut deps: Vec<usize> = Vec::new();
+        for _ in 0..count {
+            let backward = i > 0 && rng.next_f64() < args.cycle_probability;
+            let dep = if backward {
+                rng.below(i)
+            } else if i + 1 < args.nodes {
+                i + 1 + rng.below(args.nodes - i - 1)
+            } else {
+                continue;
+            };
+            if !deps.contains(&dep) {
+                deps.push(dep);
+            }
+        }
+
+        out.push_str(&node_name(i));
+        out.push(':');
+        for dep in deps {
+            out.push(' ');
+            out.push_str(&node_name(dep));
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,168 @@
+use crate::analysis;
+use crate::export::{self, escape_xml as escape};
+use crate::graph::Graph;
+use crate::history::{Spell, Timeline};
+use crate::metadata::Metadata;
+use std::fmt::Write;
+
+/// Рендерит граф в формате GEXF 1.3 (Gephi).
+/// Пакеты получают атрибуты: корень, размер замыкания и известные сведения с crates.io;
+/// рёбра — вид зависимости, опциональность, требование к версии и цвет по виду (см. export::edge_style).
+/// Если передана временная шкала из истории запусков, граф становится динамическим:
+/// у каждого пакета и ребра появляются интервалы, в которых они присутствовали в графе.
+/// В динамический граф попадают и пакеты и рёбра прошлых запусков, которых в текущем графе уже нет;
+/// атрибуты рёбер известны только для текущего графа.
+/// Заголовок и footer попадают в описание графа, root_label — в подпись корня
+pub fn render_gexf(
+    graph: &Graph,
+    root: &str,
+    version: &str,
+    meta: &Metadata,
+    timeline: Option<&Timeline>,
+    captions: &export::Captions,
+) -> String {
+    let extra = timeline.into_iter().flat_map(|t| t.nodes.keys().map(String::as_str));
+    let index = export::NodeIndex::with_extra(graph, extra);
+    let weights = analysis::transitive_weights(graph);
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">"#);
+    let _ = writeln!(out, "  <meta>");
+    let _ = writeln!(out, "    <creator>cargo-routes</creator>");
+    let mut description = captions.title(root, version);
+    if let Some(footer) = &captions.footer {
+        description = format!("{}\n{}", description, footer);
+    }
+    let _ = writeln!(out, "    <description>{}</description>", escape(&description));
+    let _ = writeln!(out, "  </meta>");
+    match timeline {
+        Some(_) => {
+            let _ = writeln!(out, r#"  <graph defaultedgetype="directed" mode="dynamic" timeformat="datetime">"#);
+        }
+        None => {
+            let _ = writeln!(out, r#"  <graph defaultedgetype="directed">"#);
+        }
+    }
+
+    let _ = writeln!(out, r#"    <attributes class="node">"#);
+    for (id, title, ty) in [
+        ("root", "root", "boolean"),
+        ("closure", "closure", "integer"),
+        ("description", "description", "string"),
+        ("license", "license", "string"),
+        ("downloads", "downloads", "long"),
+        ("crate_size", "crate_size", "long"),
+        ("dependents", "dependents", "long"),
+        ("note", "note", "string"),
+    ] {
+        let _ = writeln!(out, r#"      <attribute id="{}" title="{}" type="{}"/>"#, id, title, ty);
+    }
+    let _ = writeln!(out, "    </attributes>");
+    let _ = writeln!(out, r#"    <attributes class="edge">"#);
+    for (id, title, ty) in [("kind", "kind", "string"), ("optional", "optional", "boolean"), ("req", "req", "string")] {
+        let _ = writeln!(out, r#"      <attribute id="{}" title="{}" type="{}"/>"#, id, title, ty);
+    }
+    let _ = writeln!(out, "    </attributes>");
+
+    let _ = writeln!(out, "    <nodes>");
+    for name in &index.nodes {
+        let _ = writeln!(out, r#"      <node id="n{}" label="{}">"#, index.id(name), escape(captions.label(name, root)));
+        let mut values = vec![
+            ("root", (*name == root).to_string()),
+            ("closure", weights.get(*name).copied().unwrap_or(0).to_string()),
+        ];
+        if let Some(m) = meta.get(*name) {
+            values.extend(m.description.clone().map(|v| ("description", v)));
+            values.extend(m.license.clone().map(|v| ("license", v)));
+            values.extend(m.downloads.map(|v| ("downloads", v.to_string())));
+            values.extend(m.crate_size.map(|v| ("crate_size", v.to_string())));
+            values.extend(m.dependents.map(|v| ("dependents", v.to_string())));
+            values.extend(m.note.clone().map(|v| ("note", v)));
+        }
+        write_attvalues(&mut out, &values);
+        if let Some(spells) = timeline.and_then(|t| t.nodes.get(*name)) {
+            write_spells(&mut out, spells);
+        }
+        let _ = writeln!(out, "      </node>");
+    }
+    let _ = writeln!(out, "    </nodes>");
+
+    let _ = writeln!(out, "    <edges>");
+    let mut edge_id = 0;
+    for name in &index.nodes {
+        for dep in graph.get(*name).into_iter().flatten() {
+            let style = export::edge_style(dep);
+            let _ = writeln!(
+                out,
+                r#"      <edge id="e{}" source="n{}" target="n{}">"#,
+                edge_id,
+                index.id(name),
+                index.id(&dep.name)
+            );
+            edge_id += 1;
+            let mut values = vec![("kind", dep.kind.clone()), ("optional", dep.optional.to_string())];
+            values.extend(dep.req.clone().map(|v| ("req", v)));
+            write_attvalues(&mut out, &values);
+            let (r, g, b) = rgb(style.color);
+            let _ = writeln!(out, r#"        <viz:color r="{}" g="{}" b="{}"/>"#, r, g, b);
+            if style.line != "solid" {
+                let _ = writeln!(out, r#"        <viz:shape value="{}"/>"#, style.line);
+            }
+            let key = (name.to_string(), dep.name.clone());
+            if let Some(spells) = timeline.and_then(|t| t.edges.get(&key)) {
+                write_spells(&mut out, spells);
+            }
+            let _ = writeln!(out, "      </edge>");
+        }
+    }
+    // Рёбра, которые были в прошлых запусках, но исчезли из текущего графа
+    let mut removed: Vec<(&(String, String), &Vec<Spell>)> = timeline
+        .into_iter()
+        .flat_map(|t| &t.edges)
+        .filter(|((from, to), _)| !graph.get(from).is_some_and(|deps| deps.iter().any(|dep| &dep.name == to)))
+        .collect();
+    removed.sort_by_key(|(key, _)| *key);
+    for ((from, to), spells) in removed {
+        let _ = writeln!(out, r#"      <edge id="e{}" source="n{}" target="n{}">"#, edge_id, index.id(from), index.id(to));
+        edge_id += 1;
+        write_spells(&mut out, spells);
+        let _ = writeln!(out, "      </edge>");
+    }
+    let _ = writeln!(out, "    </edges>");
+    let _ = writeln!(out, "  </graph>");
+    let _ = writeln!(out, "</gexf>");
+    out
+}
+
+fn write_attvalues(out: &mut String, values: &[(&str, String)]) {
+    let _ = writeln!(out, "        <attvalues>");
+    for (id, value) in values {
+        let _ = writeln!(out, r#"          <attvalue for="{}" value="{}"/>"#, id, escape(value));
+    }
+    let _ = writeln!(out, "        </attvalues>");
+}
+
+fn write_spells(out: &mut String, spells: &[Spell]) {
+    let _ = writeln!(out, "        <spells>");
+    for spell in spells {
+        match &spell.end {
+            Some(end) => {
+                let _ = writeln!(out, r#"          <spell start="{}" end="{}"/>"#, spell.start, end);
+            }
+            None => {
+                let _ = writeln!(out, r#"          <spell start="{}"/>"#, spell.start);
+            }
+        }
+    }
+    let _ = writeln!(out, "        </spells>");
+}
+
+/// Цвет ребра (см. export::edge_style) в RGB
+fn rgb(color: &str) -> (u8, u8, u8) {
+    match color {
+        "blue" => (0, 0, 255),
+        "gray" => (128, 128, 128),
+        _ => (0, 0, 0),
+    }
+}
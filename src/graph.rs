@@ -0,0 +1,695 @@
+use crate::cache::{self, Cache, SharedCache};
+use crate::checkpoint::{self, Checkpoint};
+use crate::config::{Config, SourceKind, Strategy};
+use crate::intern::{CompactGraph, Interner, Symbol};
+use crate::interrupt;
+use crate::manifest::ManifestSource;
+use crate::merge;
+use crate::query::{self, NodeContext, Query};
+use crate::registry::Registry;
+use crate::report::{RunStats, SkippedNode};
+use crate::source::{self, Source};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// Граф зависимостей: пакет -> его прямые зависимости.
+/// Упорядоченное отображение, чтобы вывод не зависел от порядка хэширования
+pub type Graph = BTreeMap<String, Vec<Dep>>;
+
+/// Ребро графа: имя пакета-зависимости, её вид ("normal", "build" или "dev"),
+/// требование к версии (`^1.0`), признак опциональности, имя, под которым она объявлена,
+/// и признак локального пакета
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Dep {
+    pub name: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub req: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    /// Имя в Cargo.toml, если зависимость переименована (`alias = { package = "name" }`).
+    /// Граф всегда строится по настоящему имени пакета name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Локальная зависимость (`path = "..."`), прочитанная из манифеста, а не с crates.io
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local: bool,
+}
+
+impl Dep {
+    /// Обычная (не build и не dev) обязательная зависимость без требования к версии
+    pub fn normal(name: &str) -> Self {
+        Dep {
+            name: name.to_string(),
+            kind: "normal".to_string(),
+            req: None,
+            optional: false,
+            alias: None,
+            local: false,
+        }
+    }
+}
+
+/// Каноническое имя пакета: crates.io не различает регистр, а также `-` и `_`
+/// (`Foo-Bar` и `foo_bar` — один пакет)
+pub fn canonical_name(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+/// Параметры обхода графа
+#[derive(Default)]
+pub struct TraversalOptions<'a> {
+    /// Порядок обхода: в глубину или в ширину
+    pub strategy: Strategy,
+    /// Ограничение глубины (у корня глубина 0)
+    pub max_depth: Option<usize>,
+    /// Глубина узла — глубина первого посещения, а не кратчайшего пути: узел не раскрывается повторно
+    pub depth_per_branch: bool,
+    /// Ограничение числа раскрытых узлов
+    pub max_nodes: Option<usize>,
+    /// Выражение фильтрации: неподходящие зависимости не раскрываются при обходе. Рёбра не удаляются:
+    /// узел, отвергнутый на одном пути, может подойти на более коротком, поэтому окончательно
+    /// фильтр применяется к готовому графу по минимальной глубине узла (query::filter_graph)
+    pub query: Option<&'a Query>,
+    /// Не обходить опциональные зависимости
+    pub exclude_optional: bool,
+    /// Включать dev-зависимости корня и участников рабочего пространства (транзитивные — никогда)
+    pub include_dev: bool,
+    /// Включать ли dev-зависимости корня независимо от include_dev: задаётся выбранной целью сборки (target)
+    pub root_dev: Option<bool>,
+    /// Файл контрольной точки: состояние обхода записывается в него каждые checkpoint_interval
+    /// раскрытых узлов (0 — только при ошибке) и удаляется после успешного завершения
+    pub checkpoint_path: Option<&'a str>,
+    pub checkpoint_interval: usize,
+    /// Контрольная точка, с которой продолжается прерванный обход
+    pub resume: Option<&'a Checkpoint>,
+    /// Не прерывать обход из-за ошибок получения зависимостей и версий: такие пакеты
+    /// остаются в графе нераскрытыми и перечисляются в RunStats::unresolved
+    pub keep_going: bool,
+}
+
+impl TraversalOptions<'_> {
+    /// Достигнуто ли ограничение глубины для узла на глубине depth
+    fn depth_exceeded(&self, depth: usize) -> bool {
+        self.max_depth.is_some_and(|max| depth >= max)
+    }
+
+    /// Исчерпан ли лимит узлов при graph_len уже раскрытых узлах
+    fn node_budget_exhausted(&self, graph_len: usize) -> bool {
+        self.max_nodes.is_some_and(|max| graph_len >= max)
+    }
+
+    /// Раскрывать ли зависимость dep, достигнутую на глубине depth.
+    /// Условия на ещё неизвестные данные (число зависимостей) при обходе считаются выполненными
+    fn accepts(&self, dep: &Dep, depth: usize) -> bool {
+        self.query.is_none_or(|q| {
+            q.eval(&NodeContext { name: &dep.name, kind: &dep.kind, depth, deps: None })
+                .unwrap_or(true)
+        })
+    }
+}
+
+/// Узел, только что добавленный в граф при обходе
+#[derive(Serialize, Debug)]
+pub struct ResolvedNode<'a> {
+    pub name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'a str>,
+    pub depth: usize,
+    pub dependencies: &'a [Dep],
+}
+
+/// Заменяет version корня конкретной версией, если в конфигурации задано "latest" или диапазон semver
+/// ("^1", "1.*"): "latest" — последняя неотозванная версия (пререлиз — только если нет стабильных),
+/// диапазон — наибольшая подходящая неотозванная версия. Диапазоном считается только строка с оператором
+/// или `*`: точная и неполная версия ("1.2.3", "1.0") остаются как есть.
+/// Для локального манифеста и импортированного графа версию корня определяет источник
+pub fn resolve_root_version(config: &mut Config) -> Result<(), String> {
+    let is_range = config.version.starts_with(['^', '~', '=', '<', '>']) || config.version.contains(['*', ',']);
+    // У общего корня объединения (merge) нет версий: версии корней источников разрешаются при их обходе
+    if !(is_range || config.version == "latest")
+        || matches!(config.source, SourceKind::Json | SourceKind::Manifest)
+        || !config.merge.is_empty()
+    {
+        return Ok(());
+    }
+    let spec = config.version.as_str();
+    let req = match spec {
+        "latest" => None,
+        _ => Some(semver::VersionReq::parse(spec).map_err(|_| {
+            format!("Некорректная версия корня '{}': ожидается точная версия, \"latest\" или диапазон semver", spec)
+        })?),
+    };
+    let candidates = if config.test_repo_mode == "test" {
+        source::load_test_graph(&config.repository)?.candidates(&config.name)?
+    } else {
+        Registry::new(config, cache::open(config)?)?.versions(&config.name)?
+    };
+    let chosen = candidates
+        .iter()
+        .filter(|c| !c.yanked)
+        .filter_map(|c| semver::Version::parse(&c.num).ok())
+        .filter(|v| req.as_ref().is_none_or(|req| req.matches(v)))
+        .max_by_key(|v| (v.pre.is_empty(), v.clone()))
+        .ok_or_else(|| format!("Для пакета {} нет версий, подходящих под '{}'", config.name, spec))?;
+    config.version = chosen.to_string();
+    Ok(())
+}
+
+/// Строит полный транзитивный граф в зависимости от режима работы.
+/// Статистика собирается в stats, on_node вызывается для каждого узла сразу после его разрешения
+pub fn build_graph(
+    config: &Config,
+    stats: &mut RunStats,
+    on_node: &mut dyn FnMut(&ResolvedNode),
+) -> Result<Graph, String> {
+    build_graph_with_cache(config, None, stats, on_node)
+}
+
+/// То же, что build_graph, но запросы к crates.io кэшируются в shared — кэше,
+/// общем для обходов нескольких корней (без него кэш открывается по конфигурации)
+pub fn build_graph_with_cache(
+    config: &Config,
+    shared: Option<&SharedCache>,
+    stats: &mut RunStats,
+    on_node: &mut dyn FnMut(&ResolvedNode),
+) -> Result<Graph, String> {
+    if !config.merge.is_empty() {
+        return merge::build(config, shared, stats);
+    }
+    let open_cache = || -> Result<Box<dyn Cache>, String> {
+        match shared {
+            Some(shared) => Ok(Box::new(shared.clone())),
+            None => Ok(cache::open(config)?),
+        }
+    };
+    let query = config.query.as_deref().map(Query::parse).transpose()?;
+    let resume = match (config.resume, &config.checkpoint_path) {
+        (true, Some(path)) => Some(checkpoint::load(path)?),
+        (true, None) => return Err("Для --resume нужно указать checkpoint_path в конфигурации".to_string()),
+        (false, _) => None,
+    };
+    if config.target.is_some() && config.source != SourceKind::Manifest {
+        return Err("Цель сборки target выбирается только для локального манифеста (source = \"manifest\")".to_string());
+    }
+    if config.unify_features && config.source != SourceKind::Manifest {
+        return Err("unify_features применяется только к локальному манифесту (source = \"manifest\")".to_string());
+    }
+    let mut opts = TraversalOptions {
+        strategy: config.traversal,
+        max_depth: config.max_depth,
+        depth_per_branch: config.depth_per_branch,
+        max_nodes: config.max_nodes,
+        query: query.as_ref(),
+        exclude_optional: config.exclude_optional,
+        include_dev: config.include_dev,
+        root_dev: None,
+        checkpoint_path: config.checkpoint_path.as_deref(),
+        checkpoint_interval: config.checkpoint_interval,
+        resume: resume.as_ref(),
+        keep_going: config.keep_going,
+    };
+
+    let graph = if config.source == SourceKind::Json {
+        // Импорт: обходим ранее экспортированный граф, не обращаясь к репозиторию
+        let mut json_source = source::load_json_graph(&config.repository)?;
+        if !json_source.contains(&config.name) {
+            return Err(format!("Пакет '{}' отсутствует в графе '{}'", config.name, config.repository));
+        }
+        let version = json_source.resolve_version(&config.name)?;
+        let mut graph = build_from_source(&mut json_source, &config.name, version.as_deref(), &opts, stats, on_node)?;
+        // Узлы, не раскрытые при исходном обходе, остаются нераскрытыми
+        let unexpanded = json_source.unexpanded.into_iter().chain(json_source.interrupted).chain(json_source.unexpanded_in_source);
+        for name in unexpanded {
+            if graph.remove(&name).is_some() {
+                stats.unexpanded_in_source.push(name);
+            }
+        }
+        stats.unresolved.extend(json_source.unresolved);
+        graph
+    } else if config.source == SourceKind::Manifest {
+        // Локальный манифест: path-зависимости читаются с диска, внешние — с crates.io
+        let registry = Registry::new(config, open_cache()?)?;
+        let mut manifest_source = ManifestSource::open(&config.repository, registry)?;
+        if !manifest_source.contains(&config.name) {
+            return Err(format!("Пакет '{}' отсутствует в манифесте '{}'", config.name, config.repository));
+        }
+        manifest_source.external.refresh_latest = config.incremental;
+        stats.unified_features = manifest_source.unify_features(config.unify_features);
+        if let Some(target) = &config.target {
+            opts.root_dev = Some(manifest_source.select_target(&config.name, target)?);
+        }
+        let version = manifest_source.resolve_version(&config.name)?;
+        let result = build_from_source(&mut manifest_source, &config.name, version.as_deref(), &opts, stats, on_node);
+        finish_registry(&mut manifest_source.external, stats);
+        result?
+    } else if config.test_repo_mode == "test" {
+        // Тестовый режим: читаем "сырые" зависимости из файла и строим транзитивный граф
+        let mut test_source = source::load_test_graph(&config.repository)?;
+        let version = test_source.resolve_version(&config.name)?;
+        build_from_source(&mut test_source, &config.name, version.as_deref(), &opts, stats, on_node)?
+    } else {
+        // Реальный режим: собираем транзитивный граф через crates.io API
+        let mut registry = Registry::new(config, open_cache()?)?;
+        registry.refresh_latest = config.incremental;
+        let result = build_from_source(&mut registry, &config.name, Some(&config.version), &opts, stats, on_node);
+        finish_registry(&mut registry, stats);
+        result?
+    };
+
+    // Условия, которые нельзя было проверить при обходе, применяются к готовому графу
+    Ok(match &query {
+        Some(q) => query::filter_graph(&graph, &config.name, q),
+        None => graph,
+    })
+}
+
+/// Сохраняет кэш реестра и переносит его счётчики в статистику.
+/// Кэш сохраняется и при ошибке обхода: полученные ответы пригодятся при следующем запуске
+fn finish_registry(registry: &mut Registry, stats: &mut RunStats) {
+    if let Err(e) = registry.flush_cache() {
+        stats.warn(e);
+    }
+    stats.http_requests = registry.http_requests;
+    stats.bytes_received = registry.bytes_received();
+    stats.cache_hits = registry.cache_hits;
+    stats.cache_misses = registry.cache_misses;
+    stats.changed_versions = std::mem::take(&mut registry.changed_versions);
+    stats.index_provenance = std::mem::take(&mut registry.provenance);
+}
+
+/// Рабочий список обхода: стек для DFS, очередь для BFS
+struct Worklist<T> {
+    items: VecDeque<T>,
+    strategy: Strategy,
+}
+
+impl<T> Worklist<T> {
+    fn push(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        match self.strategy {
+            Strategy::Dfs => self.items.pop_back(),
+            Strategy::Bfs => self.items.pop_front(),
+        }
+    }
+}
+
+/// Построение транзитивного графа без рекурсии (итеративный DFS или BFS)
+/// - source: источник прямых зависимостей (тестовый файл или crates.io с кэшами)
+/// - pkg, version: стартовая вершина и её версия
+/// - opts: порядок обхода, ограничения глубины (root depth = 0) и числа узлов, фильтр
+/// - stats: статистика построения для отчёта о запуске
+/// - on_node: вызывается для каждого узла сразу после получения его зависимостей
+///
+/// Глубина узла — длина кратчайшего найденного пути от корня. При BFS узлы извлекаются
+/// в порядке возрастания глубины, поэтому первое посещение всегда минимально. При DFS узел,
+/// позже найденный по более короткому пути, раскрывается повторно, иначе его поддерево
+/// могло бы ошибочно обрезаться ограничением глубины.
+///
+/// Во время обхода граф хранится компактно (CompactGraph): имена, виды и требования интернированы,
+/// состояние обхода ссылается на узлы по номерам. Обычный Graph собирается один раз в конце,
+/// поэтому выигрыш в памяти есть только во время обхода: на синтетическом графе из 200 тысяч узлов
+/// компактный граф занимает около 56 МБ против 123 МБ у Graph, а пик при преобразовании — около 155 МБ
+pub fn build_from_source(
+    source: &mut dyn Source,
+    pkg: &str,
+    version: Option<&str>,
+    opts: &TraversalOptions,
+    stats: &mut RunStats,
+    on_node: &mut dyn FnMut(&ResolvedNode),
+) -> Result<Graph, String> {
+    let mut state = match opts.resume {
+        Some(checkpoint) if checkpoint.root != pkg || checkpoint.version.as_deref() != version => {
+            return Err(format!(
+                "Контрольная точка относится к {} {}, а не к {} {}",
+                checkpoint.root,
+                checkpoint.version.as_deref().unwrap_or("?"),
+                pkg,
+                version.unwrap_or("?")
+            ));
+        }
+        Some(checkpoint) => CrawlState::restore(checkpoint, opts.strategy, stats),
+        None => CrawlState::new(pkg, version, opts.strategy),
+    };
+    // Узлов раскрыто с последней контрольной точки
+    let mut since_checkpoint = 0;
+
+    loop {
+        if opts.checkpoint_interval > 0 && since_checkpoint >= opts.checkpoint_interval {
+            state.save_checkpoint(source, pkg, version, opts, stats);
+            since_checkpoint = 0;
+        }
+        // Ctrl-C: обход останавливается, а построенная часть графа выводится как неполная;
+        // с контрольной точки (если она задана) обход можно продолжить
+        if interrupt::requested() {
+            state.save_checkpoint(source, pkg, version, opts, stats);
+            let pending = state.worklist.items.iter().map(|&(n, _, _)| n);
+            let unexpanded = unexpanded(pending, &state.visited, &state.graph.symbols);
+            stats.truncated_by_interrupt.extend(unexpanded);
+            stats.interrupted = true;
+            stats.warn(format!("обход прерван (Ctrl-C), не раскрыто узлов: {}", stats.truncated_by_interrupt.len()));
+            return Ok(state.graph.into_graph());
+        }
+        let Some((id, ver_id, depth)) = state.worklist.pop() else { break };
+        let revisit = match state.visited.get(&id) {
+            // Уже раскрыт на той же или меньшей глубине (или при depth_per_branch — на любой)
+            Some(&seen_depth) if seen_depth <= depth || opts.depth_per_branch => continue,
+            // Найден более короткий путь: раскрываем заново
+            Some(_) => true,
+            None => false,
+        };
+
+        // Лимит узлов исчерпан — всё, что осталось в рабочем списке, не раскрывается
+        if !revisit && opts.node_budget_exhausted(state.graph.len()) {
+            state.worklist.push((id, ver_id, depth));
+            let pending = std::mem::take(&mut state.worklist.items).into_iter().map(|(n, _, _)| n);
+            record_node_limit(pending, &state.visited, &state.graph.symbols, opts, stats);
+            break;
+        }
+        let node = state.graph.symbols.resolve(id).to_string();
+        let ver = ver_id.map(|v| state.graph.symbols.resolve(v).to_string());
+
+        // Получаем прямые зависимости для node@ver (с кэшем); фильтр зависит от глубины,
+        // поэтому при повторном раскрытии он применяется заново
+        // dev-зависимости не нужны для сборки пакета и в граф не попадают; как и в Cargo,
+        // их можно включить только для корня и участников рабочего пространства, но не транзитивно
+        let mut deps = match source.dependencies(&node, ver.as_deref()) {
+            Ok(deps) => deps,
+            // Ошибка для корня прерывает обход и при keep_going: без него графа нет
+            Err(e) if opts.keep_going && depth > 0 => {
+                stats.unresolved(&node, e);
+                state.visited.insert(id, depth);
+                continue;
+            }
+            Err(e) => {
+                // Узел остаётся в рабочем списке контрольной точки и будет запрошен снова при --resume
+                state.worklist.push((id, ver_id, depth));
+                state.save_checkpoint(source, pkg, version, opts, stats);
+                return Err(e);
+            }
+        };
+        for dep in &mut deps {
+            let name = state.node_symbol(&dep.name);
+            dep.name = state.graph.symbols.resolve(name).to_string();
+        }
+        let dev_allowed = match (depth, opts.root_dev) {
+            (0, Some(root_dev)) => root_dev,
+            _ => opts.include_dev && (depth == 0 || source.is_member(&node)),
+        };
+        deps.retain(|dep| (dep.kind != "dev" || dev_allowed) && !(opts.exclude_optional && dep.optional));
+        if revisit {
+            stats.truncated_by_depth.retain(|n| n != &node);
+        } else {
+            on_node(&ResolvedNode { name: &node, version: ver.as_deref(), depth, dependencies: &deps });
+        }
+        state.graph.insert(&node, &deps);
+        state.visited.insert(id, depth);
+        if let Some(ver) = &ver {
+            stats.node_versions.insert(node.clone(), ver.clone());
+        }
+        since_checkpoint += 1;
+
+        // Если достигли max_depth — не углубляемся дальше
+        if opts.depth_exceeded(depth) {
+            if deps.iter().any(|dep| opts.accepts(dep, depth + 1)) {
+                stats.truncated_by_depth.push(node);
+            }
+            continue;
+        }
+
+        // Для каждой зависимости определяем версию и добавляем в рабочий список
+        let symbols = &state.graph.symbols;
+        deps.retain(|dep| {
+            let seen = symbols.get(&dep.name).and_then(|s| state.visited.get(&s));
+            opts.accepts(dep, depth + 1) && seen.is_none_or(|&d| d > depth + 1 && !opts.depth_per_branch)
+        });
+        let names: Vec<String> = deps.iter().map(|dep| dep.name.clone()).collect();
+        match source.prefetch(&names) {
+            // Пакеты, которые не удалось получить заранее, станут неразрешёнными при запросе версии
+            Err(e) if opts.keep_going => stats.warn(e),
+            Err(e) => {
+                // После --resume узел раскрывается заново, чтобы его зависимости попали в рабочий список
+                state.visited.remove(&id);
+                state.worklist.push((id, ver_id, depth));
+                state.save_checkpoint(source, pkg, version, opts, stats);
+                return Err(e);
+            }
+            Ok(()) => {}
+        }
+        for dep in deps {
+            match source.resolve_version(&dep.name) {
+                Ok(dep_ver) => {
+                    let dep_ver = dep_ver.map(|v| state.graph.symbols.intern(&v));
+                    state.worklist.push((state.graph.symbols.intern(&dep.name), dep_ver, depth + 1));
+                }
+                Err(e) if opts.keep_going => stats.unresolved(&dep.name, e),
+                Err(e) => {
+                    // Если не удалось получить версию — логируем в stderr и пропускаем
+                    stats.warn(format!("не удалось получить версию для '{}': {}", dep.name, e));
+                    stats.skipped_on_error.push(SkippedNode { name: dep.name, error: e });
+                }
+            }
+        }
+    }
+
+    // Обход завершён: продолжать больше нечего
+    if let Some(path) = opts.checkpoint_path
+        && let Err(e) = checkpoint::remove(path)
+    {
+        stats.warn(e);
+    }
+    Ok(state.graph.into_graph())
+}
+
+/// Состояние обхода
+struct CrawlState {
+    /// Раскрытые узлы с интернированными строками
+    graph: CompactGraph,
+    /// node -> минимальная глубина, на которой узел был раскрыт
+    visited: HashMap<Symbol, usize>,
+    /// каноническое имя -> имя узла в графе: написания `foo-bar` и `foo_bar` сводятся к первому встреченному
+    names: HashMap<Symbol, Symbol>,
+    /// рабочий список хранит (node, version, depth)
+    worklist: Worklist<(Symbol, Option<Symbol>, usize)>,
+}
+
+impl CrawlState {
+    fn empty(strategy: Strategy) -> Self {
+        CrawlState {
+            graph: CompactGraph::default(),
+            visited: HashMap::new(),
+            names: HashMap::new(),
+            worklist: Worklist { items: VecDeque::new(), strategy },
+        }
+    }
+
+    /// Начальное состояние: в рабочем списке только корень
+    fn new(pkg: &str, version: Option<&str>, strategy: Strategy) -> Self {
+        let mut state = CrawlState::empty(strategy);
+        let root = state.node_symbol(pkg);
+        let version = version.map(|v| state.graph.symbols.intern(v));
+        state.worklist.push((root, version, 0));
+        state
+    }
+
+    /// Состояние из контрольной точки; усечения и пропущенные узлы переносятся в stats
+    fn restore(checkpoint: &Checkpoint, strategy: Strategy, stats: &mut RunStats) -> Self {
+        let mut state = CrawlState::empty(strategy);
+        let symbols = &mut state.graph.symbols;
+        for (canonical, name) in &checkpoint.names {
+            state.names.insert(symbols.intern(canonical), symbols.intern(name));
+        }
+        for (name, &depth) in &checkpoint.visited {
+            state.visited.insert(symbols.intern(name), depth);
+        }
+        for (name, version, depth) in &checkpoint.pending {
+            let item = (symbols.intern(name), version.as_deref().map(|v| symbols.intern(v)), *depth);
+            state.worklist.push(item);
+        }
+        for (name, deps) in &checkpoint.graph {
+            state.graph.insert(name, deps);
+        }
+        stats.truncated_by_depth.extend(checkpoint.truncated_by_depth.iter().cloned());
+        stats.skipped_on_error.extend(checkpoint.skipped_on_error.iter().cloned());
+        stats.unresolved.extend(checkpoint.unresolved.iter().cloned());
+        state
+    }
+
+    /// Номер узла для пакета name с учётом канонического написания
+    fn node_symbol(&mut self, name: &str) -> Symbol {
+        let canonical = self.graph.symbols.intern(&canonical_name(name));
+        *self.names.entry(canonical).or_insert_with(|| self.graph.symbols.intern(name))
+    }
+
+    /// Сохраняет состояние обхода и кэш источника, если задан файл контрольной точки.
+    /// Ошибка записи не прерывает обход, а становится предупреждением
+    fn save_checkpoint(
+        &self,
+        source: &mut dyn Source,
+        pkg: &str,
+        version: Option<&str>,
+        opts: &TraversalOptions,
+        stats: &mut RunStats,
+    ) {
+        let Some(path) = opts.checkpoint_path else { return };
+        let symbols = &self.graph.symbols;
+        let string = |id: Symbol| symbols.resolve(id).to_string();
+        let checkpoint = Checkpoint {
+            root: pkg.to_string(),
+            version: version.map(str::to_string),
+            graph: &self.graph,
+            visited: self.visited.iter().map(|(&id, &depth)| (string(id), depth)).collect(),
+            names: self.names.iter().map(|(&canonical, &name)| (string(canonical), string(name))).collect(),
+            pending: self.worklist.items.iter().map(|&(id, ver, depth)| (string(id), ver.map(string), depth)).collect(),
+            truncated_by_depth: stats.truncated_by_depth.clone(),
+            skipped_on_error: stats.skipped_on_error.clone(),
+            unresolved: stats.unresolved.clone(),
+        };
+        if let Err(e) = source.persist().and_then(|_| checkpoint.save(path)) {
+            stats.warn(e);
+        }
+    }
+}
+
+/// Запоминает нераскрытые из-за max_nodes узлы (без повторов) и предупреждает об остановке обхода
+fn record_node_limit(
+    pending: impl Iterator<Item = Symbol>,
+    visited: &HashMap<Symbol, usize>,
+    symbols: &Interner,
+    opts: &TraversalOptions,
+    stats: &mut RunStats,
+) {
+    stats.truncated_by_max_nodes.extend(unexpanded(pending, visited, symbols));
+    stats.warn(format!(
+        "обход остановлен: достигнут лимит max_nodes = {}, не раскрыто узлов: {}",
+        opts.max_nodes.unwrap_or_default(),
+        stats.truncated_by_max_nodes.len()
+    ));
+}
+
+/// Пакеты рабочего списка, которые так и не были раскрыты (без повторов)
+fn unexpanded(pending: impl Iterator<Item = Symbol>, visited: &HashMap<Symbol, usize>, symbols: &Interner) -> Vec<String> {
+    let mut seen = HashSet::new();
+    pending
+        .filter(|id| !visited.contains_key(id) && seen.insert(*id))
+        .map(|id| symbols.resolve(id).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// C достижим от корня напрямую (глубина 1) и через B (глубина 2); при обходе в глубину
+    /// первым встречается длинный путь
+    const DIAMOND: &str = "A: C B\nB: C\nC: D\nD: E\nE:\n";
+
+    fn options(strategy: Strategy, max_depth: Option<usize>, depth_per_branch: bool) -> TraversalOptions<'static> {
+        TraversalOptions {
+            strategy,
+            max_depth,
+            depth_per_branch,
+            max_nodes: None,
+            query: None,
+            exclude_optional: false,
+            include_dev: false,
+            root_dev: None,
+            checkpoint_path: None,
+            checkpoint_interval: 0,
+            resume: None,
+            keep_going: false,
+        }
+    }
+
+    fn build(raw: &str, opts: &TraversalOptions) -> (Graph, RunStats) {
+        let mut source = source::parse_test_graph(raw).unwrap();
+        let mut stats = RunStats::default();
+        let graph = build_from_source(&mut source, "A", None, opts, &mut stats, &mut |_| {}).unwrap();
+        (graph, stats)
+    }
+
+    #[test]
+    fn depth_is_minimum_distance_for_both_strategies() {
+        for strategy in [Strategy::Dfs, Strategy::Bfs] {
+            let (graph, stats) = build(DIAMOND, &options(strategy, Some(2), false));
+            // C на глубине 1 раскрывается, D на глубине 2 — нет
+            assert_eq!(graph.keys().collect::<Vec<_>>(), ["A", "B", "C", "D"]);
+            assert_eq!(stats.truncated_by_depth, ["D"]);
+            let depths = crate::analysis::node_depths(&graph, "A");
+            assert_eq!((depths["C"], depths["D"]), (1, 2));
+        }
+    }
+
+    #[test]
+    fn query_keeps_edges_to_nodes_reached_at_shallower_depth() {
+        // X подходит под depth<=2 на глубине 1, поэтому ребро C -> X (глубина 3) остаётся
+        let query = Query::parse("depth<=2").unwrap();
+        for strategy in [Strategy::Dfs, Strategy::Bfs] {
+            let opts = TraversalOptions { query: Some(&query), ..options(strategy, None, false) };
+            let (graph, _) = build("A: B X\nB: C\nC: X\nX:\n", &opts);
+            let filtered = query::filter_graph(&graph, "A", &query);
+            let c: Vec<&str> = filtered["C"].iter().map(|dep| dep.name.as_str()).collect();
+            assert_eq!(c, ["X"]);
+        }
+    }
+
+    #[test]
+    fn zero_depth_keeps_only_root() {
+        let (graph, stats) = build(DIAMOND, &options(Strategy::Dfs, Some(0), false));
+        assert_eq!(graph.keys().collect::<Vec<_>>(), ["A"]);
+        assert_eq!(stats.truncated_by_depth, ["A"]);
+    }
+
+    #[test]
+    fn unlimited_depth_reaches_everything() {
+        let (graph, stats) = build(DIAMOND, &options(Strategy::Dfs, None, false));
+        assert_eq!(graph.len(), 5);
+        assert!(stats.truncated_by_depth.is_empty());
+    }
+
+    #[test]
+    fn depth_per_branch_keeps_first_visit_depth() {
+        // Стек раскрывает последнюю зависимость первой: B раньше C, и C впервые встречается на глубине 2 = max_depth
+        let (graph, stats) = build(DIAMOND, &options(Strategy::Dfs, Some(2), true));
+        assert_eq!(graph.keys().collect::<Vec<_>>(), ["A", "B", "C"]);
+        assert_eq!(stats.truncated_by_depth, ["C"]);
+        // В ширину первое посещение совпадает с кратчайшим путём
+        let (graph, _) = build(DIAMOND, &options(Strategy::Bfs, Some(2), true));
+        assert_eq!(graph.keys().collect::<Vec<_>>(), ["A", "B", "C", "D"]);
+    }
+
+    /// Тестовый граф, в котором запрос зависимостей пакета C завершается ошибкой
+    struct FailingSource(source::TestSource);
+
+    impl Source for FailingSource {
+        fn dependencies(&mut self, name: &str, version: Option<&str>) -> Result<Vec<Dep>, String> {
+            match name {
+                "C" => Err("сервер недоступен".to_string()),
+                _ => self.0.dependencies(name, version),
+            }
+        }
+
+        fn resolve_version(&mut self, name: &str) -> Result<Option<String>, String> {
+            self.0.resolve_version(name)
+        }
+    }
+
+    #[test]
+    fn keep_going_marks_failed_nodes_unresolved() {
+        let mut source = FailingSource(source::parse_test_graph(DIAMOND).unwrap());
+        let mut stats = RunStats::default();
+        let mut opts = options(Strategy::Bfs, None, false);
+        assert!(build_from_source(&mut source, "A", None, &opts, &mut stats, &mut |_| {}).is_err());
+
+        opts.keep_going = true;
+        let mut stats = RunStats::default();
+        let graph = build_from_source(&mut source, "A", None, &opts, &mut stats, &mut |_| {}).unwrap();
+        // C остаётся в графе ребром без раскрытия, его поддерево недоступно
+        assert_eq!(graph.keys().collect::<Vec<_>>(), ["A", "B"]);
+        assert_eq!(stats.unresolved.len(), 1);
+        assert_eq!((stats.unresolved[0].name.as_str(), stats.unresolved[0].error.as_str()), ("C", "сервер недоступен"));
+    }
+}
@@ -0,0 +1,67 @@
+use crate::export::{self, escape_xml as escape};
+use crate::graph::Graph;
+use std::fmt::Write;
+
+/// Рендерит граф в формате GraphML (yEd, Gephi, networkx).
+/// Вид зависимости, опциональность и требование к версии сохраняются атрибутами рёбер,
+/// а цвет и тип линии — атрибутами color и line_style, которые редакторы могут сопоставить со стилем.
+/// Заголовок и footer записываются атрибутами графа, root_label — подписью корня
+pub fn render_graphml(graph: &Graph, root: &str, version: &str, captions: &export::Captions) -> String {
+    let index = export::NodeIndex::new(graph);
+    let nodes = &index.nodes;
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#);
+    for (id, target, name, ty) in [
+        ("title", "graph", "title", "string"),
+        ("footer", "graph", "footer", "string"),
+        ("label", "node", "label", "string"),
+        ("root", "node", "root", "boolean"),
+        ("kind", "edge", "kind", "string"),
+        ("optional", "edge", "optional", "boolean"),
+        ("req", "edge", "req", "string"),
+        ("alias", "edge", "alias", "string"),
+        ("color", "edge", "color", "string"),
+        ("line_style", "edge", "line_style", "string"),
+    ] {
+        let _ = writeln!(
+            out,
+            r#"  <key id="{}" for="{}" attr.name="{}" attr.type="{}"/>"#,
+            id, target, name, ty
+        );
+    }
+    let _ = writeln!(out, r#"  <graph id="{}" edgedefault="directed">"#, escape(&format!("{} v{}", root, version)));
+    let _ = writeln!(out, r#"    <data key="title">{}</data>"#, escape(&captions.title(root, version)));
+    if let Some(footer) = &captions.footer {
+        let _ = writeln!(out, r#"    <data key="footer">{}</data>"#, escape(footer));
+    }
+    for (i, name) in nodes.iter().enumerate() {
+        let _ = writeln!(out, r#"    <node id="n{}">"#, i);
+        let _ = writeln!(out, r#"      <data key="label">{}</data>"#, escape(captions.label(name, root)));
+        if *name == root {
+            let _ = writeln!(out, r#"      <data key="root">true</data>"#);
+        }
+        let _ = writeln!(out, "    </node>");
+    }
+    for name in nodes {
+        for dep in graph.get(*name).into_iter().flatten() {
+            let style = export::edge_style(dep);
+            let _ = writeln!(out, r#"    <edge source="n{}" target="n{}">"#, index.id(name), index.id(&dep.name));
+            let _ = writeln!(out, r#"      <data key="kind">{}</data>"#, escape(&dep.kind));
+            let _ = writeln!(out, r#"      <data key="optional">{}</data>"#, dep.optional);
+            if let Some(req) = &dep.req {
+                let _ = writeln!(out, r#"      <data key="req">{}</data>"#, escape(req));
+            }
+            if let Some(alias) = &dep.alias {
+                let _ = writeln!(out, r#"      <data key="alias">{}</data>"#, escape(alias));
+            }
+            let _ = writeln!(out, r#"      <data key="color">{}</data>"#, style.color);
+            let _ = writeln!(out, r#"      <data key="line_style">{}</data>"#, style.line);
+            let _ = writeln!(out, "    </edge>");
+        }
+    }
+    let _ = writeln!(out, "  </graph>");
+    let _ = writeln!(out, "</graphml>");
+    out
+}
@@ -0,0 +1,46 @@
+use crate::analysis;
+use crate::graph::{Dep, Graph};
+use crate::metadata::Metadata;
+use crate::prune::Pattern;
+use std::collections::{BTreeMap, HashMap};
+
+/// Сворачивает семейства пакетов в групповые узлы: все пакеты (кроме корня), подходящие под шаблон,
+/// заменяются одним узлом с именем шаблона (`tokio-*`). Зависимости группы — объединение зависимостей
+/// её участников за пределами группы; рёбра к участникам становятся рёбрами к группе, без требования
+/// к версии, так как оно относится к конкретному участнику. Пакет попадает в группу первого подходящего шаблона.
+/// Возвращает свёрнутый граф и сведения о пакетах, где у групповых узлов заполнен список участников
+pub fn grouped(graph: &Graph, root: &str, patterns: &[Pattern], meta: &Metadata) -> (Graph, Metadata) {
+    let mut group_of: HashMap<&str, &str> = HashMap::new();
+    let mut members: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for name in analysis::all_nodes(graph) {
+        if name == root {
+            continue;
+        }
+        if let Some(pattern) = patterns.iter().find(|p| p.matches(name)) {
+            group_of.insert(name, pattern.as_str());
+            members.entry(pattern.as_str()).or_default().push(name);
+        }
+    }
+
+    let mut out = Graph::new();
+    for (name, deps) in graph {
+        let node = group_of.get(name.as_str()).copied().unwrap_or(name);
+        let edges = out.entry(node.to_string()).or_default();
+        for dep in deps {
+            let target = match group_of.get(dep.name.as_str()) {
+                Some(&group) if group == node => continue,
+                Some(&group) => Dep { name: group.to_string(), req: None, alias: None, ..dep.clone() },
+                None => dep.clone(),
+            };
+            if !edges.iter().any(|edge: &Dep| edge.name == target.name) {
+                edges.push(target);
+            }
+        }
+    }
+
+    let mut meta: Metadata = meta.iter().filter(|(name, _)| !group_of.contains_key(name.as_str())).map(|(n, m)| (n.clone(), m.clone())).collect();
+    for (group, names) in members {
+        meta.entry(group.to_string()).or_default().members = Some(names.iter().map(|n| n.to_string()).collect());
+    }
+    (out, meta)
+}
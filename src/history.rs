@@ -0,0 +1,323 @@
+use crate::config::Config;
+use crate::export;
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+use crate::output;
+use crate::report::{self, RunStats};
+use crate::source;
+use clap::{Args, Subcommand};
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::SystemTime;
+
+/// Параметры подкоманды history
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// База истории (поле history_path конфигурации)
+    pub db: String,
+
+    #[command(subcommand)]
+    pub action: HistoryAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// Список сохранённых запусков
+    List,
+    /// Вывести сохранённый граф в заданном формате
+    Show {
+        /// Номер запуска
+        id: i64,
+        /// Формат вывода (по умолчанию тот, что был в конфигурации запуска)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Сравнить два сохранённых графа: добавленные и удалённые пакеты и зависимости
+    Diff {
+        /// Номер более раннего запуска
+        from: i64,
+        /// Номер более позднего запуска
+        to: i64,
+    },
+}
+
+/// Сохранённый запуск
+struct Snapshot {
+    id: i64,
+    recorded_at: String,
+    config: Config,
+    graph: String,
+}
+
+/// Открывает (или создаёт) базу истории
+fn open(path: &str) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("Ошибка открытия базы истории '{}': {}", path, e))?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recorded_at TEXT NOT NULL,
+            root TEXT NOT NULL,
+            version TEXT NOT NULL,
+            node_count INTEGER NOT NULL,
+            config TEXT NOT NULL,
+            graph TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Ошибка создания таблицы истории: {}", e))?;
+    Ok(conn)
+}
+
+/// Сохраняет построенный граф (в формате JSON-экспорта) вместе с конфигурацией и временем запуска
+pub fn record(
+    path: &str,
+    config: &Config,
+    graph: &Graph,
+    stats: &RunStats,
+    meta: &Metadata,
+    recorded_at: SystemTime,
+) -> Result<(), String> {
+    let conn = open(path)?;
+    let snapshot = export::to_json(graph, &config.name, &config.version, stats, meta, None, None);
+    let config_json = serde_json::to_string(config).expect("конфигурация всегда сериализуется в JSON");
+    conn.execute(
+        "INSERT INTO runs (recorded_at, root, version, node_count, config, graph) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            report::format_timestamp(recorded_at),
+            config.name,
+            config.version,
+            graph.len() as i64,
+            config_json,
+            snapshot
+        ],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Ошибка записи в базу истории '{}': {}", path, e))
+}
+
+/// Выполняет подкоманду history и возвращает текст для вывода
+pub fn run(args: &HistoryArgs) -> Result<String, String> {
+    let conn = open(&args.db)?;
+    match &args.action {
+        HistoryAction::List => list(&conn),
+        HistoryAction::Show { id, format } => {
+            let snapshot = load(&conn, *id)?;
+            let mut config = snapshot.config;
+            if let Some(format) = format {
+                config.output_format = format.clone();
+            }
+            if config.output_format == "ndjson" || output::Exporters::builtin().get(&config.output_format).is_none() {
+                return Err(format!("Формат '{}' не поддерживается для сохранённого графа", config.output_format));
+            }
+            let imported = source::parse_json_graph(&snapshot.graph, &format!("запуск #{}", id))?;
+            let stats = RunStats {
+                truncated_by_max_nodes: imported.unexpanded,
+                truncated_by_interrupt: imported.interrupted,
+                unexpanded_in_source: imported.unexpanded_in_source,
+                unresolved: imported.unresolved,
+                ..RunStats::default()
+            };
+            Ok(output::render(&config, &imported.graph, &stats, &imported.meta, false))
+        }
+        HistoryAction::Diff { from, to } => {
+            let (old, new) = (load(&conn, *from)?, load(&conn, *to)?);
+            let old_graph = source::parse_json_graph(&old.graph, &format!("запуск #{}", old.id))?.graph;
+            let new_graph = source::parse_json_graph(&new.graph, &format!("запуск #{}", new.id))?.graph;
+            let mut out = format!(
+                "Изменения с запуска #{} ({}) по запуск #{} ({}):\n",
+                old.id, old.recorded_at, new.id, new.recorded_at
+            );
+            out.push_str(&diff_graphs(&old_graph, &new_graph));
+            Ok(out)
+        }
+    }
+}
+
+/// Таблица сохранённых запусков
+fn list(conn: &Connection) -> Result<String, String> {
+    let query_error = |e: rusqlite::Error| format!("Ошибка чтения базы истории: {}", e);
+    let mut stmt = conn
+        .prepare("SELECT id, recorded_at, root, version, node_count FROM runs ORDER BY id")
+        .map_err(query_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(format!(
+                "#{:<5} {}  {} v{}  пакетов: {}\n",
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?
+            ))
+        })
+        .map_err(query_error)?;
+    let mut out = String::new();
+    for row in rows {
+        out.push_str(&row.map_err(query_error)?);
+    }
+    if out.is_empty() {
+        out.push_str("История пуста\n");
+    }
+    Ok(out)
+}
+
+/// Последний сохранённый запуск для корня root
+fn latest_run(conn: &Connection, root: &str) -> Result<Option<Snapshot>, String> {
+    let id: Option<i64> = conn
+        .query_row("SELECT MAX(id) FROM runs WHERE root = ?1", [root], |row| row.get(0))
+        .map_err(|e| format!("Ошибка чтения базы истории: {}", e))?;
+    id.map(|id| load(conn, id)).transpose()
+}
+
+/// Краткая сводка изменений графа по сравнению с последним сохранённым запуском того же корня:
+/// новые и удалённые пакеты и изменения версий (требований рёбер, последних версий из постоянного
+/// кэша и версии корня). None, если сравнивать не с чем. Вызывается до записи текущего запуска
+pub fn change_alert(path: &str, config: &Config, graph: &Graph, stats: &RunStats) -> Result<Option<String>, String> {
+    let conn = open(path)?;
+    let Some(previous) = latest_run(&conn, &config.name)? else { return Ok(None) };
+    let imported = source::parse_json_graph(&previous.graph, &format!("запуск #{}", previous.id))?;
+    let old = &imported.graph;
+    let requirements = |g: &Graph| -> BTreeMap<(String, String), String> {
+        g.iter()
+            .flat_map(|(n, deps)| {
+                deps.iter().map(move |d| ((n.clone(), d.name.clone()), d.req.clone().unwrap_or_else(|| "*".to_string())))
+            })
+            .collect()
+    };
+    let (old_nodes, new_nodes) = (node_set(old), node_set(graph));
+    let added: Vec<&String> = new_nodes.difference(&old_nodes).collect();
+    let removed: Vec<&String> = old_nodes.difference(&new_nodes).collect();
+
+    let mut changed = Vec::new();
+    if previous.config.version != config.version {
+        changed.push(format!("{} {} -> {}", config.name, previous.config.version, config.version));
+    }
+    changed.extend(stats.changed_versions.iter().cloned());
+    let old_reqs = requirements(old);
+    for (edge, req) in requirements(graph) {
+        if let Some(old_req) = old_reqs.get(&edge).filter(|old_req| **old_req != req) {
+            changed.push(format!("{} -> {}: {} -> {}", edge.0, edge.1, old_req, req));
+        }
+    }
+
+    let mut out = format!(
+        "Изменения с прошлого запуска #{} ({}): новых пакетов: {}, удалено: {}, изменений версий: {}\n",
+        previous.id,
+        previous.recorded_at,
+        added.len(),
+        removed.len(),
+        changed.len()
+    );
+    added.iter().for_each(|name| out.push_str(&format!("  + {}\n", name)));
+    removed.iter().for_each(|name| out.push_str(&format!("  - {}\n", name)));
+    changed.iter().for_each(|change| out.push_str(&format!("  ~ {}\n", change)));
+    Ok(Some(out))
+}
+
+/// Загружает запуск по номеру
+fn load(conn: &Connection, id: i64) -> Result<Snapshot, String> {
+    let row = conn
+        .query_row("SELECT recorded_at, config, graph FROM runs WHERE id = ?1", [id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })
+        .optional()
+        .map_err(|e| format!("Ошибка чтения базы истории: {}", e))?;
+    let (recorded_at, config, graph) = row.ok_or_else(|| format!("Запуск #{} не найден", id))?;
+    let config = serde_json::from_str(&config)
+        .map_err(|e| format!("Ошибка разбора конфигурации запуска #{}: {}", id, e))?;
+    Ok(Snapshot { id, recorded_at, config, graph })
+}
+
+/// Интервал, в котором пакет или ребро присутствовали в графе: от запуска, где они появились,
+/// до запуска, где они исчезли (None — присутствуют в последнем запуске)
+#[derive(Debug, Clone)]
+pub struct Spell {
+    pub start: String,
+    pub end: Option<String>,
+}
+
+/// Интервалы присутствия пакетов и рёбер по всем сохранённым запускам одного корня
+#[derive(Debug, Default)]
+pub struct Timeline {
+    pub nodes: HashMap<String, Vec<Spell>>,
+    pub edges: HashMap<(String, String), Vec<Spell>>,
+}
+
+/// Строит временную шкалу графа по истории запусков корня root (в порядке записи)
+pub fn timeline(path: &str, root: &str) -> Result<Timeline, String> {
+    let conn = open(path)?;
+    let query_error = |e: rusqlite::Error| format!("Ошибка чтения базы истории: {}", e);
+    let mut stmt = conn
+        .prepare("SELECT id, recorded_at, graph FROM runs WHERE root = ?1 ORDER BY id")
+        .map_err(query_error)?;
+    let rows = stmt
+        .query_map([root], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+        .map_err(query_error)?;
+
+    let mut timeline = Timeline::default();
+    for row in rows {
+        let (id, recorded_at, raw) = row.map_err(query_error)?;
+        let graph = source::parse_json_graph(&raw, &format!("запуск #{}", id))?.graph;
+        let nodes: BTreeSet<String> = graph
+            .iter()
+            .flat_map(|(n, deps)| std::iter::once(n.clone()).chain(deps.iter().map(|d| d.name.clone())))
+            .collect();
+        let edges: BTreeSet<(String, String)> = graph
+            .iter()
+            .flat_map(|(n, deps)| deps.iter().map(move |d| (n.clone(), d.name.clone())))
+            .collect();
+        advance(&mut timeline.nodes, nodes, &recorded_at);
+        advance(&mut timeline.edges, edges, &recorded_at);
+    }
+    Ok(timeline)
+}
+
+/// Открывает интервалы появившихся элементов и закрывает интервалы исчезнувших
+fn advance<K: Ord + std::hash::Hash>(spells: &mut HashMap<K, Vec<Spell>>, present: BTreeSet<K>, at: &str) {
+    for (key, list) in spells.iter_mut() {
+        if let Some(last) = list.last_mut()
+            && last.end.is_none()
+            && !present.contains(key)
+        {
+            last.end = Some(at.to_string());
+        }
+    }
+    for key in present {
+        let list = spells.entry(key).or_default();
+        if list.last().is_none_or(|last| last.end.is_some()) {
+            list.push(Spell { start: at.to_string(), end: None });
+        }
+    }
+}
+
+/// Все пакеты графа, включая нераскрытые зависимости
+fn node_set(graph: &Graph) -> BTreeSet<String> {
+    graph.iter().flat_map(|(n, deps)| std::iter::once(n.clone()).chain(deps.iter().map(|d| d.name.clone()))).collect()
+}
+
+/// Добавленные и удалённые пакеты и рёбра между двумя графами
+fn diff_graphs(old: &Graph, new: &Graph) -> String {
+    let edges = |g: &Graph| -> BTreeSet<String> {
+        g.iter().flat_map(|(n, deps)| deps.iter().map(move |d| format!("{} -> {}", n, d.name))).collect()
+    };
+    let (old_nodes, new_nodes) = (node_set(old), node_set(new));
+    let (old_edges, new_edges) = (edges(old), edges(new));
+
+    let mut out = String::new();
+    let mut section = |title: &str, mark: char, items: Vec<&String>| {
+        if !items.is_empty() {
+            out.push_str(&format!("{} ({}):\n", title, items.len()));
+            for item in items {
+                out.push_str(&format!("  {} {}\n", mark, item));
+            }
+        }
+    };
+    section("Добавлены пакеты", '+', new_nodes.difference(&old_nodes).collect());
+    section("Удалены пакеты", '-', old_nodes.difference(&new_nodes).collect());
+    section("Добавлены зависимости", '+', new_edges.difference(&old_edges).collect());
+    section("Удалены зависимости", '-', old_edges.difference(&new_edges).collect());
+    if out.is_empty() {
+        out.push_str("Изменений нет\n");
+    }
+    out
+}
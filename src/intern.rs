@@ -0,0 +1,125 @@
+use crate::graph::{Dep, Graph};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Идентификатор интернированной строки
+pub type Symbol = u32;
+
+/// Таблица интернированных строк: каждая строка хранится один раз и получает номер.
+/// Словарь и список номеров делят одну копию строки
+#[derive(Default)]
+pub struct Interner {
+    ids: HashMap<Rc<str>, Symbol>,
+    strings: Vec<Rc<str>>,
+}
+
+impl Interner {
+    /// Номер строки s; новая строка добавляется в таблицу
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&id) = self.ids.get(s) {
+            return id;
+        }
+        let id = Symbol::try_from(self.strings.len()).expect("число различных строк превышает u32");
+        let s: Rc<str> = s.into();
+        self.strings.push(Rc::clone(&s));
+        self.ids.insert(s, id);
+        id
+    }
+
+    /// Номер строки s, если она уже интернирована
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.ids.get(s).copied()
+    }
+
+    /// Строка с номером id
+    pub fn resolve(&self, id: Symbol) -> &str {
+        &self.strings[id as usize]
+    }
+
+    /// Рёбра со строками вместо номеров
+    fn expand(&self, deps: &[CompactDep]) -> Vec<Dep> {
+        let string = |id: Symbol| self.resolve(id).to_string();
+        deps.iter()
+            .map(|dep| Dep {
+                name: string(dep.name),
+                kind: string(dep.kind),
+                req: dep.req.map(string),
+                optional: dep.optional,
+                alias: dep.alias.map(string),
+                local: dep.local,
+            })
+            .collect()
+    }
+}
+
+/// Ребро компактного графа: все строки заменены номерами из таблицы символов.
+/// Имена пакетов, виды и требования к версиям сильно повторяются, поэтому ребро занимает 28 байт
+/// вместо 104 у Dep и четырёх отдельных строк в куче
+#[derive(Debug, Clone, Copy)]
+pub struct CompactDep {
+    pub name: Symbol,
+    pub kind: Symbol,
+    pub req: Option<Symbol>,
+    pub alias: Option<Symbol>,
+    pub optional: bool,
+    pub local: bool,
+}
+
+/// Граф зависимостей с интернированными строками: используется при обходе больших графов,
+/// для вывода преобразуется в обычный Graph. Контрольная точка сериализуется прямо из него
+/// в том же виде, что и Graph, без промежуточной копии
+#[derive(Default)]
+pub struct CompactGraph {
+    pub symbols: Interner,
+    edges: HashMap<Symbol, Box<[CompactDep]>>,
+}
+
+impl CompactGraph {
+    /// Добавляет (или заменяет) узел name с рёбрами deps; возвращает номер узла
+    pub fn insert(&mut self, name: &str, deps: &[Dep]) -> Symbol {
+        let id = self.symbols.intern(name);
+        let deps = deps.iter().map(|dep| self.compact(dep)).collect();
+        self.edges.insert(id, deps);
+        id
+    }
+
+    /// Число раскрытых узлов
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Нет ни одного раскрытого узла
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    fn compact(&mut self, dep: &Dep) -> CompactDep {
+        CompactDep {
+            name: self.symbols.intern(&dep.name),
+            kind: self.symbols.intern(&dep.kind),
+            req: dep.req.as_deref().map(|req| self.symbols.intern(req)),
+            alias: dep.alias.as_deref().map(|alias| self.symbols.intern(alias)),
+            optional: dep.optional,
+            local: dep.local,
+        }
+    }
+
+    /// Обычный граф со строковыми именами. Рёбра компактного графа освобождаются по мере
+    /// преобразования, поэтому в памяти не оказывается двух полных копий графа
+    pub fn into_graph(self) -> Graph {
+        let CompactGraph { symbols, edges } = self;
+        edges.into_iter().map(|(id, deps)| (symbols.resolve(id).to_string(), symbols.expand(&deps))).collect()
+    }
+}
+
+/// Строки рёбер восстанавливаются по одному узлу, поэтому сериализация не удваивает граф в памяти
+impl Serialize for CompactGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.edges.len()))?;
+        for (&id, deps) in &self.edges {
+            map.serialize_entry(self.symbols.resolve(id), &self.symbols.expand(deps))?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,22 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Нажат Ctrl-C: обход останавливается на следующем узле
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Устанавливает обработчик Ctrl-C: первое нажатие останавливает обход, и построенная часть графа
+/// выводится как неполная; второе завершает программу сразу (код 130)
+pub fn install() -> Result<(), String> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            process::exit(130);
+        }
+        eprintln!("\nОбход прерывается, будет выведен построенный граф (повторный Ctrl-C — выход без вывода)");
+    })
+    .map_err(|e| format!("не удалось установить обработчик Ctrl-C: {}", e))
+}
+
+/// Запрошено ли прерывание
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,43 @@
+//! Построение и анализ графа зависимостей пакетов Cargo. Бинарный файл cargo-routes — обёртка над
+//! этими модулями; библиотека нужна бенчмаркам (benches/) и не считается стабильным API
+
+pub mod analysis;
+pub mod annotations;
+pub mod cache;
+pub mod checkpoint;
+pub mod compare;
+pub mod config;
+pub mod deny;
+pub mod diff;
+pub mod dot;
+pub mod duplicates;
+pub mod explain;
+pub mod export;
+pub mod features;
+pub mod footprint;
+pub mod generator;
+pub mod gexf;
+pub mod graph;
+pub mod graphml;
+pub mod group;
+pub mod history;
+pub mod intern;
+pub mod interrupt;
+pub mod manifest;
+pub mod merge;
+pub mod mermaid;
+pub mod metadata;
+pub mod outdated;
+pub mod output;
+pub mod policy;
+pub mod prune;
+pub mod query;
+pub mod registry;
+pub mod report;
+pub mod roots;
+pub mod sarif;
+pub mod server;
+pub mod source;
+pub mod terminal;
+pub mod tree;
+pub mod validate;
@@ -1,335 +1,530 @@
-use serde::Deserialize;
-use std::collections::{HashMap, HashSet};
-use std::env;
+use cargo_routes::{
+    analysis, compare, config, diff, explain, generator, graph, group, history, interrupt, metadata, output, policy, prune, report, roots, sarif, server, source, terminal, tree,
+};
+use clap::builder::PossibleValuesParser;
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
+use prune::Prune;
+use std::collections::HashSet;
 use std::fs;
+use std::io::{self, Write};
 use std::process;
-
-/// Конфигурация приложения
-#[derive(Deserialize, Debug)]
-struct Config {
-    name: String,
-    repository: String,
-    test_repo_mode: String, // "test" или "remote"
-    version: String,
-    output_filename: String,
-    ascii_tree_mode: bool,
-    max_depth: Option<usize>,
+use std::time::{Instant, SystemTime};
+
+/// Инструмент визуализации графа зависимостей пакетов Cargo
+#[derive(Parser, Debug)]
+#[command(name = "cargo-routes", version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Путь к конфигурационному файлу JSON
+    #[arg(required = true, value_hint = ValueHint::FilePath)]
+    config: Option<String>,
+
+    /// Запустить веб-сервер с интерактивным графом на указанном порту
+    #[arg(long, value_name = "PORT")]
+    serve: Option<u16>,
+
+    /// Не выводить имена пакетов гиперссылками на crates.io
+    #[arg(long)]
+    no_hyperlinks: bool,
+
+    /// Не показывать длинный вывод через пейджер ($PAGER или less -R)
+    #[arg(long)]
+    no_pager: bool,
+
+    /// Сравнить вывод с эталонным файлом и завершиться с ошибкой и diff при расхождении
+    #[arg(long, value_name = "EXPECTED_FILE", value_hint = ValueHint::FilePath)]
+    check: Option<String>,
+
+    /// Таймаут соединения с crates.io в секундах (переопределяет connect_timeout_secs)
+    #[arg(long, value_name = "SECS")]
+    connect_timeout: Option<u64>,
+
+    /// Общий таймаут HTTP-запроса в секундах (переопределяет timeout_secs)
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Не обходить опциональные зависимости (переопределяет exclude_optional)
+    #[arg(long)]
+    exclude_optional: bool,
+
+    /// Включать dev-зависимости корня и участников рабочего пространства (переопределяет include_dev)
+    #[arg(long)]
+    include_dev: bool,
+
+    /// Цель сборки корня: lib, bin:NAME, example:NAME, bench:NAME или test:NAME (переопределяет target)
+    #[arg(long, value_name = "TARGET")]
+    target: Option<String>,
+
+    /// Удалить из графа пакеты по шаблону (например windows-*) и недостижимые после этого пакеты;
+    /// флаг можно повторять, шаблоны добавляются к prune из конфигурации
+    #[arg(long, value_name = "PATTERN")]
+    prune: Vec<String>,
+
+    /// Свернуть при выводе пакеты по шаблону (например tokio-*) в один групповой узел;
+    /// флаг можно повторять, шаблоны добавляются к group из конфигурации
+    #[arg(long, value_name = "PATTERN")]
+    group: Vec<String>,
+
+    /// Формат вывода (переопределяет output_format), например github для аннотаций CI
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<String>,
+
+    /// Сворачивать поддеревья глубже N в строку `... ещё пакетов: M` (переопределяет display_depth)
+    #[arg(long, value_name = "N")]
+    display_depth: Option<usize>,
+
+    /// Прежняя семантика глубины: по первому посещению при обходе и по пути в ветви при печати
+    /// (переопределяет depth_per_branch)
+    #[arg(long)]
+    depth_per_branch: bool,
+
+    /// Продолжить прерванный обход с контрольной точки checkpoint_path (переопределяет resume)
+    #[arg(long)]
+    resume: bool,
+
+    /// Восстановить граф на дату YYYY-MM-DD: последние версии выбираются среди опубликованных
+    /// не позже неё (переопределяет as_of)
+    #[arg(long, value_name = "DATE")]
+    as_of: Option<String>,
+
+    /// Не прерывать обход из-за ошибок запросов, а отмечать такие пакеты неразрешёнными;
+    /// запуск с неразрешёнными пакетами завершается с кодом 2 (переопределяет keep_going)
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Показывать требования к версиям на рёбрах и разрешённые версии (переопределяет show_requirements)
+    #[arg(long)]
+    show_requirements: bool,
+
+    /// Выводить в JSON до N различных путей от корня к каждому пакету (переопределяет path_samples)
+    #[arg(long, value_name = "N")]
+    path_samples: Option<usize>,
+
+    /// Печатать под графом легенду отметок и число циклов и нераскрытых ветвей (переопределяет legend)
+    #[arg(long)]
+    legend: bool,
+
+    /// Объединять features участников рабочего пространства (переопределяет unify_features)
+    #[arg(long)]
+    unify_features: bool,
+
+    /// Читать тестовый или JSON-граф со стандартного ввода (то же, что repository: "-")
+    #[arg(long)]
+    stdin: bool,
+
+    /// Печатать ветви дерева в stderr по мере обхода (переопределяет stream_tree)
+    #[arg(long)]
+    stream: bool,
+
+    /// Вместо графа объяснить, как в него попал пакет: родители, требования и выбор версии
+    #[arg(long, value_name = "CRATE")]
+    explain: Option<String>,
+
+    /// Вместо графа сравнить замыкание корня с замыканием другого пакета (`axum` или `axum@0.7`):
+    /// общие и уникальные зависимости и размеры
+    #[arg(long, value_name = "CRATE[@VERSION]")]
+    compare: Option<String>,
+
+    /// Печатать в stderr статистику запуска: HTTP-запросы, полученные байты, кэш и длительности этапов
+    #[arg(long, short)]
+    verbose: bool,
+
+    /// Число потоков, параллельно запрашивающих crates.io (переопределяет jobs)
+    #[arg(long, short, value_name = "N")]
+    jobs: Option<usize>,
 }
 
-/// Структуры для парсинга ответов crates.io
-#[derive(Debug, Deserialize)]
-struct Dependency {
-    crate_id: String,
-    kind: Option<String>,
-    optional: bool,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Сгенерировать синтетический тестовый граф в формате "A: B C"
+    Generate(generator::GenerateArgs),
+    /// Просмотреть сохранённые запуски, вывести или сравнить их графы
+    History(history::HistoryArgs),
+    /// Напечатать скрипт дополнения команд для оболочки (bash, zsh, fish, elvish, powershell)
+    Completions {
+        shell: clap_complete::Shell,
+    },
 }
 
-#[derive(Debug, Deserialize)]
-struct DependenciesResponse {
-    dependencies: Vec<Dependency>,
+/// Описание CLI для скрипта дополнения: имена форматов вывода подставляются из реестра экспортёров,
+/// чтобы дополнение знало и форматы, которых нет в разборе аргументов
+fn completion_command() -> clap::Command {
+    let formats: Vec<String> = output::Exporters::builtin().names().into_iter().map(String::from).collect();
+    let parser = PossibleValuesParser::new(formats);
+    Cli::command()
+        .mut_arg("format", |arg| arg.value_parser(parser.clone()))
+        .mut_subcommand("history", |history| {
+            history.mut_subcommand("show", |show| show.mut_arg("format", |arg| arg.value_parser(parser.clone())))
+        })
 }
 
-#[derive(Debug, Deserialize)]
-struct VersionInfo {
-    num: String,
-}
+fn main() {
+    let cli = Cli::parse();
 
-#[derive(Debug, Deserialize)]
-struct VersionsResponse {
-    versions: Vec<VersionInfo>,
-}
+    match &cli.command {
+        Some(Command::Generate(args)) if args.bench_fixture.is_some() => {
+            let dir = args.bench_fixture.as_deref().unwrap_or_default();
+            match generator::write_bench_fixtures(dir) {
+                Ok(configs) => configs.iter().for_each(|path| println!("{}", path)),
+                Err(e) => {
+                    eprintln!("Ошибка: {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Generate(args)) => {
+            let graph = generator::generate(args);
+            match &args.output {
+                Some(path) => fs::write(path, graph).unwrap_or_else(|e| {
+                    eprintln!("Ошибка записи графа '{}': {}", path, e);
+                    process::exit(1);
+                }),
+                None => print!("{}", graph),
+            }
+            return;
+        }
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(*shell, &mut completion_command(), "cargo-routes", &mut io::stdout());
+            return;
+        }
+        Some(Command::History(args)) => {
+            match history::run(args) {
+                Ok(out) => print!("{}", out),
+                Err(e) => {
+                    eprintln!("Ошибка: {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        None => {}
+    }
 
-fn main() {
     // 1) Читаем конфиг
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Использование: {} <config.json>", args[0]);
+    let config_path = cli.config.as_deref().expect("clap требует путь к конфигурации");
+    let mut config = config::load_config(config_path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
         process::exit(1);
+    });
+    // Параметры командной строки имеют приоритет над конфигурацией
+    if cli.connect_timeout.is_some() {
+        config.connect_timeout_secs = cli.connect_timeout;
     }
-    let config_path = &args[1];
-
-    let raw = fs::read_to_string(config_path).unwrap_or_else(|e| {
-        eprintln!("Ошибка чтения конфигурации '{}': {}", config_path, e);
+    if cli.timeout.is_some() {
+        config.timeout_secs = cli.timeout;
+    }
+    if cli.exclude_optional {
+        config.exclude_optional = true;
+    }
+    if cli.include_dev {
+        config.include_dev = true;
+    }
+    if cli.target.is_some() {
+        config.target = cli.target.clone();
+    }
+    if cli.stdin {
+        config.repository = source::STDIN_PATH.to_string();
+    }
+    // Со стандартного ввода читаются только готовые графы: тестовый и JSON
+    let remote = config.source == config::SourceKind::Crawl && config.test_repo_mode != "test";
+    if config.repository == source::STDIN_PATH && (config.source == config::SourceKind::Manifest || remote) {
+        eprintln!("Ошибка: граф читается со стандартного ввода только в тестовом режиме и при source: \"json\"");
         process::exit(1);
-    });
+    }
+    config.prune.extend(cli.prune.iter().cloned());
+    config.group.extend(cli.group.iter().cloned());
+    if let Some(jobs) = cli.jobs {
+        config.jobs = jobs;
+    }
+    if let Some(format) = &cli.format {
+        config.output_format = format.clone();
+    }
+    if cli.stream {
+        config.stream_tree = true;
+    }
+    if cli.resume {
+        config.resume = true;
+    }
+    if cli.keep_going {
+        config.keep_going = true;
+    }
+    if cli.show_requirements {
+        config.show_requirements = true;
+    }
+    if cli.path_samples.is_some() {
+        config.path_samples = cli.path_samples;
+    }
+    if cli.unify_features {
+        config.unify_features = true;
+    }
+    if cli.legend {
+        config.legend = true;
+    }
+    if cli.as_of.is_some() {
+        config.as_of = cli.as_of.clone();
+    }
+    if let Some(date) = &config.as_of
+        && (date.len() != 10 || report::days_from_date(date).is_none())
+    {
+        eprintln!("Ошибка: некорректная дата as_of '{}' (ожидается YYYY-MM-DD)", date);
+        process::exit(1);
+    }
+    if cli.display_depth.is_some() {
+        config.display_depth = cli.display_depth;
+    }
+    if cli.depth_per_branch {
+        config.depth_per_branch = true;
+    }
 
-    let config: Config = serde_json::from_str(&raw).unwrap_or_else(|e| {
-        eprintln!("Ошибка разбора JSON: {}", e);
+    // "latest" и диапазоны semver для корня разрешаются в конкретную версию до обхода;
+    // версии нескольких корней разрешаются в их потоках
+    if config.roots.is_empty()
+        && let Err(e) = graph::resolve_root_version(&mut config)
+    {
+        eprintln!("Ошибка: {}", e);
         process::exit(1);
-    });
+    }
 
-    // 2) В зависимости от режима строим полный транзитивный граф
-    let graph = if config.test_repo_mode == "test" {
-        // Тестовый режим: читаем "сырые" зависимости из файла и строим транзитивный граф
-        let raw_graph = load_test_graph(&config.repository)
-            .unwrap_or_else(|e| { eprintln!("Ошибка: {}", e); process::exit(1); });
-        build_test_graph(&config.name, &raw_graph, config.max_depth)
-    } else {
-        // Реальный режим: собираем транзитивный граф через crates.io API
-        let client = reqwest::blocking::Client::new();
-        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
-        let mut visited: HashSet<String> = HashSet::new();
-        // Кэши, чтобы не запрашивать одно и то же несколько раз
-        let mut latest_cache: HashMap<String, String> = HashMap::new();
-        let mut deps_cache: HashMap<String, Vec<String>> = HashMap::new();
-
-        if let Err(e) = build_real_graph(
-            &client,
-            &config.name,
-            &config.version,
-            &mut graph,
-            &mut visited,
-            config.max_depth,
-            &mut latest_cache,
-            &mut deps_cache,
-        ) {
+    // Режим веб-сервера: граф строится лениво при запросах
+    if let Some(port) = cli.serve {
+        if let Err(e) = server::serve(&config, port) {
             eprintln!("Ошибка: {}", e);
             process::exit(1);
         }
-        graph
-    };
-
-    // 3) Печать ASCII-дерева (учитывает max_depth)
-    println!("Граф зависимостей для {} v{}:", config.name, config.version);
-    print_ascii_tree(
-        &graph,
-        &config.name,
-        "",
-        true,
-        &mut HashSet::new(),
-        0,
-        config.max_depth,
-    );
-}
+        return;
+    }
 
-/// Загружает тестовый граф из файла формата "A: B C"
-fn load_test_graph(path: &str) -> Result<HashMap<String, Vec<String>>, String> {
-    let raw = fs::read_to_string(path)
-        .map_err(|e| format!("Ошибка чтения тестового графа '{}': {}", path, e))?;
-    let mut graph = HashMap::new();
-
-    for (lineno, line) in raw.lines().enumerate() {
-        let line = line.trim();
-        if line.is_empty() { continue; }
-        if let Some((pkg, deps)) = line.split_once(':') {
-            let pkg = pkg.trim().to_string();
-            let deps: Vec<String> = deps
-                .split_whitespace()
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            graph.insert(pkg, deps);
-        } else {
-            return Err(format!("Ошибка формата в строке {}: {}", lineno + 1, line));
-        }
+    // Гиперссылки имеют смысл только для настоящих пакетов crates.io
+    // и не должны попадать в сравнение с эталоном
+    let hyperlinks = !cli.no_hyperlinks
+        && cli.check.is_none()
+        && config.test_repo_mode != "test"
+        && match config.hyperlinks.as_str() {
+            "always" => true,
+            "never" => false,
+            "auto" => terminal::supports_hyperlinks(),
+            other => {
+                eprintln!("Ошибка: неизвестное значение hyperlinks '{}' (ожидается auto, always или never)", other);
+                process::exit(1);
+            }
+        };
+
+    let exporters = output::Exporters::builtin();
+    if exporters.get(&config.output_format).is_none() {
+        eprintln!(
+            "Ошибка: неизвестный формат вывода '{}' (ожидается {})",
+            config.output_format,
+            exporters.names().join(", ")
+        );
+        process::exit(1);
     }
-    Ok(graph)
-}
 
-/// Построение транзитивного графа для тестового режима (итеративный DFS без рекурсии)
-/// - start: имя корневого пакета
-/// - graph_raw: "сырые" прямые зависимости из файла
-/// - max_depth: Option<usize> — ограничение глубины (0-based: root depth = 0)
-fn build_test_graph(
-    start: &str,
-    graph_raw: &HashMap<String, Vec<String>>,
-    max_depth: Option<usize>,
-) -> HashMap<String, Vec<String>> {
-    let mut graph: HashMap<String, Vec<String>> = HashMap::new();
-    let mut visited: HashSet<String> = HashSet::new();
-    // стек хранит (node, depth)
-    let mut stack: Vec<(String, usize)> = vec![(start.to_string(), 0)];
-
-    while let Some((node, depth)) = stack.pop() {
-        if visited.contains(&node) {
-            continue;
-        }
-        visited.insert(node.clone());
+    let parse_patterns = |patterns: &[String]| -> Vec<prune::Pattern> {
+        patterns.iter().map(|p| prune::Pattern::parse(p)).collect::<Result<_, _>>().unwrap_or_else(|e| {
+            eprintln!("Ошибка: {}", e);
+            process::exit(1);
+        })
+    };
+    let prune_patterns = parse_patterns(&config.prune);
+    let group_patterns = parse_patterns(&config.group);
 
-        // Берём прямые зависимости из исходного файла (или пустой вектор)
-        let deps = graph_raw.get(&node).cloned().unwrap_or_default();
-        graph.insert(node.clone(), deps.clone());
+    // Ctrl-C во время долгого обхода не теряет построенную часть графа
+    if let Err(e) = interrupt::install() {
+        eprintln!("Предупреждение: {}", e);
+    }
 
-        // Если есть ограничение глубины и мы достигли его — не углубляемся дальше
-        if let Some(max) = max_depth {
-            if depth >= max {
-                continue;
+    // Несколько корней: графы строятся параллельно, каждый выводится в свой файл
+    if !config.roots.is_empty() {
+        if cli.check.is_some() || cli.explain.is_some() || cli.compare.is_some() || config.output_format == "ndjson" {
+            eprintln!("Ошибка: при нескольких корнях --check, --explain, --compare и формат ndjson не поддерживаются");
+            process::exit(1);
+        }
+        match roots::run(&config, &prune_patterns, &group_patterns) {
+            Ok((summary, failed)) => {
+                print!("{}", summary);
+                if failed {
+                    process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Ошибка: {}", e);
+                process::exit(1);
             }
         }
+        return;
+    }
 
-        // Добавляем детей в стек с увеличенной глубиной
-        for dep in deps {
-            stack.push((dep, depth + 1));
+    // Сравнение двух пакетов: оба графа строятся параллельно, как при нескольких корнях
+    if let Some(spec) = &cli.compare {
+        match compare::run(&config, &compare::parse_root(spec), &prune_patterns) {
+            Ok(out) => print!("{}", out),
+            Err(e) => {
+                eprintln!("Ошибка: {}", e);
+                process::exit(1);
+            }
         }
+        return;
     }
 
-    graph
-}
-
-/// Получение прямых зависимостей конкретной версии через crates.io API
-/// Использует кэш deps_cache по ключу "crate:version"
-fn fetch_dependencies_cached(
-    client: &reqwest::blocking::Client,
-    pkg: &str,
-    version: &str,
-    deps_cache: &mut HashMap<String, Vec<String>>,
-) -> Result<Vec<String>, String> {
-    let key = format!("{}:{}", pkg, version);
-    if let Some(cached) = deps_cache.get(&key) {
-        return Ok(cached.clone());
-    }
-
-    let url = format!("https://crates.io/api/v1/crates/{}/{}/dependencies", pkg, version);
-    let resp = client
-        .get(&url)
-        .header("User-Agent", "dep-visualizer (edu)")
-        .send()
-        .map_err(|e| format!("Ошибка HTTP при запросе зависимостей {} {}: {}", pkg, version, e))?;
-
-    if !resp.status().is_success() {
-        return Err(format!("crates.io вернул статус {} для {}/{}", resp.status(), pkg, version));
-    }
-
-    let deps_resp: DependenciesResponse =
-        resp.json().map_err(|e| format!("Ошибка парсинга JSON зависимостей {} {}: {}", pkg, version, e))?;
-
-    let dep_names: Vec<String> = deps_resp
-        .dependencies
-        .into_iter()
-        .filter(|dep| dep.kind.as_deref() != Some("dev"))
-        .map(|d| d.crate_id)
-        .collect();
-
-    deps_cache.insert(key, dep_names.clone());
-    Ok(dep_names)
-}
-
-/// Получение последней версии пакета (кэшируется)
-fn fetch_latest_version_cached(
-    client: &reqwest::blocking::Client,
-    pkg: &str,
-    latest_cache: &mut HashMap<String, String>,
-) -> Result<String, String> {
-    if let Some(v) = latest_cache.get(pkg) {
-        return Ok(v.clone());
+    // Графы источников объединяются после обхода, поэтому построчно выводить нечего
+    if !config.merge.is_empty() && (config.output_format == "ndjson" || config.stream_tree) {
+        eprintln!("Ошибка: при объединении графов (merge) формат ndjson и stream_tree не поддерживаются");
+        process::exit(1);
     }
 
-    let url = format!("https://crates.io/api/v1/crates/{}/versions", pkg);
-    let resp = client
-        .get(&url)
-        .header("User-Agent", "dep-visualizer (edu)")
-        .send()
-        .map_err(|e| format!("Ошибка HTTP при запросе версий {}: {}", pkg, e))?;
+    // 2) В зависимости от режима строим полный транзитивный граф
+    let started_at = SystemTime::now();
+    let mut stats = report::RunStats::default();
+    // В режиме проверки ndjson накапливается, а не печатается
+    let mut streamed = String::new();
+    let phase_started = Instant::now();
+    let result = if config.output_format == "ndjson" {
+        // Каждый узел печатается отдельной строкой JSON сразу после получения
+        let mut stdout = io::stdout().lock();
+        graph::build_graph(&config, &mut stats, &mut |node| {
+            let line = serde_json::to_string(node).expect("узел всегда сериализуется в JSON");
+            if cli.check.is_some() {
+                streamed.push_str(&line);
+                streamed.push('\n');
+            } else {
+                let _ = writeln!(stdout, "{}", line);
+                let _ = stdout.flush();
+            }
+        })
+    } else if config.stream_tree {
+        // Предварительное дерево идёт в stderr: stdout остаётся за окончательным выводом
+        let mut stderr = io::stderr().lock();
+        graph::build_graph(&config, &mut stats, &mut |node| {
+            let _ = writeln!(stderr, "{}", tree::stream_line(node));
+        })
+    } else {
+        graph::build_graph(&config, &mut stats, &mut |_| {})
+    };
+    stats.record_phase("обход", phase_started);
 
-    if !resp.status().is_success() {
-        return Err(format!("crates.io вернул статус {} при запросе версий {}", resp.status(), pkg));
-    }
+    // Метаданные пакетов (владельцы) запрашиваются только если нужны для вывода
+    let phase_started = Instant::now();
+    let meta = match &result {
+        Ok(graph) => metadata::collect(&config, graph, &mut stats),
+        Err(_) => metadata::Metadata::new(),
+    };
+    stats.record_phase("сведения о пакетах", phase_started);
 
-    let versions: VersionsResponse =
-        resp.json().map_err(|e| format!("Ошибка парсинга JSON версий {}: {}", pkg, e))?;
-    if let Some(vinfo) = versions.versions.first() {
-        latest_cache.insert(pkg.to_string(), vinfo.num.clone());
-        Ok(vinfo.num.clone())
-    } else {
-        Err(format!("Не найдены версии для пакета {}", pkg))
+    if let Some(path) = &config.report_path {
+        let error = result.as_ref().err().map(String::as_str);
+        if let Err(e) = report::write_report(path, &config, started_at, SystemTime::now(), result.as_ref().ok(), error, &stats) {
+            eprintln!("Предупреждение: {}", e);
+        }
     }
-}
 
-/// Построение транзитивного графа для реального пакета через crates.io API
-/// Итеративный DFS без рекурсии, с кэшами и ограничением глубины.
-/// - client: reqwest client
-/// - pkg, version: стартовая вершина и её версия
-/// - graph: выходной граф (node -> прямые зависимости)
-/// - visited: множество уже обработанных узлов
-/// - max_depth: Option<usize> — ограничение глубины (root depth = 0)
-/// - latest_cache, deps_cache: кэши для уменьшения числа HTTP-запросов
-fn build_real_graph(
-    client: &reqwest::blocking::Client,
-    pkg: &str,
-    version: &str,
-    graph: &mut HashMap<String, Vec<String>>,
-    visited: &mut HashSet<String>,
-    max_depth: Option<usize>,
-    latest_cache: &mut HashMap<String, String>,
-    deps_cache: &mut HashMap<String, Vec<String>>,
-) -> Result<(), String> {
-    // стек хранит (node, version, depth)
-    let mut stack: Vec<(String, String, usize)> = vec![(pkg.to_string(), version.to_string(), 0)];
-
-    while let Some((node, ver, depth)) = stack.pop() {
-        if visited.contains(&node) {
-            continue;
+    let graph = result.unwrap_or_else(|e| {
+        if cli.verbose {
+            eprint!("{}", stats.summary());
         }
-        visited.insert(node.clone());
-
-        // Получаем прямые зависимости для node@ver (с кэшем)
-        let deps = fetch_dependencies_cached(client, &node, &ver, deps_cache)?;
-        graph.insert(node.clone(), deps.clone());
+        eprintln!("Ошибка: {}", e);
+        process::exit(1);
+    });
 
-        // Если достигли max_depth — не углубляемся дальше
-        if let Some(max) = max_depth {
-            if depth >= max {
-                continue;
+    // История: граф каждого запуска сохраняется для последующего сравнения (неполный граф прерванного обхода — нет)
+    if let Some(path) = config.history_path.as_ref().filter(|_| !stats.interrupted) {
+        let phase_started = Instant::now();
+        if config.change_alerts {
+            match history::change_alert(path, &config, &graph, &stats) {
+                Ok(Some(alert)) => eprint!("{}", alert),
+                Ok(None) => {}
+                Err(e) => eprintln!("Предупреждение: {}", e),
             }
         }
-
-        // Для каждой зависимости получаем её последнюю версию и добавляем в стек
-        for dep in deps {
-            // Получаем последнюю версию (кэш)
-            match fetch_latest_version_cached(client, &dep, latest_cache) {
-                Ok(latest_ver) => {
-                    stack.push((dep, latest_ver, depth + 1));
-                }
-                Err(e) => {
-                    // Если не удалось получить версию — логируем в stderr и пропускаем
-                    eprintln!("Предупреждение: не удалось получить версию для '{}': {}", dep, e);
-                }
-            }
+        if let Err(e) = history::record(path, &config, &graph, &stats, &meta, started_at) {
+            eprintln!("Предупреждение: {}", e);
         }
+        stats.record_phase("история", phase_started);
     }
 
-    Ok(())
-}
+    // Удаление пакетов по шаблонам и по категориям и ключевым словам из exclude_tags:
+    // граф пересобирается от корня, в истории остаётся полный граф
+    let mut prune_patterns = prune_patterns;
+    prune_patterns.extend(metadata::tagged(&graph, &config.name, &meta, &config.exclude_tags).into_iter().map(prune::Pattern::exact));
+    let graph = if prune_patterns.is_empty() {
+        graph
+    } else {
+        let pruned = graph.pruned(&config.name, &prune_patterns);
+        let remaining: HashSet<&str> = analysis::all_nodes(&pruned).into_iter().collect();
+        stats.truncated_by_depth.retain(|name| remaining.contains(name.as_str()));
+        stats.truncated_by_max_nodes.retain(|name| remaining.contains(name.as_str()));
+        stats.truncated_by_interrupt.retain(|name| remaining.contains(name.as_str()));
+        stats.unexpanded_in_source.retain(|name| remaining.contains(name.as_str()));
+        pruned
+    };
 
-/// Печать графа в виде ASCII-дерева.
-/// - seen предотвращает бесконечные циклы при печати
-/// - current_depth и max_depth контролируют глубину печати
-fn print_ascii_tree(
-    graph: &HashMap<String, Vec<String>>,
-    node: &str,
-    prefix: &str,
-    last: bool,
-    seen: &mut HashSet<String>,
-    current_depth: usize,
-    max_depth: Option<usize>,
-) {
-    let connector = if last { "└── " } else { "├── " };
-    println!("{}{}{}", prefix, connector, node);
-
-    // Если узел уже встречался — помечаем цикл и не углубляемся
-    if !seen.insert(node.to_string()) {
-        println!("{}    (цикл: узел {})", prefix, node.to_string());
+    if let Some(name) = &cli.explain {
+        match explain::explain(&config, &graph, name) {
+            Ok(out) => print!("{}", out),
+            Err(e) => {
+                eprintln!("Ошибка: {}", e);
+                process::exit(1);
+            }
+        }
         return;
     }
 
-    // Проверяем ограничение глубины для печати
-    if let Some(max) = max_depth {
-        if current_depth >= max {
-            // показываем, что дальше есть дети, но не раскрываем их
-            if let Some(children) = graph.get(node) {
-                if !children.is_empty() {
-                    println!("{}    ... (ограничение глубины)", prefix);
-                }
-            }
-            return;
+    // 3) Вывод в выбранном формате; семейства пакетов сворачиваются только для вывода,
+    // политика проверяется по полному графу
+    let phase_started = Instant::now();
+    let rendered = if group_patterns.is_empty() {
+        output::render(&config, &graph, &stats, &meta, hyperlinks)
+    } else {
+        let (grouped, grouped_meta) = group::grouped(&graph, &config.name, &group_patterns, &meta);
+        output::render(&config, &grouped, &stats, &grouped_meta, hyperlinks)
+    };
+    stats.record_phase("вывод", phase_started);
+    if cli.verbose {
+        eprint!("{}", stats.summary());
+    }
+
+    // Режим проверки: сравниваем вывод с эталонным файлом
+    if let Some(expected_path) = &cli.check {
+        let actual = if config.output_format == "ndjson" { streamed } else { rendered };
+        let expected = fs::read_to_string(expected_path).unwrap_or_else(|e| {
+            eprintln!("Ошибка чтения эталона '{}': {}", expected_path, e);
+            process::exit(1);
+        });
+        if expected == actual {
+            eprintln!("Вывод совпадает с эталоном '{}'", expected_path);
+        } else {
+            print!("{}", diff::unified_diff(&expected, &actual, expected_path));
+            eprintln!("Ошибка: вывод отличается от эталона '{}'", expected_path);
+            process::exit(1);
         }
+    } else if cli.no_pager || !terminal::page(&rendered) {
+        print!("{}", rendered);
     }
 
-    if let Some(children) = graph.get(node) {
-        let new_prefix = if last { format!("{}    ", prefix) } else { format!("{}│   ", prefix) };
-        for (i, child) in children.iter().enumerate() {
-            let is_last = i == children.len() - 1;
-            print_ascii_tree(graph, child, &new_prefix, is_last, seen, current_depth + 1, max_depth);
+    // Политика проверяется после вывода, чтобы граф был виден и при нарушениях
+    if !config.policy.is_empty() {
+        let violations = policy::check(&config.policy, &graph, &config.name, &meta);
+        if let Some(path) = &config.sarif_path
+            && let Err(e) = sarif::write_sarif(path, &violations)
+        {
+            eprintln!("Предупреждение: {}", e);
+        }
+        if !violations.is_empty() {
+            eprint!("{}", policy::render_violations(&violations));
+            process::exit(1);
         }
     }
+
+    // Прерванный обход завершается кодом 130, как принято для Ctrl-C
+    if stats.interrupted {
+        process::exit(130);
+    }
+
+    // Граф с неразрешёнными пакетами (keep_going) неполон: это отдельный код завершения
+    if !stats.unresolved.is_empty() {
+        eprintln!("Ошибка: граф построен не полностью, не разрешено пакетов: {}", stats.unresolved.len());
+        process::exit(2);
+    }
 }
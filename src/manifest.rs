@@ -0,0 +1,594 @@
+use crate::graph::{self, Dep};
+use crate::source::{Candidate, Source};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Разделы манифеста с зависимостями и соответствующий вид зависимости
+const DEPENDENCY_TABLES: &[(&str, &str)] =
+    &[("dependencies", "normal"), ("build-dependencies", "build"), ("dev-dependencies", "dev")];
+
+/// Рабочее пространство: каталог и раздел [workspace] его манифеста
+struct Workspace {
+    dir: PathBuf,
+    table: Table,
+}
+
+/// Виды целей сборки, которые можно выбрать в target: `kind:name` (для lib — просто `lib`)
+const TARGET_KINDS: &[&str] = &["lib", "bin", "example", "bench", "test"];
+
+/// Пакет, загруженный из локального манифеста
+struct LocalPackage {
+    name: String,
+    version: String,
+    deps: Vec<Dep>,
+    /// Features, которые пакет запрашивает у обычных зависимостей: имя в манифесте -> запрос
+    requests: HashMap<String, FeatureRequest>,
+    /// Раздел [features]: feature -> что она включает
+    features: Table,
+    targets: Vec<Target>,
+}
+
+/// Запрос features у зависимости: `dep = { features = ["derive"], default-features = false }`
+#[derive(Debug, Clone)]
+struct FeatureRequest {
+    features: Vec<String>,
+    default_features: bool,
+}
+
+/// Feature пакета (или включённая опциональная зависимость, `dep:имя`), которую при сборке рабочего
+/// пространства получают и участники, сами её не запрашивающие: Cargo объединяет features каждого пакета
+/// по всем участникам, которые его используют
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnifiedFeature {
+    pub package: String,
+    pub feature: String,
+    /// Участники, которые включают feature и без остальных; пусто, если её включает только их сочетание
+    pub members: Vec<String>,
+    /// Участники, которые используют пакет, но без объединения обошлись бы без этой feature
+    pub extra_for: Vec<String>,
+}
+
+/// Шаг объединения features
+enum Step {
+    /// Feature пакета
+    Feature(String, String),
+    /// Зависимость пакета (по имени в манифесте) подключена
+    Dep(String, String),
+}
+
+/// Цель сборки пакета: библиотека, исполняемый файл, пример, бенчмарк или тест
+#[derive(Debug, Clone)]
+struct Target {
+    kind: &'static str,
+    name: String,
+    /// Features, без которых цель не собирается (required-features)
+    required_features: Vec<String>,
+}
+
+impl Target {
+    fn spec(&self) -> String {
+        if self.kind == "lib" { "lib".to_string() } else { format!("{}:{}", self.kind, self.name) }
+    }
+
+    /// Собирается ли цель с dev-зависимостями: примеры, бенчмарки и тесты — да, lib и bin — нет
+    fn uses_dev_dependencies(&self) -> bool {
+        matches!(self.kind, "example" | "bench" | "test")
+    }
+}
+
+/// Локальный источник: пакеты из Cargo.toml (пакета или рабочего пространства) и все пакеты,
+/// подключённые через `path = "..."`. Зависимости на них помечаются как локальные,
+/// а внешние зависимости запрашиваются у источника external (crates.io)
+pub struct ManifestSource<S: Source> {
+    /// Каноническое имя -> локальный пакет
+    locals: HashMap<String, LocalPackage>,
+    /// Уже прочитанные каталоги пакетов
+    loaded: HashSet<PathBuf>,
+    /// Канонические имена пакета манифеста и участников рабочего пространства
+    members: HashSet<String>,
+    pub external: S,
+}
+
+impl<S: Source> ManifestSource<S> {
+    /// Загружает манифест path (файл Cargo.toml или каталог с ним) вместе с path-зависимостями.
+    /// Для манифеста рабочего пространства загружаются все его участники
+    pub fn open(path: &str, external: S) -> Result<Self, String> {
+        let mut source =
+            ManifestSource { locals: HashMap::new(), loaded: HashSet::new(), members: HashSet::new(), external };
+        let manifest = manifest_path(Path::new(path));
+        let table = read_manifest(&manifest)?;
+        let dir = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+        if table.contains_key("package") {
+            let name = source.load_package(&dir)?;
+            source.members.insert(graph::canonical_name(&name));
+        }
+        if let Some(workspace) = table.get("workspace").and_then(Value::as_table) {
+            for member in workspace_members(&dir, workspace)? {
+                let name = source.load_package(&member)?;
+                source.members.insert(graph::canonical_name(&name));
+            }
+        }
+        Ok(source)
+    }
+
+    /// Есть ли локальный пакет name
+    pub fn contains(&self, name: &str) -> bool {
+        self.locals.contains_key(&graph::canonical_name(name))
+    }
+
+    /// Читает пакет из каталога dir и рекурсивно — его path-зависимости. Возвращает имя пакета
+    fn load_package(&mut self, dir: &Path) -> Result<String, String> {
+        let manifest = dir.join("Cargo.toml");
+        let table = read_manifest(&manifest)?;
+        let workspace = find_workspace(dir);
+        let package = table
+            .get("package")
+            .and_then(Value::as_table)
+            .ok_or_else(|| format!("В манифесте '{}' нет раздела [package]", manifest.display()))?;
+        let name = package
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("В манифесте '{}' не указано имя пакета", manifest.display()))?
+            .to_string();
+        let key = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        if !self.loaded.insert(key) {
+            return Ok(name);
+        }
+        let version = match package.get("version") {
+            Some(Value::String(v)) => v.clone(),
+            // version.workspace = true: версия задаётся в [workspace.package]
+            Some(Value::Table(_)) => workspace
+                .as_ref()
+                .and_then(|w| w.table.get("package")?.get("version")?.as_str().map(str::to_string))
+                .unwrap_or_else(|| "0.0.0".to_string()),
+            _ => "0.0.0".to_string(),
+        };
+
+        let mut sections: Vec<(&Table, &str)> = Vec::new();
+        for (section, kind) in DEPENDENCY_TABLES {
+            if let Some(deps) = table.get(*section).and_then(Value::as_table) {
+                sections.push((deps, kind));
+            }
+            // [target.'cfg(...)'.dependencies]: платформенные зависимости учитываются все
+            for target in table.get("target").and_then(Value::as_table).into_iter().flat_map(|t| t.values()) {
+                if let Some(deps) = target.get(*section).and_then(Value::as_table) {
+                    sections.push((deps, kind));
+                }
+            }
+        }
+
+        let mut deps = Vec::new();
+        let mut requests: HashMap<String, FeatureRequest> = HashMap::new();
+        for (section, kind) in sections {
+            for (key, spec) in section {
+                let (dep, request) = self.parse_dependency(dir, workspace.as_ref(), key, spec, kind)?;
+                // Платформенные записи об одной зависимости дополняют друг друга
+                if kind == "normal" {
+                    let merged = requests.entry(key.clone()).or_insert(FeatureRequest { features: Vec::new(), default_features: false });
+                    merged.features.extend(request.features);
+                    merged.default_features |= request.default_features;
+                }
+                deps.push(dep);
+            }
+        }
+        deps.sort_by(|a, b| (&a.name, &a.kind).cmp(&(&b.name, &b.kind)));
+        deps.dedup_by(|a, b| a.name == b.name && a.kind == b.kind);
+        let features = table.get("features").and_then(Value::as_table).cloned().unwrap_or_default();
+        let targets = discover_targets(dir, &table, &name);
+        self.locals.insert(
+            graph::canonical_name(&name),
+            LocalPackage { name: name.clone(), version, deps, requests, features, targets },
+        );
+        Ok(name)
+    }
+
+    /// Ограничивает граф локального пакета root целью сборки spec (`lib`, `bin:server`, `example:demo`):
+    /// опциональные зависимости, которые включают required-features цели, становятся обязательными.
+    /// Возвращает, нужны ли цели dev-зависимости пакета
+    pub fn select_target(&mut self, root: &str, spec: &str) -> Result<bool, String> {
+        let package = self
+            .locals
+            .get_mut(&graph::canonical_name(root))
+            .ok_or_else(|| format!("Пакет '{}' не найден в манифесте", root))?;
+        let (kind, name) = spec.split_once(':').unwrap_or((spec, ""));
+        if !TARGET_KINDS.contains(&kind) {
+            return Err(format!("Неизвестный вид цели '{}' (ожидается {})", kind, TARGET_KINDS.join(", ")));
+        }
+        let candidates: Vec<&Target> = package.targets.iter().filter(|t| t.kind == kind).collect();
+        let target = match (name, candidates.as_slice()) {
+            // Единственную цель вида можно не называть: `bin` вместо `bin:server`
+            ("", [only]) => *only,
+            _ => candidates.iter().copied().find(|t| kind == "lib" || t.name == name).ok_or_else(|| {
+                let available: Vec<String> = package.targets.iter().map(Target::spec).collect();
+                format!("У пакета {} нет цели '{}'; есть: {}", root, spec, available.join(", "))
+            })?,
+        };
+        let target = target.clone();
+        let enabled = enabled_dependencies(&package.features, &target.required_features);
+        for dep in &mut package.deps {
+            if enabled.contains(manifest_name(dep)) {
+                dep.optional = false;
+            }
+        }
+        Ok(target.uses_dev_dependencies())
+    }
+
+    /// Разбирает запись о зависимости `key = "1.0"` или
+    /// `key = { version, path, package, optional, features, default-features, workspace }`
+    fn parse_dependency(
+        &mut self,
+        dir: &Path,
+        workspace: Option<&Workspace>,
+        key: &str,
+        spec: &Value,
+        kind: &str,
+    ) -> Result<(Dep, FeatureRequest), String> {
+        let mut dep = Dep { kind: kind.to_string(), ..Dep::normal(key) };
+        let mut request = FeatureRequest { features: Vec::new(), default_features: true };
+        let mut spec = spec.clone();
+        let mut base = dir.to_path_buf();
+        // key.workspace = true: описание берётся из [workspace.dependencies], пути — относительно рабочего пространства
+        if spec.get("workspace").and_then(Value::as_bool) == Some(true)
+            && let Some(ws) = workspace
+            && let Some(inherited) = ws.table.get("dependencies").and_then(|d| d.get(key))
+        {
+            base = ws.dir.clone();
+            let optional = spec.get("optional").cloned();
+            let features = spec.get("features").and_then(Value::as_array).cloned().unwrap_or_default();
+            let mut table = match inherited {
+                Value::String(req) => Table::from_iter([("version".to_string(), Value::String(req.clone()))]),
+                Value::Table(table) => table.clone(),
+                _ => Table::new(),
+            };
+            // Опциональность задаётся участником, а не рабочим пространством
+            if let Some(optional) = optional {
+                table.insert("optional".to_string(), optional);
+            }
+            // Features участника добавляются к заданным в рабочем пространстве
+            if let Value::Array(inherited) = table.entry("features").or_insert_with(|| Value::Array(Vec::new())) {
+                inherited.extend(features);
+            }
+            spec = Value::Table(table);
+        }
+        match &spec {
+            Value::String(req) => dep.req = Some(req.clone()),
+            Value::Table(table) => {
+                dep.req = table.get("version").and_then(Value::as_str).map(str::to_string);
+                dep.optional = table.get("optional").and_then(Value::as_bool).unwrap_or(false);
+                request.features = table
+                    .get("features")
+                    .and_then(Value::as_array)
+                    .map(|features| features.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                    .unwrap_or_default();
+                request.default_features = table
+                    .get("default-features")
+                    .or_else(|| table.get("default_features"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+                if let Some(package) = table.get("package").and_then(Value::as_str) {
+                    dep.name = package.to_string();
+                    dep.alias = Some(key.to_string());
+                }
+                if let Some(path) = table.get("path").and_then(Value::as_str) {
+                    // Имя локального пакета определяется его собственным манифестом
+                    dep.name = self.load_package(&base.join(path))?;
+                    dep.local = true;
+                }
+            }
+            _ => {}
+        }
+        Ok((dep, request))
+    }
+
+    /// Объединяет features, как Cargo при сборке всего рабочего пространства: каждый пакет собирается
+    /// с объединением features, которые запрашивают все участники (напрямую и через цепочки локальных
+    /// пакетов). Возвращает features, которые объединение добавляет участникам сверх их собственных.
+    /// Учитываются обычные зависимости; features внешних пакетов не раскрываются — их таблицы не загружаются.
+    /// apply — сделать включённые объединением опциональные зависимости локальных пакетов обязательными
+    pub fn unify_features(&mut self, apply: bool) -> Vec<UnifiedFeature> {
+        let mut members: Vec<&str> =
+            self.members.iter().filter_map(|m| self.locals.get(m)).map(|p| p.name.as_str()).collect();
+        members.sort_unstable();
+        let solo: Vec<BTreeMap<String, BTreeSet<String>>> = members.iter().map(|m| self.activate(&[m])).collect();
+        let unified = self.activate(&members);
+
+        let mut extra = Vec::new();
+        for (package, items) in &unified {
+            for item in items {
+                let users = || members.iter().zip(&solo).filter(|(_, active)| active.contains_key(package));
+                let including: Vec<String> =
+                    users().filter(|(_, active)| active[package].contains(item)).map(|(m, _)| m.to_string()).collect();
+                let extra_for: Vec<String> = users().map(|(m, _)| m.to_string()).filter(|m| !including.contains(m)).collect();
+                if !extra_for.is_empty() {
+                    extra.push(UnifiedFeature { package: package.clone(), feature: item.clone(), members: including, extra_for });
+                }
+            }
+        }
+
+        if apply {
+            for (package, items) in &unified {
+                let Some(local) = self.locals.get_mut(&graph::canonical_name(package)) else { continue };
+                for dep in &mut local.deps {
+                    if items.contains(&format!("dep:{}", manifest_name(dep))) {
+                        dep.optional = false;
+                    }
+                }
+            }
+        }
+        extra
+    }
+
+    /// Features и опциональные зависимости (`dep:имя`), включённые при сборке участников members
+    /// с features по умолчанию: пакет -> включённое. Пакет есть в результате, если он участвует в сборке
+    fn activate(&self, members: &[&str]) -> BTreeMap<String, BTreeSet<String>> {
+        let mut active: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut connected: HashSet<(String, String)> = HashSet::new();
+        // Отложенные `dep?/feature`: пакет, зависимость, feature — включаются вместе с зависимостью
+        let mut weak: Vec<(String, String, String)> = Vec::new();
+        let mut stack: Vec<Step> = members.iter().map(|m| Step::Feature(m.to_string(), "default".to_string())).collect();
+        for member in members {
+            self.enter(member, &mut active, &mut stack);
+        }
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Dep(package, name) => {
+                    let Some(dep) = self.dependency(&package, &name) else { continue };
+                    if !connected.insert((package.clone(), name.clone())) {
+                        continue;
+                    }
+                    if dep.optional {
+                        active.entry(package.clone()).or_default().insert(format!("dep:{}", name));
+                    }
+                    let target = dep.name.clone();
+                    self.enter(&target, &mut active, &mut stack);
+                    if let Some(request) = self.locals[&graph::canonical_name(&package)].requests.get(&name) {
+                        stack.extend(request.features.iter().map(|f| Step::Feature(target.clone(), f.clone())));
+                        if request.default_features {
+                            stack.push(Step::Feature(target.clone(), "default".to_string()));
+                        }
+                    }
+                    for (_, _, feature) in weak.iter().filter(|(p, d, _)| *p == package && *d == name) {
+                        stack.push(Step::Feature(target.clone(), feature.clone()));
+                    }
+                }
+                Step::Feature(package, feature) => {
+                    self.enter(&package, &mut active, &mut stack);
+                    let Some(local) = self.locals.get(&graph::canonical_name(&package)) else {
+                        active.entry(package).or_default().insert(feature);
+                        continue;
+                    };
+                    let Some(items) = local.features.get(&feature) else {
+                        // Неявная feature опциональной зависимости
+                        if feature != "default" {
+                            stack.push(Step::Dep(package, feature));
+                        }
+                        continue;
+                    };
+                    if !active.entry(package.clone()).or_default().insert(feature) {
+                        continue;
+                    }
+                    for item in items.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                        if let Some(dep) = item.strip_prefix("dep:") {
+                            stack.push(Step::Dep(package.clone(), dep.to_string()));
+                        } else if let Some((dep, dep_feature)) = item.split_once('/') {
+                            match dep.strip_suffix('?') {
+                                Some(dep) if connected.contains(&(package.clone(), dep.to_string())) => {
+                                    if let Some(target) = self.dependency(&package, dep) {
+                                        stack.push(Step::Feature(target.name.clone(), dep_feature.to_string()));
+                                    }
+                                }
+                                Some(dep) => weak.push((package.clone(), dep.to_string(), dep_feature.to_string())),
+                                None => {
+                                    stack.push(Step::Dep(package.clone(), dep.to_string()));
+                                    if let Some(target) = self.dependency(&package, dep) {
+                                        stack.push(Step::Feature(target.name.clone(), dep_feature.to_string()));
+                                    }
+                                }
+                            }
+                        } else if local.features.contains_key(item) {
+                            stack.push(Step::Feature(package.clone(), item.to_string()));
+                        } else {
+                            stack.push(Step::Dep(package.clone(), item.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+        active
+    }
+
+    /// Отмечает пакет участвующим в сборке; у локального пакета подключаются обязательные зависимости
+    fn enter(&self, package: &str, active: &mut BTreeMap<String, BTreeSet<String>>, stack: &mut Vec<Step>) {
+        if active.contains_key(package) {
+            return;
+        }
+        active.insert(package.to_string(), BTreeSet::new());
+        if let Some(local) = self.locals.get(&graph::canonical_name(package)) {
+            for dep in local.deps.iter().filter(|d| d.kind == "normal" && !d.optional) {
+                stack.push(Step::Dep(package.to_string(), manifest_name(dep).to_string()));
+            }
+        }
+    }
+
+    /// Обычная зависимость локального пакета по имени в манифесте
+    fn dependency(&self, package: &str, name: &str) -> Option<&Dep> {
+        let local = self.locals.get(&graph::canonical_name(package))?;
+        local.deps.iter().find(|d| d.kind == "normal" && manifest_name(d) == name)
+    }
+}
+
+/// Имя, под которым зависимость упоминается в манифесте и таблице features (для переименованных — alias)
+fn manifest_name(dep: &Dep) -> &str {
+    dep.alias.as_deref().unwrap_or(&dep.name)
+}
+
+impl<S: Source> Source for ManifestSource<S> {
+    fn dependencies(&mut self, name: &str, version: Option<&str>) -> Result<Vec<Dep>, String> {
+        match self.locals.get(&graph::canonical_name(name)) {
+            Some(package) => Ok(package.deps.clone()),
+            None => self.external.dependencies(name, version),
+        }
+    }
+
+    fn resolve_version(&mut self, name: &str) -> Result<Option<String>, String> {
+        match self.locals.get(&graph::canonical_name(name)) {
+            Some(package) => Ok(Some(package.version.clone())),
+            None => self.external.resolve_version(name),
+        }
+    }
+
+    fn prefetch(&mut self, names: &[String]) -> Result<(), String> {
+        let external: Vec<String> = names.iter().filter(|name| !self.contains(name)).cloned().collect();
+        self.external.prefetch(&external)
+    }
+
+    fn is_member(&self, name: &str) -> bool {
+        self.members.contains(&graph::canonical_name(name))
+    }
+
+    fn persist(&mut self) -> Result<(), String> {
+        self.external.persist()
+    }
+
+    fn candidates(&mut self, name: &str) -> Result<Vec<Candidate>, String> {
+        match self.locals.get(&graph::canonical_name(name)) {
+            Some(package) => Ok(vec![Candidate { num: package.version.clone(), yanked: false }]),
+            None => self.external.candidates(name),
+        }
+    }
+}
+
+/// Цели сборки пакета из каталога dir: описанные в манифесте ([lib], [[bin]], [[example]], [[bench]], [[test]])
+/// и найденные по соглашениям Cargo (src/lib.rs, src/main.rs, src/bin/, examples/, benches/, tests/),
+/// если автоматический поиск не отключён (autobins = false и т.п.)
+fn discover_targets(dir: &Path, table: &Table, package: &str) -> Vec<Target> {
+    let package_table = table.get("package").and_then(Value::as_table);
+    let auto = |key: &str| package_table.and_then(|p| p.get(key)).and_then(Value::as_bool).unwrap_or(true);
+    let mut targets = Vec::new();
+
+    let lib = table.get("lib").and_then(Value::as_table);
+    if lib.is_some() || dir.join("src/lib.rs").is_file() {
+        let name = lib.and_then(|l| l.get("name")).and_then(Value::as_str).map(str::to_string);
+        targets.push(Target { kind: "lib", name: name.unwrap_or_else(|| package.replace('-', "_")), required_features: Vec::new() });
+    }
+
+    for (kind, section, auto_key, auto_dir) in [
+        ("bin", "bin", "autobins", "src/bin"),
+        ("example", "example", "autoexamples", "examples"),
+        ("bench", "bench", "autobenches", "benches"),
+        ("test", "test", "autotests", "tests"),
+    ] {
+        let mut found: Vec<Target> = Vec::new();
+        if auto(auto_key) {
+            if kind == "bin" && dir.join("src/main.rs").is_file() {
+                found.push(Target { kind, name: package.to_string(), required_features: Vec::new() });
+            }
+            found.extend(auto_target_names(&dir.join(auto_dir)).into_iter().map(|name| Target { kind, name, required_features: Vec::new() }));
+        }
+        for entry in table.get(section).and_then(Value::as_array).into_iter().flatten() {
+            let Some(name) = entry.get("name").and_then(Value::as_str) else { continue };
+            let required_features = entry
+                .get("required-features")
+                .and_then(Value::as_array)
+                .map(|features| features.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                .unwrap_or_default();
+            // Явное описание заменяет найденную по соглашениям цель с тем же именем
+            found.retain(|t| t.name != name);
+            found.push(Target { kind, name: name.to_string(), required_features });
+        }
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        targets.extend(found);
+    }
+    targets
+}
+
+/// Имена целей в каталоге по соглашениям Cargo: файлы `name.rs` и подкаталоги `name/main.rs`
+fn auto_target_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension().is_some_and(|ext| ext == "rs") {
+                path.file_stem()?.to_str().map(str::to_string)
+            } else if path.join("main.rs").is_file() {
+                path.file_name()?.to_str().map(str::to_string)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Зависимости (по имени в манифесте), которые включают features: `dep:name`, `name/feature`
+/// и неявные features опциональных зависимостей. `name?/feature` зависимость не включает
+fn enabled_dependencies(table: &Table, features: &[String]) -> HashSet<String> {
+    let mut enabled = HashSet::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = features.iter().map(String::as_str).collect();
+    while let Some(feature) = stack.pop() {
+        if !seen.insert(feature) {
+            continue;
+        }
+        if let Some(dep) = feature.strip_prefix("dep:") {
+            enabled.insert(dep.to_string());
+        } else if let Some((dep, _)) = feature.split_once('/') {
+            if !dep.ends_with('?') {
+                enabled.insert(dep.to_string());
+            }
+        } else if let Some(items) = table.get(feature).and_then(Value::as_array) {
+            stack.extend(items.iter().filter_map(Value::as_str));
+        } else {
+            // Feature, которой нет в [features], — неявная feature опциональной зависимости
+            enabled.insert(feature.to_string());
+        }
+    }
+    enabled
+}
+
+/// Путь к Cargo.toml: path может указывать на сам файл или на каталог пакета
+fn manifest_path(path: &Path) -> PathBuf {
+    if path.is_dir() { path.join("Cargo.toml") } else { path.to_path_buf() }
+}
+
+fn read_manifest(path: &Path) -> Result<Table, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("Ошибка чтения манифеста '{}': {}", path.display(), e))?;
+    raw.parse::<Table>().map_err(|e| format!("Ошибка разбора манифеста '{}': {}", path.display(), e))
+}
+
+/// Ближайшее рабочее пространство, содержащее каталог dir
+fn find_workspace(dir: &Path) -> Option<Workspace> {
+    let dir = fs::canonicalize(dir).ok()?;
+    dir.ancestors().find_map(|ancestor| {
+        match read_manifest(&ancestor.join("Cargo.toml")).ok()?.remove("workspace")? {
+            Value::Table(table) => Some(Workspace { dir: ancestor.to_path_buf(), table }),
+            _ => None,
+        }
+    })
+}
+
+/// Каталоги участников рабочего пространства; поддерживаются шаблоны вида `crates/*`
+fn workspace_members(dir: &Path, workspace: &Table) -> Result<Vec<PathBuf>, String> {
+    let mut members = Vec::new();
+    for member in workspace.get("members").and_then(Value::as_array).into_iter().flatten() {
+        let Some(member) = member.as_str() else { continue };
+        match member.strip_suffix("/*") {
+            Some(parent) => {
+                let parent = dir.join(parent);
+                let entries = fs::read_dir(&parent)
+                    .map_err(|e| format!("Ошибка чтения каталога '{}': {}", parent.display(), e))?;
+                let mut found: Vec<PathBuf> = entries
+                    .filter_map(|entry| Some(entry.ok()?.path()))
+                    .filter(|path| path.join("Cargo.toml").is_file())
+                    .collect();
+                found.sort();
+                members.extend(found);
+            }
+            None => members.push(dir.join(member)),
+        }
+    }
+    Ok(members)
+}
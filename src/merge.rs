@@ -0,0 +1,110 @@
+use crate::analysis;
+use crate::cache::SharedCache;
+use crate::config::{Config, MergeSource, Root};
+use crate::graph::{self, Dep, Graph};
+use crate::report::RunStats;
+use crate::roots;
+use std::collections::BTreeMap;
+use std::thread;
+
+/// Конфигурация обхода источника: общая конфигурация с его корнем, видом источника и репозиторием
+fn source_config(config: &Config, source: &MergeSource) -> Result<Config, String> {
+    let mut base = config.clone();
+    base.merge.clear();
+    base.source = source.source;
+    if let Some(repository) = &source.repository {
+        base.repository = repository.clone();
+    }
+    if let Some(mode) = &source.test_repo_mode {
+        base.test_repo_mode = mode.clone();
+    }
+    roots::root_config(&base, &Root { name: source.name.clone(), version: source.version.clone() })
+}
+
+/// Строит графы всех источников из config.merge параллельно и объединяет их в один граф
+/// с общим корнем config.name, зависящим от корней источников.
+/// Метки источников, в графах которых встретился пакет, записываются в stats.node_sources
+pub fn build(config: &Config, shared: Option<&SharedCache>, stats: &mut RunStats) -> Result<Graph, String> {
+    let configs: Vec<Config> = config.merge.iter().map(|source| source_config(config, source)).collect::<Result<_, _>>()?;
+    let opened = match shared {
+        Some(_) => None,
+        None => configs.iter().map(roots::shared_cache).find_map(Result::transpose).transpose()?,
+    };
+    let shared = shared.or(opened.as_ref());
+
+    let results: Vec<Result<(Graph, RunStats), String>> = thread::scope(|scope| {
+        let handles: Vec<_> = configs
+            .iter()
+            .map(|config| {
+                scope.spawn(move || {
+                    let mut stats = RunStats::default();
+                    graph::build_graph_with_cache(config, shared, &mut stats, &mut |_| {}).map(|graph| (graph, stats))
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err("обход завершился аварийно".to_string())))
+            .collect()
+    });
+
+    let mut graphs = Vec::new();
+    for ((source, config), result) in config.merge.iter().zip(&configs).zip(results) {
+        let (graph, source_stats) = result.map_err(|e| format!("источник {} ({} v{}): {}", source.label(), config.name, config.version, e))?;
+        stats.absorb(source_stats);
+        graphs.push((source.label(), config.name.as_str(), graph));
+    }
+    Ok(merge_graphs(&config.name, &graphs, stats))
+}
+
+/// Объединяет графы источников (метка, корень, граф). Если у пакета в разных источниках разные
+/// зависимости (например, разрешены разные версии), в граф попадает их объединение:
+/// из одноимённых рёбер одного вида остаётся ребро первого источника, а расхождение становится предупреждением
+fn merge_graphs(root: &str, graphs: &[(&str, &str, Graph)], stats: &mut RunStats) -> Graph {
+    let mut merged = Graph::new();
+    let mut origin: BTreeMap<&str, &str> = BTreeMap::new();
+    let root_deps: &mut Vec<Dep> = merged.entry(root.to_string()).or_default();
+    for (_, source_root, _) in graphs {
+        if !root_deps.iter().any(|dep| dep.name == *source_root) {
+            root_deps.push(Dep::normal(source_root));
+        }
+    }
+
+    for (label, _, graph) in graphs {
+        for name in analysis::all_nodes(graph) {
+            let labels = stats.node_sources.entry(name.to_string()).or_default();
+            if !labels.iter().any(|l| l == label) {
+                labels.push(label.to_string());
+            }
+        }
+        for (name, deps) in graph {
+            match merged.get_mut(name) {
+                Some(existing) if name != root => {
+                    if !same_dependencies(existing, deps) {
+                        stats.warn(format!(
+                            "зависимости '{}' в источниках {} и {} различаются, в графе их объединение",
+                            name, origin[name.as_str()], label
+                        ));
+                    }
+                    for dep in deps {
+                        if !existing.iter().any(|e| e.name == dep.name && e.kind == dep.kind) {
+                            existing.push(dep.clone());
+                        }
+                    }
+                }
+                // Пакет с именем общего корня не может стать вторым корнем
+                Some(_) => stats.warn(format!("пакет '{}' совпадает с именем общего корня и пропущен", name)),
+                None => {
+                    merged.insert(name.clone(), deps.clone());
+                    origin.insert(name, label);
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Совпадают ли наборы зависимостей без учёта порядка
+fn same_dependencies(a: &[Dep], b: &[Dep]) -> bool {
+    a.len() == b.len() && a.iter().all(|dep| b.contains(dep))
+}
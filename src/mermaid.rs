@@ -0,0 +1,68 @@
+use crate::export;
+use crate::graph::Graph;
+use std::fmt::Write;
+
+/// Рендерит граф в формате Mermaid flowchart (например, для вставки в Markdown на GitHub).
+/// Вид зависимости передаётся стрелкой (`==>` build, `-.->` dev и опциональные), подписью и цветом.
+/// Заголовок выводится заголовком диаграммы, footer — отдельной подписью без рамки
+pub fn render_mermaid(graph: &Graph, root: &str, version: &str, captions: &export::Captions) -> String {
+    let index = export::NodeIndex::new(graph);
+    let nodes = &index.nodes;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "---");
+    let _ = writeln!(out, "title: {}", yaml_string(&captions.title(root, version)));
+    let _ = writeln!(out, "---");
+    let _ = writeln!(out, "flowchart LR");
+    for (i, name) in nodes.iter().enumerate() {
+        let _ = writeln!(out, "    n{}[\"{}\"]", i, escape(captions.label(name, root)));
+    }
+    if nodes.contains(&root) {
+        let _ = writeln!(out, "    style n{} stroke-width:3px", index.id(root));
+    }
+    if let Some(footer) = &captions.footer {
+        let _ = writeln!(out, "    footer[\"{}\"]", escape(footer));
+        let _ = writeln!(out, "    style footer fill:none,stroke:none");
+    }
+
+    // linkStyle ссылается на рёбра по порядковому номеру
+    let mut edge_index = 0;
+    for name in nodes {
+        for dep in graph.get(*name).into_iter().flatten() {
+            let style = export::edge_style(dep);
+            let arrow = match (style.line, dep.kind.as_str()) {
+                ("solid", "build") => "==>",
+                ("solid", _) => "-->",
+                _ => "-.->",
+            };
+            let label = style.label.as_ref().map(|l| format!("|\"{}\"|", escape(l))).unwrap_or_default();
+            let _ = writeln!(out, "    n{} {}{} n{}", index.id(name), arrow, label, index.id(&dep.name));
+            if style.color != "black" {
+                let _ = writeln!(out, "    linkStyle {} stroke:{}", edge_index, style.color);
+            }
+            edge_index += 1;
+        }
+    }
+    out
+}
+
+/// Кавычки внутри подписей Mermaid записываются HTML-сущностью
+fn escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+/// Строка YAML в двойных кавычках: заголовок с `:` или `#` не ломает front matter диаграммы
+fn yaml_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,430 @@
+use crate::analysis;
+use crate::cache::{self, MemoryCache};
+use crate::config::{Config, NodeMetric, SourceKind};
+use crate::graph::{self, Graph};
+use crate::registry::Registry;
+use crate::report::{self, RunStats};
+use crate::source::{self, Features, Source};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Дополнительные сведения о пакете, получаемые с crates.io после построения графа
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct NodeMeta {
+    /// Логины владельцев пакета
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owners: Option<Vec<String>>,
+    /// Краткое описание пакета
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// URL репозитория исходного кода
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// Дата публикации последней версии
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_release: Option<String>,
+    /// Число загрузок за всё время
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<u64>,
+    /// Лицензия последней версии (выражение SPDX)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Размер архива .crate последней версии в байтах
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crate_size: Option<u64>,
+    /// Число пакетов crates.io, зависящих от пакета
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependents: Option<u64>,
+    /// Последняя версия пакета (запрашивается только для отчёта "outdated")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_version: Option<String>,
+    /// Таблица features (запрашивается только для корня в формате "features")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<Features>,
+    /// Категории crates.io (`cryptography`, `development-tools::ffi`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<String>>,
+    /// Ключевые слова пакета на crates.io
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keywords: Option<Vec<String>>,
+    /// Ссылка на документацию пакета на docs.rs (docs_links)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+    /// Собралась ли документация на docs.rs (fetch_docs_status)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_built: Option<bool>,
+    /// Заметка о пакете из файла notes_path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Ошибка, из-за которой пакет не удалось разрешить при keep_going
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unresolved: Option<String>,
+    /// Метки источников объединённого графа (merge), в которых встретился пакет
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Vec<String>>,
+    /// Версия, с которой пакет раскрывался при обходе (show_requirements)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Участники группового узла (см. group::grouped)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<String>>,
+}
+
+impl NodeMeta {
+    /// Нет ни одного сведения о пакете
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_none()
+            && self.description.is_none()
+            && self.repository.is_none()
+            && self.latest_release.is_none()
+            && self.downloads.is_none()
+            && self.latest_version.is_none()
+            && self.license.is_none()
+            && self.crate_size.is_none()
+            && self.dependents.is_none()
+            && self.features.is_none()
+            && self.members.is_none()
+            && self.docs_url.is_none()
+            && self.docs_built.is_none()
+            && self.note.is_none()
+            && self.unresolved.is_none()
+            && self.sources.is_none()
+            && self.version.is_none()
+            && self.categories.is_none()
+            && self.keywords.is_none()
+    }
+
+    /// Метки из tags, которым соответствует пакет: категория (целиком или любая её часть,
+    /// `ffi` подходит к `development-tools::ffi`) или ключевое слово
+    pub fn matching_tags<'a>(&self, tags: &'a [String]) -> Vec<&'a str> {
+        let categories = self.categories.iter().flatten();
+        let keywords = self.keywords.iter().flatten();
+        tags.iter()
+            .filter(|tag| {
+                categories.clone().any(|c| c == *tag || c.split("::").any(|part| part == tag.as_str()))
+                    || keywords.clone().any(|k| k == *tag)
+            })
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Сведения о пакетах графа: имя -> метаданные
+pub type Metadata = BTreeMap<String, NodeMeta>;
+
+/// Собирает метаданные для всех пакетов графа, если они запрошены конфигурацией, и добавляет
+/// к ним заметки из notes_path, ошибки неразрешённых пакетов, источники объединённого графа
+/// и разрешённые версии (show_requirements)
+pub fn collect(config: &Config, graph: &Graph, stats: &mut RunStats) -> Metadata {
+    let mut meta = collect_fetched(config, graph, stats);
+    for node in &stats.unresolved {
+        meta.entry(node.name.clone()).or_default().unresolved = Some(node.error.clone());
+    }
+    for (name, labels) in &stats.node_sources {
+        meta.entry(name.clone()).or_default().sources = Some(labels.clone());
+    }
+    if config.show_requirements {
+        for (name, version) in &stats.node_versions {
+            meta.entry(name.clone()).or_default().version = Some(version.clone());
+        }
+    }
+    if let Some(path) = &config.notes_path {
+        match load_notes(path) {
+            Ok(notes) => {
+                let nodes = analysis::all_nodes(graph);
+                for (name, note) in notes.into_iter().filter(|(name, _)| nodes.contains(&name.as_str())) {
+                    meta.entry(name).or_default().note = Some(note);
+                }
+            }
+            Err(e) => stats.warn(e),
+        }
+    }
+    meta
+}
+
+/// Заметки о пакетах: JSON-объект "имя пакета" -> "заметка"
+pub fn load_notes(path: &str) -> Result<BTreeMap<String, String>, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("Ошибка чтения заметок '{}': {}", path, e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Ошибка разбора заметок '{}': {}", path, e))
+}
+
+/// Метаданные с crates.io и docs.rs.
+/// В тестовом режиме данных о пакетах нет, и метаданные остаются пустыми.
+/// Для импортированного JSON-графа используются сохранённые в нём сведения
+fn collect_fetched(config: &Config, graph: &Graph, stats: &mut RunStats) -> Metadata {
+    if config.source == SourceKind::Json {
+        return match source::load_json_graph(&config.repository) {
+            Ok(imported) => imported.meta.into_iter().filter(|(name, _)| graph.contains_key(name)).collect(),
+            Err(e) => {
+                stats.warn(format!("не удалось прочитать сведения о пакетах: {}", e));
+                Metadata::new()
+            }
+        };
+    }
+
+    let mut meta = Metadata::new();
+    if config.output_format == "features" {
+        match root_features(config) {
+            Ok(features) => meta.entry(config.name.clone()).or_default().features = Some(features),
+            Err(e) => stats.warn(format!("не удалось получить features '{}': {}", config.name, e)),
+        }
+    }
+
+    if config.output_format == "outdated" {
+        collect_latest_versions(config, graph, &mut meta, stats);
+    }
+
+    // Ссылки на docs.rs строятся без запросов, поэтому доступны и в тестовом режиме
+    if config.docs_links || config.fetch_docs_status {
+        for name in docs_crates(config, graph) {
+            meta.entry(name.to_string()).or_default().docs_url = Some(docs_url(name, docs_version(config, stats, name)));
+        }
+    }
+
+    let want_owners = config.fetch_owners || config.output_format == "owners";
+    // Число загрузок, лицензия и размер архива приходят вместе с описанием пакета
+    let want_details = config.fetch_metadata
+        || config.output_format == "footprint"
+        || config.output_format == "deny"
+        || config.stale_after_days.is_some()
+        || !(config.highlight_tags.is_empty() && config.fold_tags.is_empty() && config.exclude_tags.is_empty())
+        || config.node_size_metric == Some(NodeMetric::Downloads)
+        || !config.policy.deny_licenses.is_empty();
+    if config.test_repo_mode == "test" || !(want_owners || want_details || config.fetch_dependents || config.fetch_docs_status) {
+        return meta;
+    }
+
+    let mut registry = match Registry::new(config, Box::new(MemoryCache::default())) {
+        Ok(registry) => registry,
+        Err(e) => {
+            stats.warn(e);
+            return meta;
+        }
+    };
+    for name in analysis::all_nodes(graph) {
+        if want_owners {
+            match registry.owners(name) {
+                Ok(owners) => meta.entry(name.to_string()).or_default().owners = Some(owners),
+                Err(e) => stats.warn(format!("не удалось получить владельцев '{}': {}", name, e)),
+            }
+        }
+        if config.fetch_dependents {
+            match registry.dependents(name) {
+                Ok(count) => meta.entry(name.to_string()).or_default().dependents = Some(count),
+                Err(e) => stats.warn(format!("не удалось получить число зависимых от '{}': {}", name, e)),
+            }
+        }
+        if want_details {
+            match registry.crate_details(name) {
+                Ok(details) => {
+                    let entry = meta.entry(name.to_string()).or_default();
+                    entry.description = details.description;
+                    entry.repository = details.repository;
+                    entry.latest_release = details.latest_release;
+                    entry.downloads = Some(details.downloads);
+                    entry.license = details.license;
+                    entry.crate_size = details.crate_size;
+                    entry.categories = Some(details.categories);
+                    entry.keywords = Some(details.keywords);
+                }
+                Err(e) => stats.warn(format!("не удалось получить сведения о '{}': {}", name, e)),
+            }
+        }
+    }
+    if config.fetch_docs_status {
+        for name in docs_crates(config, graph) {
+            match registry.docs_status(name, docs_version(config, stats, name)) {
+                Ok(built) => meta.entry(name.to_string()).or_default().docs_built = Some(built),
+                Err(e) => stats.warn(format!("не удалось получить статус документации '{}': {}", name, e)),
+            }
+        }
+    }
+    stats.http_requests += registry.http_requests;
+    stats.bytes_received += registry.bytes_received();
+    meta
+}
+
+/// Пакеты графа с категорией или ключевым словом из tags (корень не учитывается)
+pub fn tagged<'a>(graph: &'a Graph, root: &str, meta: &Metadata, tags: &[String]) -> Vec<&'a str> {
+    if tags.is_empty() {
+        return Vec::new();
+    }
+    analysis::all_nodes(graph)
+        .into_iter()
+        .filter(|name| *name != root && meta.get(*name).is_some_and(|m| !m.matching_tags(tags).is_empty()))
+        .collect()
+}
+
+/// Пакеты, документация которых публикуется на docs.rs: все, кроме локальных (path) пакетов манифеста
+fn docs_crates<'a>(config: &Config, graph: &'a Graph) -> Vec<&'a str> {
+    let mut local: Vec<&str> = graph.values().flatten().filter(|dep| dep.local).map(|dep| dep.name.as_str()).collect();
+    if config.source == SourceKind::Manifest {
+        local.push(&config.name);
+    }
+    analysis::all_nodes(graph).into_iter().filter(|name| !local.contains(name)).collect()
+}
+
+/// Версия, документация которой проверяется: у корня — заданная, у остальных пакетов — выбранная при обходе.
+/// Версии тестового графа не настоящие, поэтому для него, как и для пакетов без известной версии, берётся "latest"
+pub fn docs_version<'a>(config: &'a Config, stats: &'a RunStats, name: &str) -> &'a str {
+    if name == config.name {
+        return &config.version;
+    }
+    let crawled = if config.test_repo_mode == "test" { None } else { stats.node_versions.get(name) };
+    crawled.map_or("latest", String::as_str)
+}
+
+/// Ссылка на документацию версии пакета на docs.rs
+pub fn docs_url(name: &str, version: &str) -> String {
+    format!("https://docs.rs/{}/{}", name, version)
+}
+
+/// Раздел вывода со списком пакетов, документация которых не собралась на docs.rs; None, если таких нет
+pub fn render_docs_section(graph: &Graph, meta: &Metadata) -> Option<String> {
+    let broken: Vec<&str> = analysis::all_nodes(graph)
+        .into_iter()
+        .filter(|name| meta.get(*name).and_then(|m| m.docs_built) == Some(false))
+        .collect();
+    if broken.is_empty() {
+        return None;
+    }
+    let mut out = format!("Документация не собрана на docs.rs: {}\n", broken.len());
+    for name in broken {
+        out.push_str(&format!("  {}\n", name));
+    }
+    Some(out)
+}
+
+/// Последние версии всех пакетов графа: из тестового файла или с crates.io.
+/// Запросы идут через настроенный кэш, поэтому после обхода графа они обычно не требуют обращений к сети
+fn collect_latest_versions(config: &Config, graph: &Graph, meta: &mut Metadata, stats: &mut RunStats) {
+    if config.test_repo_mode == "test" {
+        match source::load_test_graph(&config.repository) {
+            Ok(mut test_source) => resolve_latest(&mut test_source, graph, meta, stats),
+            Err(e) => stats.warn(e),
+        }
+        return;
+    }
+    match cache::open(config).and_then(|cache| Registry::new(config, cache)) {
+        Ok(mut registry) => {
+            resolve_latest(&mut registry, graph, meta, stats);
+            if let Err(e) = registry.flush_cache() {
+                stats.warn(e);
+            }
+            stats.http_requests += registry.http_requests;
+            stats.bytes_received += registry.bytes_received();
+            stats.cache_hits += registry.cache_hits;
+            stats.cache_misses += registry.cache_misses;
+        }
+        Err(e) => stats.warn(e),
+    }
+}
+
+fn resolve_latest(source: &mut dyn Source, graph: &Graph, meta: &mut Metadata, stats: &mut RunStats) {
+    for name in analysis::all_nodes(graph) {
+        match source.resolve_version(name) {
+            Ok(Some(latest)) => meta.entry(name.to_string()).or_default().latest_version = Some(latest),
+            Ok(None) => {}
+            Err(e) => stats.warn(format!("не удалось получить последнюю версию '{}': {}", name, e)),
+        }
+    }
+}
+
+/// Таблица features корневого пакета: из тестового файла или с crates.io
+fn root_features(config: &Config) -> Result<Features, String> {
+    if config.test_repo_mode == "test" {
+        let mut test_source = source::load_test_graph(&config.repository)?;
+        return Ok(test_source.features.remove(&graph::canonical_name(&config.name)).unwrap_or_default());
+    }
+    Registry::new(config, Box::new(MemoryCache::default()))?.features(&config.name, &config.version)
+}
+
+/// Рендерит отчёт о владельцах: владельцы каждого пакета и пакеты каждого владельца.
+/// Пакеты с единственным владельцем выделяются отдельно — это оценка bus factor
+pub fn render_owners_report(graph: &Graph, meta: &Metadata, root: &str, version: &str) -> String {
+    let mut out = format!("Владельцы пакетов для {} v{}:\n", root, version);
+    let mut by_owner: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut single_owner = Vec::new();
+    let mut unknown = Vec::new();
+
+    for name in analysis::all_nodes(graph) {
+        match meta.get(name).and_then(|m| m.owners.as_ref()) {
+            Some(owners) => {
+                out.push_str(&format!("{}: {}\n", name, owners.join(", ")));
+                for owner in owners {
+                    by_owner.entry(owner).or_default().push(name);
+                }
+                if owners.len() == 1 {
+                    single_owner.push(name);
+                }
+            }
+            None => unknown.push(name),
+        }
+    }
+
+    out.push_str("\nПакеты по владельцам:\n");
+    let mut owners: Vec<_> = by_owner.into_iter().collect();
+    owners.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+    for (owner, crates) in owners {
+        out.push_str(&format!("{} ({}): {}\n", owner, crates.len(), crates.join(", ")));
+    }
+
+    out.push_str(&format!("\nПакеты с единственным владельцем ({}):\n", single_owner.len()));
+    for name in single_owner {
+        out.push_str(&format!("- {}\n", name));
+    }
+    if !unknown.is_empty() {
+        out.push_str(&format!("\nВладельцы неизвестны ({}): {}\n", unknown.len(), unknown.join(", ")));
+    }
+    out
+}
+
+/// Пакет, давно не выпускавший релизов
+pub struct Stale {
+    /// Дата последнего релиза (YYYY-MM-DD)
+    pub released: String,
+}
+
+/// Момент, относительно которого пакеты считаются давно не обновлявшимися: дата as_of,
+/// если граф восстанавливается на прошедшую дату, иначе текущее время
+pub fn stale_reference(config: &Config) -> SystemTime {
+    let as_of = config.as_of.as_deref().and_then(report::days_from_date);
+    match as_of {
+        Some(days) => UNIX_EPOCH + Duration::from_secs(days.max(0) as u64 * 86_400),
+        None => SystemTime::now(),
+    }
+}
+
+/// Пакеты графа, последний релиз которых старше threshold_days дней на момент now.
+/// Учитываются только пакеты с известной датой релиза (fetch_metadata или stale_after_days)
+pub fn stale_crates(graph: &Graph, meta: &Metadata, threshold_days: u64, now: SystemTime) -> BTreeMap<String, Stale> {
+    let today = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or(0) as i64;
+    analysis::all_nodes(graph)
+        .into_iter()
+        .filter_map(|name| {
+            let released = meta.get(name)?.latest_release.as_deref()?;
+            let days = today - report::days_from_date(released)?;
+            let stale = Stale { released: released[..10].to_string() };
+            (days > threshold_days as i64).then(|| (name.to_string(), stale))
+        })
+        .collect()
+}
+
+/// Раздел вывода со списком давно не обновлявшихся пакетов; None, если таких нет
+pub fn render_stale_section(stale: &BTreeMap<String, Stale>, threshold_days: u64) -> Option<String> {
+    if stale.is_empty() {
+        return None;
+    }
+    let mut out = format!(
+        "Возможно, не поддерживаются (нет релизов дольше {} дн.): {}\n",
+        threshold_days,
+        stale.len()
+    );
+    for (name, stale) in stale {
+        // Без числа прошедших дней: вывод того же графа не должен меняться со временем (--check)
+        out.push_str(&format!("  {}: последний релиз {}\n", name, stale.released));
+    }
+    Some(out)
+}
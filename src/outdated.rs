@@ -0,0 +1,99 @@
+use crate::analysis;
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+use semver::{Version, VersionReq};
+use std::fmt;
+
+/// Насколько требование отстаёт от последней версии пакета
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Lag {
+    Patch,
+    Minor,
+    /// Несовместимое по semver обновление (для 0.x — смена minor)
+    Major,
+}
+
+impl fmt::Display for Lag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Lag::Patch => "patch",
+            Lag::Minor => "minor",
+            Lag::Major => "major",
+        })
+    }
+}
+
+/// Отставание требования req от последней версии latest; None, если последняя версия
+/// удовлетворяет требованию или версии не разбираются
+pub fn lag(req: &str, latest: &str) -> Option<Lag> {
+    let req = VersionReq::parse(req).ok()?;
+    let latest = Version::parse(latest).ok()?;
+    if req.matches(&latest) {
+        return None;
+    }
+    let base = req.comparators.first()?;
+    let (minor, patch) = (base.minor.unwrap_or(0), base.patch.unwrap_or(0));
+    if (latest.major, latest.minor, latest.patch) < (base.major, minor, patch) {
+        // Требуется версия новее последней опубликованной (например, пререлиз)
+        return None;
+    }
+    Some(if latest.major != base.major || (base.major == 0 && latest.minor != minor) {
+        Lag::Major
+    } else if latest.minor != minor {
+        Lag::Minor
+    } else {
+        Lag::Patch
+    })
+}
+
+/// Рендерит отчёт об устаревших зависимостях: рёбра, требование которых не допускает
+/// последнюю версию пакета, с оценкой отставания (patch/minor/major).
+/// Последние версии берутся из метаданных (NodeMeta::latest_version)
+pub fn render_outdated_report(graph: &Graph, meta: &Metadata, root: &str, version: &str) -> String {
+    let latest = |name: &str| meta.get(name).and_then(|m| m.latest_version.as_deref());
+    let mut out = format!("Устаревшие зависимости для {} v{}:\n", root, version);
+    let mut counts = [0usize; 3];
+
+    // Версия корня задаётся точно, поэтому сравнивается как требование `=version`
+    if let Some(root_latest) = latest(root)
+        && let Some(root_lag) = lag(&format!("={}", version), root_latest)
+    {
+        out.push_str(&format!("{} v{}: последняя версия {} ({})\n", root, version, root_latest, root_lag));
+        counts[root_lag as usize] += 1;
+    }
+
+    let mut unknown = Vec::new();
+    for parent in analysis::all_nodes(graph) {
+        for dep in graph.get(parent).into_iter().flatten() {
+            let Some(req) = dep.req.as_deref() else { continue };
+            let Some(dep_latest) = latest(&dep.name) else {
+                unknown.push(dep.name.as_str());
+                continue;
+            };
+            if let Some(dep_lag) = lag(req, dep_latest) {
+                out.push_str(&format!(
+                    "{} -> {}: требование {}, последняя версия {} ({})\n",
+                    parent, dep.name, req, dep_latest, dep_lag
+                ));
+                counts[dep_lag as usize] += 1;
+            }
+        }
+    }
+
+    if counts.iter().all(|&c| c == 0) {
+        out.push_str("Все требования допускают последние версии пакетов\n");
+    } else {
+        out.push_str(&format!(
+            "Итого: major: {}, minor: {}, patch: {}\n",
+            counts[Lag::Major as usize],
+            counts[Lag::Minor as usize],
+            counts[Lag::Patch as usize]
+        ));
+    }
+    unknown.sort_unstable();
+    unknown.dedup();
+    if !unknown.is_empty() {
+        out.push_str(&format!("Последняя версия неизвестна ({}): {}\n", unknown.len(), unknown.join(", ")));
+    }
+    out
+}
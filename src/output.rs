@@ -0,0 +1,229 @@
+use crate::config::Config;
+use crate::metadata::{self, Metadata};
+use crate::report::RunStats;
+use crate::{
+    analysis, annotations, deny, dot, duplicates, export, features, footprint, gexf, graph::Graph, graphml, history, mermaid, outdated,
+    tree,
+};
+use std::io;
+
+/// Всё, что получает формат вывода: построенный граф, конфигурация запуска, статистика обхода
+/// и сведения о пакетах. hyperlinks — выводить ли имена пакетов ссылками
+pub struct RenderInput<'a> {
+    pub config: &'a Config,
+    pub graph: &'a Graph,
+    pub stats: &'a RunStats,
+    pub meta: &'a Metadata,
+    pub hyperlinks: bool,
+}
+
+/// Формат вывода: по построенному графу пишет его представление в поток.
+/// Собственный формат добавляется реализацией трейта и регистрацией в Exporters под своим именем
+pub trait Exporter: Send + Sync {
+    fn export(&self, input: &RenderInput, out: &mut dyn io::Write) -> io::Result<()>;
+}
+
+/// Текстовый формат, заданный функцией
+impl<F> Exporter for F
+where
+    F: Fn(&RenderInput) -> String + Send + Sync,
+{
+    fn export(&self, input: &RenderInput, out: &mut dyn io::Write) -> io::Result<()> {
+        out.write_all(self(input).as_bytes())
+    }
+}
+
+/// Реестр форматов вывода по имени (значение output_format)
+pub struct Exporters {
+    formats: Vec<(String, Box<dyn Exporter>)>,
+}
+
+impl Exporters {
+    /// Реестр без форматов
+    pub fn empty() -> Exporters {
+        Exporters { formats: Vec::new() }
+    }
+
+    /// Встроенные форматы
+    pub fn builtin() -> Exporters {
+        let mut exporters = Exporters::empty();
+        exporters.register("ascii", ascii);
+        exporters.register("json", json);
+        // ndjson выводится во время обхода, после него печатать нечего
+        exporters.register("ndjson", |_: &RenderInput| String::new());
+        exporters.register("markdown", markdown);
+        exporters.register("dot", dot);
+        exporters.register("mermaid", |i: &RenderInput| {
+            mermaid::render_mermaid(i.graph, &i.config.name, &i.config.version, &captions(i.config))
+        });
+        exporters.register("graphml", |i: &RenderInput| {
+            graphml::render_graphml(i.graph, &i.config.name, &i.config.version, &captions(i.config))
+        });
+        exporters.register("gexf", gexf);
+        exporters.register("matrix", |i: &RenderInput| export::to_adjacency_csv(i.graph));
+        exporters.register("topo", |i: &RenderInput| analysis::render_topological_order(i.graph, &i.config.name, &i.config.version));
+        exporters.register("scc", |i: &RenderInput| analysis::render_scc_report(i.graph, &i.config.name, &i.config.version));
+        exporters.register("routes", |i: &RenderInput| analysis::render_routes_summary(i.graph, &i.config.name, &i.config.version));
+        exporters.register("longest", |i: &RenderInput| analysis::render_longest_chains(i.graph, &i.config.name, &i.config.version));
+        exporters.register("duplicates", |i: &RenderInput| {
+            duplicates::render_duplicates_report(i.graph, &i.config.name, &i.config.version)
+        });
+        exporters.register("outdated", |i: &RenderInput| {
+            outdated::render_outdated_report(i.graph, i.meta, &i.config.name, &i.config.version)
+        });
+        exporters.register("footprint", |i: &RenderInput| {
+            footprint::render_footprint_report(i.graph, i.meta, &i.config.name, &i.config.version)
+        });
+        exporters.register("owners", |i: &RenderInput| {
+            metadata::render_owners_report(i.graph, i.meta, &i.config.name, &i.config.version)
+        });
+        exporters.register("features", |i: &RenderInput| features::render_features(i.graph, i.config, i.meta));
+        exporters.register("unification", |i: &RenderInput| features::render_unification(i.config, i.stats));
+        exporters.register("deny", |i: &RenderInput| deny::render_deny_toml(i.config, i.graph, i.meta));
+        exporters.register("github", |i: &RenderInput| annotations::render_github(i.config, i.graph, i.stats, i.meta));
+        exporters.register("gitlab", |i: &RenderInput| annotations::render_gitlab(i.config, i.graph, i.stats, i.meta));
+        exporters
+    }
+
+    /// Добавляет формат name; формат с тем же именем заменяется
+    pub fn register(&mut self, name: &str, exporter: impl Exporter + 'static) {
+        match self.formats.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, slot)) => *slot = Box::new(exporter),
+            None => self.formats.push((name.to_string(), Box::new(exporter))),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        self.formats.iter().find(|(existing, _)| existing == name).map(|(_, exporter)| exporter.as_ref())
+    }
+
+    /// Имена форматов в порядке регистрации
+    pub fn names(&self) -> Vec<&str> {
+        self.formats.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Рендерит граф в формате input.config.output_format
+    pub fn render(&self, input: &RenderInput) -> Result<Vec<u8>, String> {
+        let format = &input.config.output_format;
+        let exporter = self.get(format).ok_or_else(|| {
+            format!("неизвестный формат вывода '{}' (ожидается {})", format, self.names().join(", "))
+        })?;
+        let mut out = Vec::new();
+        exporter.export(input, &mut out).map_err(|e| format!("Ошибка вывода в формате '{}': {}", format, e))?;
+        Ok(out)
+    }
+}
+
+/// Рендерит построенный граф во встроенном формате config.output_format.
+/// Формат ndjson выводится во время обхода, поэтому здесь для него возвращается пустая строка
+pub fn render(config: &Config, graph: &Graph, stats: &RunStats, meta: &Metadata, hyperlinks: bool) -> String {
+    let input = RenderInput { config, graph, stats, meta, hyperlinks };
+    match Exporters::builtin().render(&input) {
+        Ok(out) => String::from_utf8(out).expect("встроенные форматы выводят UTF-8"),
+        Err(_) => String::new(),
+    }
+}
+
+/// Заголовок графа из config.title с подстановкой `{crate}` и `{version}`
+pub fn custom_title(config: &Config) -> Option<String> {
+    let title = config.title.as_ref()?;
+    Some(title.replace("{crate}", &config.name).replace("{version}", &config.version))
+}
+
+/// Подписи графа для Mermaid, GraphML и GEXF
+fn captions(config: &Config) -> export::Captions {
+    export::Captions { title: custom_title(config), root_label: config.root_label.clone(), footer: config.footer.clone() }
+}
+
+/// Заголовок текстовых форматов и веб-страницы: config.title или «Граф зависимостей для имя vверсия»
+pub fn heading(config: &Config) -> String {
+    custom_title(config).unwrap_or_else(|| format!("Граф зависимостей для {} v{}", config.name, config.version))
+}
+
+/// ASCII-дерево (учитывает max_depth)
+fn ascii(input: &RenderInput) -> String {
+    let RenderInput { config, graph, stats, meta, hyperlinks } = *input;
+    let mut out = format!("{}:\n", heading(config));
+    out.push_str(&tree::render_configured(graph, config, meta, stats, tree::TreeStyle::Ascii, hyperlinks));
+    if let Some(note) = tree::node_limit_note(stats, config.max_nodes) {
+        out.push_str(&note);
+    }
+    if let Some(section) = stale_section(config, graph, meta) {
+        out.push_str(&format!("\n{}", section));
+    }
+    if let Some(section) = metadata::render_docs_section(graph, meta) {
+        out.push_str(&format!("\n{}", section));
+    }
+    if config.legend {
+        out.push_str(&format!("\n{}", tree::Legend::new(graph, stats, config.show_weights, false).render(tree::TreeStyle::Ascii)));
+    }
+    if let Some(footer) = &config.footer {
+        out.push_str(&format!("\n{}\n", footer));
+    }
+    out
+}
+
+/// Раздел о давно не обновлявшихся пакетах под деревом (stale_after_days)
+fn stale_section(config: &Config, graph: &Graph, meta: &Metadata) -> Option<String> {
+    let threshold = config.stale_after_days?;
+    metadata::render_stale_section(&metadata::stale_crates(graph, meta, threshold, metadata::stale_reference(config)), threshold)
+}
+
+fn json(input: &RenderInput) -> String {
+    let RenderInput { config, graph, stats, meta, .. } = *input;
+    let reach = config.show_paths.then(|| analysis::reach(graph, &config.name));
+    let samples = config.path_samples.map(|limit| analysis::sample_paths(graph, &config.name, limit));
+    export::to_json(graph, &config.name, &config.version, stats, meta, reach.as_ref(), samples.as_ref()) + "\n"
+}
+
+fn markdown(input: &RenderInput) -> String {
+    let RenderInput { config, graph, stats, meta, .. } = *input;
+    let mut out = format!("# {}\n\n", heading(config));
+    let links = config.test_repo_mode != "test";
+    out.push_str(&tree::render_configured(graph, config, meta, stats, tree::TreeStyle::Markdown, links));
+    if let Some(note) = tree::node_limit_note(stats, config.max_nodes) {
+        out.push_str(&format!("\n{}", note));
+    }
+    if let Some(section) = stale_section(config, graph, meta) {
+        out.push_str(&format!("\n{}", section));
+    }
+    if let Some(section) = metadata::render_docs_section(graph, meta) {
+        out.push_str(&format!("\n{}", section));
+    }
+    if config.legend {
+        out.push_str(&format!("\n{}", tree::Legend::new(graph, stats, config.show_weights, false).render(tree::TreeStyle::Markdown)));
+    }
+    if let Some(footer) = &config.footer {
+        out.push_str(&format!("\n---\n\n{}\n", footer));
+    }
+    out
+}
+
+fn dot(input: &RenderInput) -> String {
+    let RenderInput { config, graph, meta, .. } = *input;
+    let opts = dot::DotOptions {
+        clusters: config.dot_clusters,
+        node_scale: config
+            .node_size_metric
+            .map(|metric| export::node_scale(graph, &config.name, meta, metric))
+            .unwrap_or_default(),
+        highlighted: metadata::tagged(graph, &config.name, meta, &config.highlight_tags).into_iter().map(String::from).collect(),
+        title: custom_title(config),
+        root_label: config.root_label.clone(),
+        footer: config.footer.clone(),
+        requirements: config.show_requirements,
+    };
+    dot::render_dot(graph, &config.name, &config.version, meta, &opts)
+}
+
+fn gexf(input: &RenderInput) -> String {
+    let RenderInput { config, graph, meta, .. } = *input;
+    // Динамический граф строится по истории запусков; без неё экспорт статический
+    let timeline = match (&config.history_path, config.gexf_dynamic) {
+        (Some(path), true) => history::timeline(path, &config.name)
+            .map_err(|e| eprintln!("Предупреждение: {}", e))
+            .ok(),
+        _ => None,
+    };
+    gexf::render_gexf(graph, &config.name, &config.version, meta, timeline.as_ref(), &captions(config))
+}
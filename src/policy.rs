@@ -0,0 +1,153 @@
+use crate::analysis;
+use crate::config::Policy;
+use crate::duplicates;
+use crate::graph::{self, Graph};
+use crate::metadata::Metadata;
+use std::collections::HashSet;
+use std::iter::Peekable;
+
+/// Нарушение правила политики
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// Идентификатор правила: "denied-crate", "denied-license", "max-depth" или "duplicate-major"
+    pub rule: &'static str,
+    /// Пакет, к которому относится нарушение
+    pub package: String,
+    /// Описание нарушения без имени пакета
+    pub message: String,
+}
+
+/// Проверяет построенный граф по правилам политики.
+/// Лицензии проверяются только у пакетов, для которых они известны (см. metadata::collect)
+pub fn check(policy: &Policy, graph: &Graph, root: &str, meta: &Metadata) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let depths = analysis::node_depths(graph, root);
+    let denied: HashSet<String> = policy.deny.iter().map(|name| graph::canonical_name(name)).collect();
+    let denied_licenses: HashSet<&str> = policy.deny_licenses.iter().map(String::as_str).collect();
+
+    for name in analysis::all_nodes(graph) {
+        let canonical = graph::canonical_name(name);
+        if denied.contains(&canonical) {
+            violations.push(Violation {
+                rule: "denied-crate",
+                package: name.to_string(),
+                message: "пакет запрещён".to_string(),
+            });
+        }
+        if let Some(license) = meta.get(name).and_then(|m| m.license.as_deref())
+            && license_denied(license, &denied_licenses)
+        {
+            violations.push(Violation {
+                rule: "denied-license",
+                package: name.to_string(),
+                message: format!("запрещённая лицензия {}", license),
+            });
+        }
+        let limit = policy.max_depth.iter().find(|(pkg, _)| graph::canonical_name(pkg) == canonical);
+        if let (Some((_, &max)), Some(&depth)) = (limit, depths.get(name))
+            && depth > max
+        {
+            violations.push(Violation {
+                rule: "max-depth",
+                package: name.to_string(),
+                message: format!("подключён на глубине {}, допустимо не глубже {}", depth, max),
+            });
+        }
+    }
+
+    if policy.deny_duplicate_majors {
+        for duplicate in duplicates::find_duplicates(graph) {
+            let classes: Vec<String> = duplicate.classes.keys().map(ToString::to_string).collect();
+            violations.push(Violation {
+                rule: "duplicate-major",
+                package: duplicate.name.to_string(),
+                message: format!("несовместимые требования к версиям: {}", classes.join(", ")),
+            });
+        }
+    }
+    violations
+}
+
+/// Запрещена ли лицензия: выражение `A OR B` допустимо, если допустим хотя бы один вариант,
+/// а вариант `A AND B` запрещён, если запрещена любая из его частей. AND связывает сильнее OR,
+/// скобки меняют порядок, как в SPDX: `(MIT OR GPL-3.0) AND Apache-2.0`
+fn license_denied(expression: &str, denied: &HashSet<&str>) -> bool {
+    let tokens = license_tokens(expression);
+    any_denied(&mut tokens.iter().map(String::as_str).peekable(), denied)
+}
+
+/// Лексемы SPDX-выражения: скобки, OR, AND и лицензии (`GPL-2.0 WITH exception` — одна лексема).
+/// Устаревшая запись `MIT/Apache-2.0` читается как `MIT OR Apache-2.0`
+fn license_tokens(expression: &str) -> Vec<String> {
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ").replace('/', " OR ");
+    let mut tokens: Vec<String> = Vec::new();
+    let mut words = spaced.split_whitespace();
+    while let Some(word) = words.next() {
+        match word {
+            "WITH" => {
+                if let (Some(last), Some(exception)) = (tokens.last_mut(), words.next()) {
+                    last.push_str(&format!(" WITH {}", exception));
+                }
+            }
+            _ => tokens.push(word.to_string()),
+        }
+    }
+    tokens
+}
+
+/// Выражение `A OR B OR ...`: запрещено, если запрещены все варианты
+fn any_denied<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>, denied: &HashSet<&str>) -> bool {
+    let mut result = all_denied(tokens, denied);
+    while tokens.next_if_eq(&"OR").is_some() {
+        result &= all_denied(tokens, denied);
+    }
+    result
+}
+
+/// Выражение `A AND B AND ...`: запрещено, если запрещена любая часть
+fn all_denied<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>, denied: &HashSet<&str>) -> bool {
+    let mut result = term_denied(tokens, denied);
+    while tokens.next_if_eq(&"AND").is_some() {
+        result |= term_denied(tokens, denied);
+    }
+    result
+}
+
+/// Лицензия или выражение в скобках; незакрытая скобка закрывается концом выражения
+fn term_denied<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>, denied: &HashSet<&str>) -> bool {
+    match tokens.next() {
+        Some("(") => {
+            let result = any_denied(tokens, denied);
+            tokens.next_if_eq(&")");
+            result
+        }
+        Some(license) => denied.contains(license),
+        None => false,
+    }
+}
+
+/// Рендерит нарушения политики для stderr
+pub fn render_violations(violations: &[Violation]) -> String {
+    let mut out = format!("Нарушения политики ({}):\n", violations.len());
+    for violation in violations {
+        out.push_str(&format!("- [{}] {}: {}\n", violation.rule, violation.package, violation.message));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn license_expression_respects_precedence_and_parentheses() {
+        let denied = HashSet::from(["GPL-3.0"]);
+        // AND связывает сильнее OR: MIT — допустимый вариант
+        assert!(!license_denied("MIT OR GPL-3.0 AND Apache-2.0", &denied));
+        // Скобки: GPL-3.0 обязателен при любом выборе
+        assert!(license_denied("(MIT OR Apache-2.0) AND GPL-3.0", &denied));
+        assert!(!license_denied("(MIT OR GPL-3.0) AND Apache-2.0", &denied));
+        assert!(license_denied("GPL-3.0/GPL-3.0 AND MIT", &denied));
+        assert!(!license_denied("MIT/Apache-2.0", &denied));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::graph::{self, Graph};
+use regex::Regex;
+use std::collections::{HashSet, VecDeque};
+
+/// Шаблон имени пакета: `*` соответствует любой последовательности символов (`windows-*`, `*-sys`).
+/// Сравнение, как и на crates.io, не различает регистр, `-` и `_`
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+    text: String,
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Pattern, String> {
+        let canonical = graph::canonical_name(pattern);
+        let body: Vec<String> = canonical.split('*').map(regex::escape).collect();
+        Regex::new(&format!("^{}$", body.join(".*")))
+            .map(|regex| Pattern { regex, text: pattern.to_string() })
+            .map_err(|e| format!("Некорректный шаблон '{}': {}", pattern, e))
+    }
+
+    /// Шаблон, которому подходит только пакет name
+    pub fn exact(name: &str) -> Pattern {
+        Pattern {
+            regex: Regex::new(&format!("^{}$", regex::escape(&graph::canonical_name(name)))).expect("экранированное имя — корректное выражение"),
+            text: name.to_string(),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(&graph::canonical_name(name))
+    }
+
+    /// Шаблон в том виде, в каком он задан
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Удаление узлов из уже построенного графа
+pub trait Prune {
+    /// Граф без пакетов, подходящих под patterns, и без пакетов, которые после их удаления
+    /// стали недостижимы из root: граф заново собирается от корня. Сам корень не удаляется
+    fn pruned(&self, root: &str, patterns: &[Pattern]) -> Graph;
+}
+
+impl Prune for Graph {
+    fn pruned(&self, root: &str, patterns: &[Pattern]) -> Graph {
+        let removed = |name: &str| name != root && patterns.iter().any(|p| p.matches(name));
+        let mut reached: HashSet<&str> = HashSet::from([root]);
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            for dep in self.get(node).into_iter().flatten() {
+                if !removed(&dep.name) && reached.insert(&dep.name) {
+                    queue.push_back(&dep.name);
+                }
+            }
+        }
+        self.iter()
+            .filter(|(name, _)| reached.contains(name.as_str()))
+            .map(|(name, deps)| (name.clone(), deps.iter().filter(|dep| !removed(&dep.name)).cloned().collect()))
+            .collect()
+    }
+}
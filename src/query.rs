@@ -0,0 +1,410 @@
+use crate::graph::{Dep, Graph};
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Выражение фильтрации узлов графа, например:
+/// `depth<=3 && kind!=build && name~"^tokio"`
+///
+/// Поля:
+/// - `name`  — имя пакета (операции `=`, `!=`, `~`, `!~`)
+/// - `kind`  — вид зависимости, по которой пакет достигнут: normal, build или dev
+/// - `depth` — глубина узла (у корня 0)
+/// - `deps`  — число прямых зависимостей пакета
+///
+/// Числовые поля поддерживают `=`, `!=`, `<`, `<=`, `>`, `>=`.
+/// Условия объединяются через `&&`, `||`, `!` и скобки.
+#[derive(Debug)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Text { field: TextField, op: TextOp, value: String },
+    Matches { field: TextField, regex: Regex, negate: bool },
+    Number { field: NumberField, op: NumberOp, value: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TextField {
+    Name,
+    Kind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NumberField {
+    Depth,
+    Deps,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TextOp {
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NumberOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Сведения об узле, по которым вычисляется выражение.
+/// deps = None, если зависимости узла ещё не получены
+pub struct NodeContext<'a> {
+    pub name: &'a str,
+    pub kind: &'a str,
+    pub depth: usize,
+    pub deps: Option<usize>,
+}
+
+impl Query {
+    /// Разбирает выражение фильтрации
+    pub fn parse(input: &str) -> Result<Query, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("Ошибка в выражении фильтра: лишний токен '{}'", token.text()));
+        }
+        Ok(query)
+    }
+
+    /// Вычисляет выражение для узла.
+    /// Возвращает None, если результат зависит от ещё неизвестных данных (например, deps при обходе)
+    pub fn eval(&self, node: &NodeContext) -> Option<bool> {
+        match self {
+            // Трёхзначная логика: false && ? = false, true || ? = true
+            Query::And(a, b) => match (a.eval(node), b.eval(node)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Query::Or(a, b) => match (a.eval(node), b.eval(node)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Query::Not(a) => a.eval(node).map(|v| !v),
+            Query::Text { field, op, value } => {
+                let actual = field.get(node);
+                Some(match op {
+                    TextOp::Eq => actual == value,
+                    TextOp::Ne => actual != value,
+                })
+            }
+            Query::Matches { field, regex, negate } => Some(regex.is_match(field.get(node)) != *negate),
+            Query::Number { field, op, value } => {
+                let actual = field.get(node)?;
+                Some(match op {
+                    NumberOp::Eq => actual == *value,
+                    NumberOp::Ne => actual != *value,
+                    NumberOp::Lt => actual < *value,
+                    NumberOp::Le => actual <= *value,
+                    NumberOp::Gt => actual > *value,
+                    NumberOp::Ge => actual >= *value,
+                })
+            }
+        }
+    }
+}
+
+impl TextField {
+    fn get<'a>(&self, node: &NodeContext<'a>) -> &'a str {
+        match self {
+            TextField::Name => node.name,
+            TextField::Kind => node.kind,
+        }
+    }
+}
+
+impl NumberField {
+    fn get(&self, node: &NodeContext) -> Option<usize> {
+        match self {
+            NumberField::Depth => Some(node.depth),
+            NumberField::Deps => node.deps,
+        }
+    }
+}
+
+/// Оставляет в графе только корень и узлы, удовлетворяющие выражению,
+/// а затем удаляет узлы, ставшие недостижимыми из корня.
+/// Глубина и вид узла определяются по кратчайшему пути от корня
+pub fn filter_graph(graph: &Graph, root: &str, query: &Query) -> Graph {
+    // BFS от корня: минимальная глубина и вид ребра, по которому узел достигнут впервые
+    let mut reached: HashMap<&str, (usize, &str)> = HashMap::new();
+    let mut queue: VecDeque<&str> = VecDeque::new();
+    reached.insert(root, (0, "normal"));
+    queue.push_back(root);
+    while let Some(node) = queue.pop_front() {
+        let depth = reached[node].0;
+        for dep in graph.get(node).into_iter().flatten() {
+            if !reached.contains_key(dep.name.as_str()) {
+                reached.insert(&dep.name, (depth + 1, &dep.kind));
+                queue.push_back(&dep.name);
+            }
+        }
+    }
+
+    let keep: HashSet<&str> = reached
+        .iter()
+        .filter(|(name, (depth, kind))| {
+            **name == root
+                || query
+                    .eval(&NodeContext {
+                        name,
+                        kind,
+                        depth: *depth,
+                        deps: graph.get(**name).map(Vec::len),
+                    })
+                    .unwrap_or(true)
+        })
+        .map(|(name, _)| *name)
+        .collect();
+
+    // Повторный обход только по оставшимся узлам
+    let mut filtered: Graph = Graph::new();
+    let mut stack: Vec<String> = vec![root.to_string()];
+    while let Some(node) = stack.pop() {
+        if filtered.contains_key(&node) {
+            continue;
+        }
+        let deps: Vec<Dep> = graph
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .filter(|dep| keep.contains(dep.name.as_str()))
+            .cloned()
+            .collect();
+        stack.extend(deps.iter().filter(|dep| graph.contains_key(&dep.name)).map(|dep| dep.name.clone()));
+        filtered.insert(node, deps);
+    }
+    filtered
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Ident(s) => s.clone(),
+            Token::Str(s) => format!("\"{}\"", s),
+            Token::Op(op) => op.to_string(),
+            Token::LParen => "(".to_string(),
+            Token::RParen => ")".to_string(),
+        }
+    }
+}
+
+/// Операторы; двухсимвольные идут первыми, чтобы `<=` не разбиралось как `<`
+const OPERATORS: &[&str] = &["&&", "||", "==", "!=", "<=", ">=", "!~", "=", "<", ">", "~", "!"];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            rest = &rest[1..];
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            rest = &rest[1..];
+        } else if c == '"' {
+            let end = rest[1..]
+                .find('"')
+                .ok_or_else(|| "Ошибка в выражении фильтра: незакрытая кавычка".to_string())?;
+            tokens.push(Token::Str(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if c.is_alphanumeric() || "_-.*^$".contains(c) {
+            let end = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || "_-.*^$".contains(ch)))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(format!("Ошибка в выражении фильтра: неожиданный символ '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Query, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Op("||")) {
+            self.pos += 1;
+            left = Query::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, String> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::Op("&&")) {
+            self.pos += 1;
+            left = Query::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, String> {
+        match self.next() {
+            Some(Token::Op("!")) => Ok(Query::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Ошибка в выражении фильтра: ожидается ')'".to_string()),
+                }
+            }
+            Some(Token::Ident(field)) => self.parse_comparison(&field),
+            Some(token) => Err(format!("Ошибка в выражении фильтра: неожиданный токен '{}'", token.text())),
+            None => Err("Ошибка в выражении фильтра: неожиданный конец выражения".to_string()),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: &str) -> Result<Query, String> {
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(format!("Ошибка в выражении фильтра: ожидается оператор после '{}'", field)),
+        };
+        let value = match self.next() {
+            Some(Token::Ident(v)) | Some(Token::Str(v)) => v,
+            _ => return Err(format!("Ошибка в выражении фильтра: ожидается значение после '{} {}'", field, op)),
+        };
+
+        let text_field = match field {
+            "name" => Some(TextField::Name),
+            "kind" => Some(TextField::Kind),
+            _ => None,
+        };
+        if let Some(field) = text_field {
+            return match op {
+                "=" | "==" => Ok(Query::Text { field, op: TextOp::Eq, value }),
+                "!=" => Ok(Query::Text { field, op: TextOp::Ne, value }),
+                "~" | "!~" => {
+                    let regex = Regex::new(&value)
+                        .map_err(|e| format!("Ошибка в выражении фильтра: неверное регулярное выражение '{}': {}", value, e))?;
+                    Ok(Query::Matches { field, regex, negate: op == "!~" })
+                }
+                _ => Err(format!("Ошибка в выражении фильтра: оператор '{}' не применим к полю '{}'", op, field_name(field))),
+            };
+        }
+
+        let field = match field {
+            "depth" => NumberField::Depth,
+            "deps" => NumberField::Deps,
+            other => {
+                return Err(format!(
+                    "Ошибка в выражении фильтра: неизвестное поле '{}' (ожидается name, kind, depth или deps)",
+                    other
+                ));
+            }
+        };
+        let op = match op {
+            "=" | "==" => NumberOp::Eq,
+            "!=" => NumberOp::Ne,
+            "<" => NumberOp::Lt,
+            "<=" => NumberOp::Le,
+            ">" => NumberOp::Gt,
+            ">=" => NumberOp::Ge,
+            other => return Err(format!("Ошибка в выражении фильтра: оператор '{}' не применим к числовому полю", other)),
+        };
+        let value = value
+            .parse()
+            .map_err(|_| format!("Ошибка в выражении фильтра: '{}' не является числом", value))?;
+        Ok(Query::Number { field, op, value })
+    }
+}
+
+fn field_name(field: TextField) -> &'static str {
+    match field {
+        TextField::Name => "name",
+        TextField::Kind => "kind",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node<'a>(name: &'a str, kind: &'a str, depth: usize, deps: Option<usize>) -> NodeContext<'a> {
+        NodeContext { name, kind, depth, deps }
+    }
+
+    fn eval(input: &str, node: &NodeContext) -> Option<bool> {
+        Query::parse(input).unwrap().eval(node)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let serde = node("serde", "normal", 3, Some(0));
+        assert_eq!(eval("name=tokio || name=serde && depth<2", &serde), Some(false));
+        assert_eq!(eval("(name=tokio || name=serde) && depth>=3", &serde), Some(true));
+        assert_eq!(eval("!(kind==build) && name~\"^ser\" && name!~de$", &node("serde", "normal", 1, None)), Some(false));
+    }
+
+    #[test]
+    fn unknown_deps_follow_three_valued_logic() {
+        let pending = node("syn", "build", 2, None);
+        assert_eq!(eval("deps>3", &pending), None);
+        assert_eq!(eval("deps>3 && kind=normal", &pending), Some(false));
+        assert_eq!(eval("deps>3 || depth=2", &pending), Some(true));
+        assert_eq!(eval("!(deps>3)", &pending), None);
+        assert_eq!(eval("deps>3", &node("syn", "build", 2, Some(4))), Some(true));
+    }
+
+    #[test]
+    fn quoted_values_keep_operators() {
+        assert_eq!(eval("name=\"a&&b\"", &node("a&&b", "normal", 1, None)), Some(true));
+    }
+
+    #[test]
+    fn parse_errors_are_reported() {
+        for (input, error) in [
+            ("depth<=", "ожидается значение после 'depth <='"),
+            ("depth<=x", "'x' не является числом"),
+            ("size>1", "неизвестное поле 'size'"),
+            ("name<a", "оператор '<' не применим к полю 'name'"),
+            ("deps~1", "оператор '~' не применим к числовому полю"),
+            ("(depth=1", "ожидается ')'"),
+            ("depth=1 depth=2", "лишний токен 'depth'"),
+            ("name=\"tokio", "незакрытая кавычка"),
+            ("name=a;", "неожиданный символ ';'"),
+            ("name~\"(\"", "неверное регулярное выражение"),
+            ("depth=1 &&", "неожиданный конец выражения"),
+        ] {
+            let message = Query::parse(input).unwrap_err();
+            assert!(message.contains(error), "{}: {}", input, message);
+        }
+    }
+}
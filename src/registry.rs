@@ -0,0 +1,1007 @@
+use crate::cache::Cache;
+use crate::config::{Config, RegistryProtocol};
+use crate::graph::{self, Dep};
+use crate::source::{Candidate, Features, Source};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Структуры для парсинга ответов crates.io
+#[derive(Debug, Deserialize)]
+struct Dependency {
+    crate_id: String,
+    kind: Option<String>,
+    req: String,
+    optional: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DependenciesResponse {
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionInfo {
+    num: String,
+    #[serde(default)]
+    yanked: bool,
+    /// Дата публикации (RFC 3339)
+    #[serde(default)]
+    created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionsResponse {
+    versions: Vec<VersionInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateInfo {
+    description: Option<String>,
+    repository: Option<String>,
+    max_version: String,
+    #[serde(default)]
+    downloads: u64,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+/// Ответ /crates/{name}/{version}
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    version: VersionFeatures,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionFeatures {
+    #[serde(default)]
+    features: Features,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateVersion {
+    num: String,
+    created_at: String,
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    license: Option<String>,
+    /// Размер опубликованного архива .crate в байтах
+    #[serde(default)]
+    crate_size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+    versions: Vec<CrateVersion>,
+}
+
+/// Описание пакета с crates.io
+#[derive(Debug)]
+pub struct CrateDetails {
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    /// Дата публикации последней версии (RFC 3339)
+    pub latest_release: Option<String>,
+    /// Число загрузок за всё время
+    pub downloads: u64,
+    /// Лицензия последней версии (выражение SPDX)
+    pub license: Option<String>,
+    /// Размер архива последней версии в байтах
+    pub crate_size: Option<u64>,
+    /// Категории crates.io (`cryptography`, `development-tools::ffi`)
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Owner {
+    login: String,
+}
+
+/// Ответ docs.rs /crate/{name}/{version}/status.json
+#[derive(Debug, Deserialize)]
+struct DocsStatusResponse {
+    doc_status: bool,
+}
+
+/// Ответ поиска /crates?q=: нужны только имена найденных пакетов
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    crates: Vec<SearchHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchHit {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwnersResponse {
+    users: Vec<Owner>,
+}
+
+/// Ответ /crates/{name}/reverse_dependencies: нужен только общий счётчик
+#[derive(Debug, Deserialize)]
+struct ReverseDependenciesResponse {
+    meta: ReverseDependenciesMeta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReverseDependenciesMeta {
+    total: u64,
+}
+
+/// Строка файла sparse-индекса: одна версия пакета вместе с её зависимостями
+#[derive(Debug, Deserialize)]
+struct IndexVersion {
+    vers: String,
+    deps: Vec<IndexDependency>,
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    features: Features,
+    /// Features с синтаксисом "dep:" и "?/" хранятся в индексе отдельно
+    #[serde(default)]
+    features2: Features,
+    /// SHA-256 архива .crate этой версии
+    #[serde(default)]
+    cksum: String,
+}
+
+/// Файл sparse-индекса пакета вместе с заголовками ответа, по которым видно его состояние
+struct IndexFile {
+    versions: Vec<IndexVersion>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl IndexFile {
+    /// Происхождение версии version: контрольная сумма из индекса и состояние файла индекса
+    fn provenance(&self, version: &str) -> Option<IndexProvenance> {
+        let entry = self.versions.iter().find(|v| v.vers == version)?;
+        Some(IndexProvenance {
+            cksum: entry.cksum.clone(),
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+            verified: false,
+        })
+    }
+}
+
+/// Откуда взяты зависимости версии пакета в sparse-индексе: SHA-256 архива .crate из индекса
+/// и состояние файла индекса (заголовки ETag и Last-Modified ответа). Попадает в отчёт о запуске,
+/// чтобы по сохранённому графу было видно, из какого состояния индекса он построен
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IndexProvenance {
+    pub cksum: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Контрольная сумма сверена со скачанным архивом (verify_checksums)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexDependency {
+    name: String,
+    req: String,
+    #[serde(default)]
+    optional: bool,
+    kind: Option<String>,
+    /// Настоящее имя пакета, если зависимость переименована
+    package: Option<String>,
+}
+
+/// Клиент crates.io API с кэшем, чтобы не запрашивать одно и то же несколько раз
+pub struct Registry {
+    client: HttpClient,
+    cache: Box<dyn Cache>,
+    /// Перепроверять закэшированные последние версии (инкрементальный обход): зависимости
+    /// конкретной версии не меняются, поэтому заново запрашиваются только пакеты с новой версией
+    pub refresh_latest: bool,
+    /// Пакеты, последняя версия которых уже перепроверена в этом запуске
+    refreshed: HashSet<String>,
+    /// Пакеты, последняя версия которых изменилась: "serde 1.0.1 -> 1.0.2"
+    pub changed_versions: Vec<String>,
+    /// Число потоков для параллельной загрузки (1 — последовательно)
+    jobs: usize,
+    /// Способ получения версий и зависимостей: API crates.io или sparse-индекс
+    protocol: RegistryProtocol,
+    /// Дата YYYY-MM-DD, на которую выбирается последняя версия (as_of): более поздние публикации не учитываются
+    as_of: Option<String>,
+    /// Сверять контрольные суммы индекса со скачанными архивами .crate (verify_checksums)
+    verify_checksums: bool,
+    /// Происхождение версий, зависимости которых взяты из sparse-индекса: "имя@версия" -> сведения
+    pub provenance: BTreeMap<String, IndexProvenance>,
+    /// Ключи кэша, заранее заполненные по сети в этом запуске (prefetch): обращение к ним считается промахом
+    prefetched: HashSet<String>,
+    /// Счётчики для отчёта о запуске
+    pub http_requests: usize,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+}
+
+impl Registry {
+    /// Создаёт клиент с сетевыми настройками из конфигурации
+    pub fn new(config: &Config, cache: Box<dyn Cache>) -> Result<Self, String> {
+        Ok(Registry {
+            client: HttpClient { inner: build_client(config)?, bytes_received: AtomicU64::new(0) },
+            cache,
+            refresh_latest: false,
+            refreshed: HashSet::new(),
+            changed_versions: Vec::new(),
+            jobs: config.jobs.max(1),
+            protocol: config.registry_protocol,
+            as_of: config.as_of.clone(),
+            verify_checksums: config.verify_checksums,
+            provenance: BTreeMap::new(),
+            prefetched: HashSet::new(),
+            http_requests: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+        })
+    }
+
+    /// Получение прямых зависимостей конкретной версии через crates.io API
+    /// Использует кэш по ключу "deps:crate:version" ("index-deps:crate:version" для sparse-индекса)
+    pub fn dependencies(&mut self, pkg: &str, version: &str) -> Result<Vec<Dep>, String> {
+        let key = deps_key(pkg, version, self.protocol);
+        let cached = self.cache.get(&key)?;
+        self.count_lookup(&key, cached.is_some());
+        let deps = if let Some(cached) = cached {
+            serde_json::from_str(&cached).map_err(|e| format!("Повреждённая запись кэша '{}': {}", key, e))?
+        } else {
+            self.http_requests += 1;
+            let deps = match self.protocol {
+                RegistryProtocol::Api => {
+                    fetch_dependencies(&self.client, pkg, version).map_err(|e| self.version_not_found(pkg, version, e))?
+                }
+                RegistryProtocol::Sparse => {
+                    let index = fetch_index(&self.client, pkg).map_err(|e| self.not_found(pkg, e))?;
+                    self.store_provenance(pkg, version, index.provenance(version))?;
+                    index_dependencies(&index.versions, pkg, version)?
+                }
+            };
+            self.store_dependencies(pkg, version, &deps)?;
+            deps
+        };
+        if self.protocol == RegistryProtocol::Sparse {
+            self.check_provenance(pkg, version)?;
+        }
+        Ok(deps)
+    }
+
+    /// Добавляет происхождение версии в отчёт (из этого запуска или из кэша) и при verify_checksums
+    /// сверяет контрольную сумму индекса с архивом .crate. Сверенная сумма запоминается в кэше,
+    /// и при следующих запусках архив не скачивается заново
+    fn check_provenance(&mut self, pkg: &str, version: &str) -> Result<(), String> {
+        let id = format!("{}@{}", pkg, version);
+        if !self.provenance.contains_key(&id) {
+            let key = provenance_key(pkg, version);
+            let cached = match self.cache.get(&key)? {
+                Some(cached) => Some(serde_json::from_str(&cached).map_err(|e| format!("Повреждённая запись кэша '{}': {}", key, e))?),
+                // Зависимости попали в кэш прежними версиями программы, без происхождения
+                None if self.verify_checksums => {
+                    self.http_requests += 1;
+                    fetch_index(&self.client, pkg).map_err(String::from)?.provenance(version)
+                }
+                None => None,
+            };
+            let Some(provenance) = cached else { return Ok(()) };
+            self.store_provenance(pkg, version, Some(provenance))?;
+        }
+        let provenance = &self.provenance[&id];
+        if !self.verify_checksums || provenance.verified {
+            return Ok(());
+        }
+        self.http_requests += 1;
+        let url = format!("https://static.crates.io/crates/{}/{}-{}.crate", pkg, pkg, version);
+        let archive = self.client.get_bytes(&url, &format!("архива {} {}", pkg, version)).map_err(String::from)?;
+        let actual: String = Sha256::digest(&archive).iter().map(|b| format!("{:02x}", b)).collect();
+        if actual != provenance.cksum {
+            return Err(format!(
+                "контрольная сумма архива {} {} не совпадает с индексом: {} вместо {}",
+                pkg, version, actual, provenance.cksum
+            ));
+        }
+        let verified = IndexProvenance { verified: true, ..provenance.clone() };
+        self.store_provenance(pkg, version, Some(verified))
+    }
+
+    /// Запоминает происхождение версии в отчёте и в кэше
+    fn store_provenance(&mut self, pkg: &str, version: &str, provenance: Option<IndexProvenance>) -> Result<(), String> {
+        let Some(provenance) = provenance else { return Ok(()) };
+        let json = serde_json::to_string(&provenance).expect("происхождение всегда сериализуется в JSON");
+        self.cache.put(&provenance_key(pkg, version), &json)?;
+        self.provenance.insert(format!("{}@{}", pkg, version), provenance);
+        Ok(())
+    }
+
+    /// Получение последней версии пакета (кэшируется по ключу "latest:crate")
+    pub fn latest_version(&mut self, pkg: &str) -> Result<String, String> {
+        let (cached, usable) = self.lookup_latest(pkg)?;
+        self.count_lookup(&latest_key(pkg, self.as_of.as_deref()), usable);
+        if let (Some(v), true) = (&cached, usable) {
+            return Ok(v.clone());
+        }
+
+        self.http_requests += 1;
+        let latest = match (self.protocol, self.as_of.clone()) {
+            // Дат публикации в индексе нет, поэтому версию на дату выбирает API
+            (RegistryProtocol::Api, as_of) | (RegistryProtocol::Sparse, as_of @ Some(_)) => {
+                fetch_latest(&self.client, pkg, as_of.as_deref()).map_err(|e| self.not_found(pkg, e))?
+            }
+            (RegistryProtocol::Sparse, None) => {
+                // Индекс сразу содержит зависимости: сохраняем их, чтобы не запрашивать пакет повторно
+                let index = fetch_index(&self.client, pkg).map_err(|e| self.not_found(pkg, e))?;
+                let latest = index_latest(&index.versions, pkg)?;
+                self.store_dependencies(pkg, &latest, &index_dependencies(&index.versions, pkg, &latest)?)?;
+                self.prefetched.insert(deps_key(pkg, &latest, self.protocol));
+                self.store_provenance(pkg, &latest, index.provenance(&latest))?;
+                latest
+            }
+        };
+        self.store_latest(pkg, cached, &latest)?;
+        Ok(latest)
+    }
+
+    /// Параллельно получает последние версии и зависимости пакетов names и сохраняет их в кэш,
+    /// чтобы последующие latest_version и dependencies обошлись без сетевых запросов.
+    /// Ошибки здесь не сообщаются: неудачный запрос повторится при обычном обращении и попадёт в отчёт
+    pub fn prefetch(&mut self, names: &[String]) -> Result<(), String> {
+        if self.jobs <= 1 || names.len() < 2 {
+            return Ok(());
+        }
+
+        // Запросы планируются в основном потоке: кэш не разделяется между потоками
+        let mut jobs = Vec::new();
+        for name in names {
+            let (cached, usable) = self.lookup_latest(name)?;
+            let version = if usable { cached.clone() } else { None };
+            if let Some(v) = &version
+                && self.cache.get(&deps_key(name, v, self.protocol))?.is_some()
+            {
+                continue;
+            }
+            jobs.push(PrefetchJob { name: name.clone(), cached, version });
+        }
+
+        let workers = self.jobs.min(jobs.len());
+        let queue = Mutex::new(jobs.into_iter());
+        let results = Mutex::new(Vec::new());
+        let (client, protocol, as_of) = (&self.client, self.protocol, self.as_of.as_deref());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    loop {
+                        let Some(job) = queue.lock().expect("очередь не отравлена").next() else { break };
+                        let fetched = job.run(client, protocol, as_of);
+                        results.lock().expect("результаты не отравлены").push(fetched);
+                    }
+                });
+            }
+        });
+
+        // Порядок завершения потоков случаен — сохраняем результаты в порядке имён
+        let mut results = results.into_inner().expect("результаты не отравлены");
+        results.sort_by(|a, b| a.job.name.cmp(&b.job.name));
+        for fetched in results {
+            self.http_requests += fetched.requests;
+            let PrefetchJob { name, cached, version } = fetched.job;
+            let version = match (version, fetched.latest) {
+                (Some(version), _) => version,
+                (None, Some(Ok(latest))) => {
+                    self.store_latest(&name, cached, &latest)?;
+                    self.prefetched.insert(latest_key(&name, self.as_of.as_deref()));
+                    latest
+                }
+                _ => continue,
+            };
+            if let Some(Ok(deps)) = fetched.deps {
+                self.store_dependencies(&name, &version, &deps)?;
+                self.prefetched.insert(deps_key(&name, &version, self.protocol));
+                self.store_provenance(&name, &version, fetched.provenance)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Учитывает обращение к кэшу в счётчиках отчёта. Запись, полученная по сети в этом же запуске
+    /// заранее (prefetch), считается промахом, иначе доля попаданий завышалась бы
+    fn count_lookup(&mut self, key: &str, found: bool) {
+        if found && !self.prefetched.remove(key) {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+        }
+    }
+
+    /// Закэшированная последняя версия пакета и можно ли использовать её без перепроверки
+    fn lookup_latest(&mut self, pkg: &str) -> Result<(Option<String>, bool), String> {
+        let cached = self.cache.get(&latest_key(pkg, self.as_of.as_deref()))?;
+        let usable = cached.is_some() && (!self.refresh_latest || self.refreshed.contains(pkg));
+        Ok((cached, usable))
+    }
+
+    /// Сохраняет полученную последнюю версию; cached — прежнее значение из кэша
+    fn store_latest(&mut self, pkg: &str, cached: Option<String>, latest: &str) -> Result<(), String> {
+        if let Some(old) = cached.filter(|old| old != latest) {
+            self.changed_versions.push(format!("{} {} -> {}", pkg, old, latest));
+        }
+        self.refreshed.insert(pkg.to_string());
+        self.cache.put(&latest_key(pkg, self.as_of.as_deref()), latest)
+    }
+
+    fn store_dependencies(&mut self, pkg: &str, version: &str, deps: &[Dep]) -> Result<(), String> {
+        let value = serde_json::to_string(deps).expect("зависимости всегда сериализуются в JSON");
+        self.cache.put(&deps_key(pkg, version, self.protocol), &value)
+    }
+
+    /// Все опубликованные версии пакета: в API — от новых к старым, в индексе — в порядке публикации
+    pub fn versions(&mut self, pkg: &str) -> Result<Vec<Candidate>, String> {
+        self.http_requests += 1;
+        Ok(match (self.protocol, self.as_of.clone()) {
+            // При as_of версии, опубликованные позже этой даты, не видны; даты есть только в API
+            (RegistryProtocol::Api, as_of) | (RegistryProtocol::Sparse, as_of @ Some(_)) => {
+                let url = format!("https://crates.io/api/v1/crates/{}/versions", pkg);
+                let versions: VersionsResponse =
+                    get_json(&self.client, &url, &format!("версий {}", pkg)).map_err(|e| self.not_found(pkg, e))?;
+                versions
+                    .versions
+                    .into_iter()
+                    .filter(|v| as_of.as_deref().is_none_or(|date| published_by(&v.created_at, date)))
+                    .map(|v| Candidate { num: v.num, yanked: v.yanked })
+                    .collect()
+            }
+            (RegistryProtocol::Sparse, None) => fetch_index(&self.client, pkg)
+                .map_err(|e| self.not_found(pkg, e))?
+                .versions
+                .into_iter()
+                .map(|v| Candidate { num: v.vers, yanked: v.yanked })
+                .collect(),
+        })
+    }
+
+    /// Сообщение об ошибке запроса пакета pkg. Если пакета нет на crates.io (404), к сообщению
+    /// добавляются похожие имена из поиска crates.io: "возможно, имелся в виду `serde_json`?"
+    fn not_found(&mut self, pkg: &str, error: HttpError) -> String {
+        let HttpError::NotFound(_) = error else { return error.into() };
+        let mut message = format!("пакет {} не найден на crates.io", pkg);
+        match self.similar_names(pkg) {
+            Ok(names) if names.is_empty() => {}
+            Ok(names) => {
+                let names: Vec<String> = names.iter().map(|name| format!("`{}`", name)).collect();
+                message.push_str(&format!("; возможно, имелся в виду {}?", names.join(" или ")));
+            }
+            // Подсказка не обязательна: ошибка поиска не должна заслонять исходную
+            Err(_) => {}
+        }
+        message
+    }
+
+    /// Сообщение об ошибке запроса версии version пакета pkg. API отвечает 404 и тогда, когда пакет есть,
+    /// а версии нет, поэтому перед подсказками похожих имён проверяется, существует ли сам пакет
+    fn version_not_found(&mut self, pkg: &str, version: &str, error: HttpError) -> String {
+        let HttpError::NotFound(_) = error else { return error.into() };
+        self.http_requests += 1;
+        let url = format!("https://crates.io/api/v1/crates/{}", pkg);
+        match self.client.get(&url, &format!("сведений о пакете {}", pkg)) {
+            Ok(_) => format!("версия {} пакета {} не найдена на crates.io", version, pkg),
+            Err(HttpError::NotFound(_)) => self.not_found(pkg, error),
+            // Существование пакета не удалось проверить: остаётся исходная ошибка
+            Err(HttpError::Failed(_)) => error.into(),
+        }
+    }
+
+    /// Имена из поиска crates.io, близкие к pkg по расстоянию редактирования (не больше трёх, ближайшие первыми).
+    /// Имя, совпадающее с pkg с точностью до регистра и `-`/`_`, не предлагается
+    fn similar_names(&mut self, pkg: &str) -> Result<Vec<String>, String> {
+        let url = reqwest::Url::parse_with_params("https://crates.io/api/v1/crates", &[("q", pkg), ("per_page", "20")])
+            .map_err(|e| format!("Некорректный запрос поиска {}: {}", pkg, e))?;
+        let resp: SearchResponse = self.get_json(url.as_str(), &format!("поиска {}", pkg))?;
+        let wanted = graph::canonical_name(pkg);
+        let limit = (wanted.chars().count() / 3).max(2);
+        let mut close: Vec<(usize, String)> = resp
+            .crates
+            .into_iter()
+            .map(|krate| (edit_distance(&wanted, &graph::canonical_name(&krate.name)), krate.name))
+            .filter(|(distance, _)| (1..=limit).contains(distance))
+            .collect();
+        close.sort();
+        Ok(close.into_iter().take(3).map(|(_, name)| name).collect())
+    }
+
+    /// Таблица features версии пакета
+    pub fn features(&mut self, pkg: &str, version: &str) -> Result<Features, String> {
+        match self.protocol {
+            RegistryProtocol::Api => {
+                let url = format!("https://crates.io/api/v1/crates/{}/{}", pkg, version);
+                let resp: VersionResponse = self.get_json(&url, &format!("features {} {}", pkg, version))?;
+                Ok(resp.version.features)
+            }
+            RegistryProtocol::Sparse => {
+                self.http_requests += 1;
+                let entry = fetch_index(&self.client, pkg)?
+                    .versions
+                    .into_iter()
+                    .find(|v| v.vers == version)
+                    .ok_or_else(|| format!("Версия {} пакета {} не найдена в индексе", version, pkg))?;
+                let mut features = entry.features;
+                features.extend(entry.features2);
+                Ok(features)
+            }
+        }
+    }
+
+    /// Логины владельцев пакета (пользователи и команды crates.io)
+    pub fn owners(&mut self, pkg: &str) -> Result<Vec<String>, String> {
+        let url = format!("https://crates.io/api/v1/crates/{}/owners", pkg);
+        let owners: OwnersResponse = self.get_json(&url, &format!("владельцев {}", pkg))?;
+        Ok(owners.users.into_iter().map(|o| o.login).collect())
+    }
+
+    /// Число пакетов crates.io, зависящих от пакета (запрашивается одна запись, чтобы получить счётчик)
+    pub fn dependents(&mut self, pkg: &str) -> Result<u64, String> {
+        let url = format!("https://crates.io/api/v1/crates/{}/reverse_dependencies?per_page=1", pkg);
+        let resp: ReverseDependenciesResponse = self.get_json(&url, &format!("зависимых от {}", pkg))?;
+        Ok(resp.meta.total)
+    }
+
+    /// Описание, репозиторий и дата последнего релиза пакета
+    pub fn crate_details(&mut self, pkg: &str) -> Result<CrateDetails, String> {
+        let url = format!("https://crates.io/api/v1/crates/{}", pkg);
+        let resp: CrateResponse = self.get_json(&url, &format!("сведений о пакете {}", pkg))?;
+        // Последний релиз — самая поздняя публикация, а не max_version: исправление старой ветки (1.x после 2.0)
+        // тоже означает, что пакет поддерживается
+        let latest_release = resp.versions.iter().filter(|v| !v.yanked).map(|v| v.created_at.clone()).max();
+        let latest = resp.versions.into_iter().find(|v| v.num == resp.krate.max_version);
+        let (license, crate_size) = match latest {
+            Some(v) => (v.license, v.crate_size),
+            None => (None, None),
+        };
+        Ok(CrateDetails {
+            description: resp.krate.description.map(|d| d.trim().to_string()),
+            repository: resp.krate.repository,
+            latest_release,
+            downloads: resp.krate.downloads,
+            license,
+            crate_size,
+            categories: resp.krate.categories,
+            keywords: resp.krate.keywords,
+        })
+    }
+
+    /// Число байт, полученных от crates.io (тела ответов)
+    pub fn bytes_received(&self) -> u64 {
+        self.client.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Собралась ли документация версии пакета на docs.rs ("latest" — последней версии).
+    /// 404 означает, что docs.rs не знает о сборке этой версии, и тоже считается несобранной документацией
+    pub fn docs_status(&mut self, pkg: &str, version: &str) -> Result<bool, String> {
+        self.http_requests += 1;
+        let url = format!("https://docs.rs/crate/{}/{}/status.json", pkg, version);
+        match get_json::<DocsStatusResponse>(&self.client, &url, &format!("статуса документации {}", pkg)) {
+            Ok(status) => Ok(status.doc_status),
+            Err(HttpError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Сохраняет кэш в постоянное хранилище
+    pub fn flush_cache(&mut self) -> Result<(), String> {
+        self.cache.flush()
+    }
+
+    /// Выполняет GET-запрос к crates.io и разбирает JSON-ответ
+    fn get_json<T: DeserializeOwned>(&mut self, url: &str, what: &str) -> Result<T, String> {
+        self.http_requests += 1;
+        Ok(get_json(&self.client, url, what)?)
+    }
+}
+
+/// Ключи кэша строятся по каноническому имени, чтобы `foo-bar` и `foo_bar` делили одну запись.
+/// Зависимости из индекса хранятся отдельно: в отличие от API, индекс знает имена переименованных зависимостей
+fn deps_key(pkg: &str, version: &str, protocol: RegistryProtocol) -> String {
+    let prefix = match protocol {
+        RegistryProtocol::Api => "deps",
+        RegistryProtocol::Sparse => "index-deps",
+    };
+    format!("{}:{}:{}", prefix, graph::canonical_name(pkg), version)
+}
+
+/// Ключ кэша для происхождения версии из sparse-индекса
+fn provenance_key(pkg: &str, version: &str) -> String {
+    format!("index:{}:{}", graph::canonical_name(pkg), version)
+}
+
+/// Ключ последней версии; версия на дату as_of хранится отдельно от текущей
+fn latest_key(pkg: &str, as_of: Option<&str>) -> String {
+    match as_of {
+        Some(date) => format!("latest@{}:{}", date, graph::canonical_name(pkg)),
+        None => format!("latest:{}", graph::canonical_name(pkg)),
+    }
+}
+
+/// Запрос предварительной загрузки: version известна, если последняя версия уже есть в кэше
+struct PrefetchJob {
+    name: String,
+    cached: Option<String>,
+    version: Option<String>,
+}
+
+/// Результат предварительной загрузки одного пакета
+struct Prefetched {
+    job: PrefetchJob,
+    latest: Option<Result<String, String>>,
+    deps: Option<Result<Vec<Dep>, String>>,
+    /// Происхождение версии из sparse-индекса
+    provenance: Option<IndexProvenance>,
+    requests: usize,
+}
+
+impl PrefetchJob {
+    /// Выполняется в рабочем потоке: только сетевые запросы, без обращения к кэшу
+    fn run(self, client: &HttpClient, protocol: RegistryProtocol, as_of: Option<&str>) -> Prefetched {
+        if protocol == RegistryProtocol::Sparse {
+            // Версии и зависимости приходят одним запросом; версию на дату as_of выбирает API
+            let index = fetch_index(client, &self.name).map_err(String::from);
+            let mut requests = 1;
+            let latest = match (&self.version, &index, as_of) {
+                (Some(_), _, _) => None,
+                (None, _, Some(date)) => {
+                    requests += 1;
+                    Some(fetch_latest(client, &self.name, Some(date)).map_err(String::from))
+                }
+                (None, Ok(index), None) => Some(index_latest(&index.versions, &self.name)),
+                (None, Err(e), None) => Some(Err(e.clone())),
+            };
+            let version = self.version.clone().or_else(|| latest.clone().and_then(Result::ok));
+            let (deps, provenance) = match (version, index) {
+                (Some(version), Ok(index)) => {
+                    (Some(index_dependencies(&index.versions, &self.name, &version)), index.provenance(&version))
+                }
+                _ => (None, None),
+            };
+            return Prefetched { job: self, latest, deps, provenance, requests };
+        }
+
+        let mut requests = 0;
+        let mut latest = None;
+        let version = match &self.version {
+            Some(version) => Some(version.clone()),
+            None => {
+                requests += 1;
+                let fetched = fetch_latest(client, &self.name, as_of).map_err(String::from);
+                let version = fetched.as_ref().ok().cloned();
+                latest = Some(fetched);
+                version
+            }
+        };
+        let deps = version.map(|version| {
+            requests += 1;
+            fetch_dependencies(client, &self.name, &version).map_err(String::from)
+        });
+        Prefetched { job: self, latest, deps, provenance: None, requests }
+    }
+}
+
+/// Последняя версия пакета (один запрос к crates.io): как и в индексе, наибольшая неотозванная,
+/// пререлизы — только если других версий нет
+fn fetch_latest(client: &HttpClient, pkg: &str, as_of: Option<&str>) -> Result<String, HttpError> {
+    let url = format!("https://crates.io/api/v1/crates/{}/versions", pkg);
+    let versions: VersionsResponse = get_json(client, &url, &format!("версий {}", pkg))?;
+    latest_published(&versions.versions, as_of).ok_or_else(|| match as_of {
+        Some(date) => HttpError::Failed(format!("Не найдены версии пакета {}, опубликованные до {}", pkg, date)),
+        None => HttpError::Failed(format!("Не найдены версии для пакета {}", pkg)),
+    })
+}
+
+/// Последняя версия из ответа API: стабильная, если такая есть; с as_of — среди опубликованных не позже этой даты
+fn latest_published(versions: &[VersionInfo], as_of: Option<&str>) -> Option<String> {
+    versions
+        .iter()
+        // Отозванные версии учитываются при as_of: на ту дату они, как правило, ещё не были отозваны
+        .filter(|v| match as_of {
+            None => !v.yanked,
+            Some(date) => published_by(&v.created_at, date),
+        })
+        .filter_map(|v| semver::Version::parse(&v.num).ok())
+        .max_by_key(|v| (v.pre.is_empty(), v.clone()))
+        .map(|v| v.to_string())
+}
+
+/// Опубликована ли версия не позже дня date (YYYY-MM-DD)
+fn published_by(created_at: &str, date: &str) -> bool {
+    created_at.get(..10).is_some_and(|day| day <= date)
+}
+
+/// Прямые зависимости версии пакета (один запрос к crates.io)
+fn fetch_dependencies(client: &HttpClient, pkg: &str, version: &str) -> Result<Vec<Dep>, HttpError> {
+    let url = format!("https://crates.io/api/v1/crates/{}/{}/dependencies", pkg, version);
+    let deps_resp: DependenciesResponse = get_json(client, &url, &format!("зависимостей {} {}", pkg, version))?;
+
+    let mut deps: Vec<Dep> = deps_resp
+        .dependencies
+        .into_iter()
+        .map(|d| Dep {
+            name: d.crate_id,
+            kind: d.kind.unwrap_or_else(|| "normal".to_string()),
+            req: Some(d.req),
+            optional: d.optional,
+            // API возвращает только настоящее имя пакета, имя из Cargo.toml известно лишь индексу
+            alias: None,
+            local: false,
+        })
+        .collect();
+    normalize_dependencies(&mut deps);
+    Ok(deps)
+}
+
+/// Все версии пакета из sparse-индекса crates.io (один запрос)
+fn fetch_index(client: &HttpClient, pkg: &str) -> Result<IndexFile, HttpError> {
+    let url = format!("https://index.crates.io/{}", index_path(pkg));
+    let what = format!("индекса {}", pkg);
+    let resp = client.send(&url, &what)?;
+    let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let (etag, last_modified) = (header(reqwest::header::ETAG), header(reqwest::header::LAST_MODIFIED));
+    let body = client.text(resp, &what)?;
+    // Файл индекса — JSON Lines: одна строка на версию
+    let versions = body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| HttpError::Failed(format!("Ошибка парсинга JSON {}: {}", what, e))))
+        .collect::<Result<_, _>>()?;
+    Ok(IndexFile { versions, etag, last_modified })
+}
+
+/// Путь к файлу пакета в индексе: "se/rd/serde", "3/s/syn", "2/cc", "1/a"
+fn index_path(pkg: &str) -> String {
+    let name = pkg.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Наибольшая неотозванная версия; пререлизы выбираются, только если других версий нет
+fn index_latest(versions: &[IndexVersion], pkg: &str) -> Result<String, String> {
+    versions
+        .iter()
+        .filter(|v| !v.yanked)
+        .filter_map(|v| semver::Version::parse(&v.vers).ok())
+        .max_by_key(|v| (v.pre.is_empty(), v.clone()))
+        .map(|v| v.to_string())
+        .ok_or_else(|| format!("Не найдены версии для пакета {}", pkg))
+}
+
+/// Зависимости версии version из файла индекса, в том же виде, что и из API
+fn index_dependencies(versions: &[IndexVersion], pkg: &str, version: &str) -> Result<Vec<Dep>, String> {
+    let entry = versions
+        .iter()
+        .find(|v| v.vers == version)
+        .ok_or_else(|| format!("Версия {} пакета {} не найдена в индексе", version, pkg))?;
+    let mut deps: Vec<Dep> = entry
+        .deps
+        .iter()
+        .map(|d| Dep {
+            // В индексе name — имя из Cargo.toml, а настоящее имя переименованной зависимости лежит в package
+            name: d.package.clone().unwrap_or_else(|| d.name.clone()),
+            kind: d.kind.clone().unwrap_or_else(|| "normal".to_string()),
+            req: Some(d.req.clone()),
+            optional: d.optional,
+            alias: d.package.as_ref().map(|_| d.name.clone()),
+            local: false,
+        })
+        .collect();
+    normalize_dependencies(&mut deps);
+    Ok(deps)
+}
+
+/// Порядок зависимостей в ответе не гарантирован — сортируем для воспроизводимого вывода.
+/// Одна зависимость может быть объявлена несколько раз (например, для разных target);
+/// такие записи объединяются, и зависимость опциональна, только если опциональны все объявления
+fn normalize_dependencies(deps: &mut Vec<Dep>) {
+    deps.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.kind.cmp(&b.kind)));
+    deps.dedup_by(|dup, kept| {
+        let same = dup.name == kept.name && dup.kind == kept.kind;
+        if same {
+            kept.optional &= dup.optional;
+        }
+        same
+    });
+}
+
+/// Выполняет GET-запрос к crates.io и разбирает JSON-ответ.
+/// what описывает запрос для сообщений об ошибках, например "версий serde"
+fn get_json<T: DeserializeOwned>(client: &HttpClient, url: &str, what: &str) -> Result<T, HttpError> {
+    let body = client.get(url, what)?;
+    serde_json::from_str(&body).map_err(|e| HttpError::Failed(format!("Ошибка парсинга JSON {}: {}", what, e)))
+}
+
+/// Ошибка HTTP-запроса: отсутствие ресурса (404) отделено от остальных ошибок,
+/// чтобы для ненайденного пакета можно было предложить похожие имена
+enum HttpError {
+    NotFound(String),
+    Failed(String),
+}
+
+impl From<HttpError> for String {
+    fn from(error: HttpError) -> String {
+        match error {
+            HttpError::NotFound(message) | HttpError::Failed(message) => message,
+        }
+    }
+}
+
+/// Расстояние Левенштейна между строками (в символах)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// HTTP-клиент, подсчитывающий полученные байты; счётчик общий для потоков предварительной загрузки
+struct HttpClient {
+    inner: reqwest::blocking::Client,
+    bytes_received: AtomicU64,
+}
+
+impl HttpClient {
+    /// GET-запрос: тело успешного ответа или ошибка с описанием запроса what
+    fn get(&self, url: &str, what: &str) -> Result<String, HttpError> {
+        let resp = self.send(url, what)?;
+        self.text(resp, what)
+    }
+
+    /// GET-запрос двоичного файла (архива .crate)
+    fn get_bytes(&self, url: &str, what: &str) -> Result<Vec<u8>, HttpError> {
+        let resp = self.send(url, what)?;
+        let body = resp.bytes().map_err(|e| HttpError::Failed(format!("Ошибка чтения ответа {}: {}", what, e)))?;
+        self.bytes_received.fetch_add(body.len() as u64, Ordering::Relaxed);
+        Ok(body.to_vec())
+    }
+
+    /// Отправляет GET-запрос; неуспешный статус становится ошибкой
+    fn send(&self, url: &str, what: &str) -> Result<reqwest::blocking::Response, HttpError> {
+        let resp = self
+            .inner
+            .get(url)
+            .send()
+            .map_err(|e| HttpError::Failed(format!("Ошибка HTTP при запросе {}: {}", what, e)))?;
+        if !resp.status().is_success() {
+            let message = format!("crates.io вернул статус {} при запросе {}", resp.status(), what);
+            return Err(match resp.status() {
+                reqwest::StatusCode::NOT_FOUND => HttpError::NotFound(message),
+                _ => HttpError::Failed(message),
+            });
+        }
+        Ok(resp)
+    }
+
+    /// Тело ответа как текст; полученные байты учитываются в счётчике
+    fn text(&self, resp: reqwest::blocking::Response, what: &str) -> Result<String, HttpError> {
+        let body = resp.text().map_err(|e| HttpError::Failed(format!("Ошибка чтения ответа {}: {}", what, e)))?;
+        self.bytes_received.fetch_add(body.len() as u64, Ordering::Relaxed);
+        Ok(body)
+    }
+}
+
+/// HTTP-клиент с User-Agent, заголовками, таймаутами и прокси из конфигурации.
+/// Без таймаутов одно зависшее соединение останавливает весь обход.
+/// Переменные HTTP_PROXY, HTTPS_PROXY и NO_PROXY учитываются reqwest автоматически;
+/// явно заданный прокси их заменяет, но исключения из NO_PROXY сохраняются
+fn build_client(config: &Config) -> Result<reqwest::blocking::Client, String> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in &config.headers {
+        // Секреты не обязательно хранить в конфигурации: "env:CRATES_TOKEN" берёт значение из окружения
+        let value = match value.strip_prefix("env:") {
+            Some(var) => std::env::var(var)
+                .map_err(|_| format!("Переменная окружения '{}' для заголовка '{}' не задана", var, name))?,
+            None => value.clone(),
+        };
+        let header_name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Неверное имя заголовка '{}': {}", name, e))?;
+        let mut header_value = reqwest::header::HeaderValue::from_str(&value)
+            .map_err(|e| format!("Неверное значение заголовка '{}': {}", name, e))?;
+        header_value.set_sensitive(true);
+        headers.insert(header_name, header_value);
+    }
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(config.user_agent.as_str())
+        .default_headers(headers);
+    if let Some(url) = &config.proxy {
+        let proxy = reqwest::Proxy::all(url)
+            .map_err(|e| format!("Неверный адрес прокси '{}': {}", url, e))?
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    if let Some(secs) = config.connect_timeout_secs {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+    // Таймаут общий, а не на чтение: медленная загрузка большого ответа тоже прерывается
+    if let Some(secs) = config.timeout_secs {
+        builder = builder.timeout(Duration::from_secs(secs));
+    }
+    builder.build().map_err(|e| format!("Не удалось создать HTTP-клиент: {}", e))
+}
+
+impl Source for Registry {
+    fn dependencies(&mut self, name: &str, version: Option<&str>) -> Result<Vec<Dep>, String> {
+        let version = version.ok_or_else(|| format!("не задана версия пакета {}", name))?;
+        Registry::dependencies(self, name, version)
+    }
+
+    fn resolve_version(&mut self, name: &str) -> Result<Option<String>, String> {
+        self.latest_version(name).map(Some)
+    }
+
+    fn prefetch(&mut self, names: &[String]) -> Result<(), String> {
+        Registry::prefetch(self, names)
+    }
+
+    fn persist(&mut self) -> Result<(), String> {
+        self.flush_cache()
+    }
+
+    fn candidates(&mut self, name: &str) -> Result<Vec<Candidate>, String> {
+        self.versions(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(num: &str, yanked: bool, created_at: &str) -> VersionInfo {
+        VersionInfo { num: num.to_string(), yanked, created_at: created_at.to_string() }
+    }
+
+    #[test]
+    fn published_by_compares_publication_day() {
+        assert!(published_by("2024-03-01T23:59:59.123+00:00", "2024-03-01"));
+        assert!(published_by("2024-02-29T00:00:00+00:00", "2024-03-01"));
+        assert!(!published_by("2024-03-02T00:00:00+00:00", "2024-03-01"));
+        assert!(!published_by("", "2024-03-01"));
+    }
+
+    #[test]
+    fn latest_published_respects_as_of() {
+        let versions = [
+            version("1.0.0", false, "2023-01-10T00:00:00+00:00"),
+            version("1.1.0", true, "2023-06-01T00:00:00+00:00"),
+            version("2.0.0-rc.1", false, "2024-01-05T00:00:00+00:00"),
+            version("2.0.0", false, "2024-02-01T00:00:00+00:00"),
+        ];
+        assert_eq!(latest_published(&versions, None).as_deref(), Some("2.0.0"));
+        // Позже даты as_of опубликованы 2.0.0 и rc; отозванная 1.1.0 на ту дату ещё была доступна
+        assert_eq!(latest_published(&versions, Some("2023-12-31")).as_deref(), Some("1.1.0"));
+        // Предварительная версия выбирается, только если стабильных нет
+        assert_eq!(latest_published(&versions[2..3], Some("2024-01-31")).as_deref(), Some("2.0.0-rc.1"));
+        assert_eq!(latest_published(&versions, Some("2024-01-31")).as_deref(), Some("1.1.0"));
+        assert_eq!(latest_published(&versions, Some("2022-12-31")), None);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::analysis;
+use crate::config::Config;
+use crate::footprint;
+use crate::graph::Graph;
+use crate::manifest::UnifiedFeature;
+use crate::registry::IndexProvenance;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Узел, пропущенный из-за ошибки при обращении к репозиторию
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SkippedNode {
+    pub name: String,
+    pub error: String,
+}
+
+/// Длительность этапа запуска
+#[derive(Serialize, Debug, Clone)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub ms: u128,
+}
+
+/// Статистика и диагностика одного построения графа
+#[derive(Serialize, Default, Debug)]
+pub struct RunStats {
+    pub http_requests: usize,
+    /// Байт получено от crates.io (тела ответов)
+    pub bytes_received: u64,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    pub warnings: Vec<String>,
+    /// Узлы, зависимости которых не раскрывались из-за max_depth
+    pub truncated_by_depth: Vec<String>,
+    /// Узлы, оставшиеся нераскрытыми из-за max_nodes
+    pub truncated_by_max_nodes: Vec<String>,
+    /// Узлы, оставшиеся нераскрытыми из-за прерывания обхода (Ctrl-C)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated_by_interrupt: Vec<String>,
+    /// Узлы, не раскрытые ещё в исходном обходе импортированного графа (source = json)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unexpanded_in_source: Vec<String>,
+    /// Обход прерван по Ctrl-C: граф неполный
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+    pub skipped_on_error: Vec<SkippedNode>,
+    /// Пакеты, которые не удалось разрешить при keep_going: они остаются в графе нераскрытыми узлами
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<SkippedNode>,
+    /// Пакеты, последняя версия которых изменилась с прошлого запуска (incremental): "serde 1.0.1 -> 1.0.2"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_versions: Vec<String>,
+    /// Происхождение версий из sparse-индекса: "имя@версия" -> контрольная сумма архива
+    /// и состояние файла индекса (ETag, Last-Modified), признак сверки с архивом
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub index_provenance: BTreeMap<String, IndexProvenance>,
+    /// Features, которые объединение features рабочего пространства добавило участникам (source = "manifest")
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unified_features: Vec<UnifiedFeature>,
+    /// Источники (merge), в графах которых встретился пакет: имя -> метки источников
+    #[serde(skip)]
+    pub node_sources: BTreeMap<String, Vec<String>>,
+    /// Версии, с которыми пакеты раскрывались при обходе: имя -> версия
+    #[serde(skip)]
+    pub node_versions: BTreeMap<String, String>,
+    /// Длительности этапов: в отчёт попадают только при report_timestamps, как и время запуска
+    #[serde(skip)]
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl RunStats {
+    /// Печатает предупреждение в stderr и сохраняет его для отчёта
+    pub fn warn(&mut self, message: String) {
+        eprintln!("Предупреждение: {}", message);
+        self.warnings.push(message);
+    }
+
+    /// Отмечает пакет как неразрешённый (keep_going): ошибка печатается в stderr и попадает в отчёт
+    pub fn unresolved(&mut self, name: &str, error: String) {
+        if self.unresolved.iter().any(|node| node.name == name) {
+            return;
+        }
+        self.warn(format!("не удалось разрешить '{}': {}", name, error));
+        self.unresolved.push(SkippedNode { name: name.to_string(), error });
+    }
+
+    /// Записывает длительность этапа phase, начавшегося в started
+    pub fn record_phase(&mut self, phase: &'static str, started: Instant) {
+        self.phases.push(PhaseTiming { phase, ms: started.elapsed().as_millis() });
+    }
+
+    /// Добавляет статистику отдельного обхода (источника merge) к общей
+    pub fn absorb(&mut self, other: RunStats) {
+        self.http_requests += other.http_requests;
+        self.bytes_received += other.bytes_received;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.warnings.extend(other.warnings);
+        self.truncated_by_depth.extend(other.truncated_by_depth);
+        self.truncated_by_max_nodes.extend(other.truncated_by_max_nodes);
+        self.truncated_by_interrupt.extend(other.truncated_by_interrupt);
+        self.unexpanded_in_source.extend(other.unexpanded_in_source);
+        self.skipped_on_error.extend(other.skipped_on_error);
+        self.unresolved.extend(other.unresolved);
+        self.changed_versions.extend(other.changed_versions);
+        self.interrupted |= other.interrupted;
+        self.node_versions.extend(other.node_versions);
+        self.index_provenance.extend(other.index_provenance);
+        self.unified_features.extend(other.unified_features);
+    }
+
+    /// Сводка для подробного режима (--verbose): запросы, трафик, кэш и длительности этапов
+    pub fn summary(&self) -> String {
+        let mut out = String::from("Статистика запуска:\n");
+        out.push_str(&format!(
+            "  HTTP-запросов: {}, получено: {}\n",
+            self.http_requests,
+            footprint::format_size(self.bytes_received)
+        ));
+        let lookups = self.cache_hits + self.cache_misses;
+        let hit_rate = if lookups == 0 { 0.0 } else { self.cache_hits as f64 * 100.0 / lookups as f64 };
+        out.push_str(&format!(
+            "  Кэш: попаданий {}, промахов {} ({:.0}% попаданий)\n",
+            self.cache_hits, self.cache_misses, hit_rate
+        ));
+        if !self.index_provenance.is_empty() {
+            let verified = self.index_provenance.values().filter(|p| p.verified).count();
+            out.push_str(&format!(
+                "  Индекс: версий с контрольными суммами {}, сверено с архивами {}\n",
+                self.index_provenance.len(),
+                verified
+            ));
+        }
+        for timing in &self.phases {
+            out.push_str(&format!("  {}: {} мс\n", timing.phase, timing.ms));
+        }
+        out
+    }
+}
+
+/// Отчёт о запуске, сохраняемый рядом с результатом
+#[derive(Serialize, Debug)]
+struct Report<'a> {
+    config: &'a Config,
+    /// Время запуска пишется только по запросу (report_timestamps), чтобы отчёты можно было сравнивать
+    #[serde(skip_serializing_if = "Option::is_none")]
+    started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phases: Option<&'a [PhaseTiming]>,
+    node_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    /// Выборки путей от корня (path_samples), как в поле sample_paths JSON-экспорта: пакет -> пути
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_paths: Option<BTreeMap<&'a str, Vec<Vec<&'a str>>>>,
+    #[serde(flatten)]
+    stats: &'a RunStats,
+}
+
+/// Записывает отчёт о запуске в формате JSON.
+/// graph — построенный граф (None, если построение прервала ошибка error)
+pub fn write_report(
+    path: &str,
+    config: &Config,
+    started_at: SystemTime,
+    finished_at: SystemTime,
+    graph: Option<&Graph>,
+    error: Option<&str>,
+    stats: &RunStats,
+) -> Result<(), String> {
+    let timestamps = config.report_timestamps;
+    let report = Report {
+        config,
+        started_at: timestamps.then(|| format_timestamp(started_at)),
+        finished_at: timestamps.then(|| format_timestamp(finished_at)),
+        duration_ms: timestamps.then(|| {
+            finished_at
+                .duration_since(started_at)
+                .map(|d| d.as_millis())
+                .unwrap_or(0)
+        }),
+        phases: timestamps.then_some(stats.phases.as_slice()),
+        node_count: graph.map_or(0, Graph::len),
+        error,
+        sample_paths: graph
+            .zip(config.path_samples)
+            .map(|(graph, limit)| analysis::sample_paths(graph, &config.name, limit).into_iter().collect()),
+        stats,
+    };
+    let json = serde_json::to_string_pretty(&report).expect("отчёт всегда сериализуется в JSON");
+    fs::write(path, json + "\n").map_err(|e| format!("Ошибка записи отчёта '{}': {}", path, e))
+}
+
+/// Номер дня с 1970-01-01 для даты, начинающейся с YYYY-MM-DD (например, "2019-03-01T12:00:00+00:00").
+/// None, если начало строки не дата в этом формате или такого дня нет в календаре
+pub fn days_from_date(date: &str) -> Option<i64> {
+    let prefix = date.get(..10)?.as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        prefix[range].iter().try_fold(0i64, |n, b| b.is_ascii_digit().then(|| n * 10 + i64::from(b - b'0')))
+    };
+    if prefix[4] != b'-' || prefix[7] != b'-' {
+        return None;
+    }
+    let (year, month, day) = (digits(0..4)?, digits(5..7)?, digits(8..10)?);
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if !(1..=month_days).contains(&day) {
+        return None;
+    }
+    // Обратное преобразование к civil_from_days (алгоритм Howard Hinnant, days_from_civil)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+/// Форматирует момент времени как RFC 3339 в UTC (например, 2024-05-01T12:00:00Z)
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Перевод числа дней с 1970-01-01 в дату (алгоритм Howard Hinnant, civil_from_days)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_from_date_checks_format_and_calendar() {
+        assert_eq!(days_from_date("1970-01-01"), Some(0));
+        assert_eq!(days_from_date("2024-03-01"), Some(19_783));
+        // Дата в начале отметки времени RFC 3339
+        assert_eq!(days_from_date("2019-03-01T12:00:00+00:00"), Some(17_956));
+        // Високосные годы
+        assert_eq!(days_from_date("2024-02-29"), Some(19_782));
+        assert_eq!(days_from_date("2000-02-29"), Some(11_016));
+        assert_eq!(days_from_date("2023-02-29"), None);
+        assert_eq!(days_from_date("1900-02-29"), None);
+        // Несуществующие дни и месяцы
+        assert_eq!(days_from_date("2024-02-31"), None);
+        assert_eq!(days_from_date("2024-04-31"), None);
+        assert_eq!(days_from_date("2024-13-01"), None);
+        assert_eq!(days_from_date("2024-00-10"), None);
+        // Поля фиксированной ширины
+        assert_eq!(days_from_date("2024-1-011"), None);
+        assert_eq!(days_from_date("2024-01-1"), None);
+        assert_eq!(days_from_date("+202-01-01"), None);
+        assert_eq!(days_from_date("2024/01/01"), None);
+    }
+}
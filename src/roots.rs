@@ -0,0 +1,161 @@
+use crate::analysis;
+use crate::cache::{self, SharedCache};
+use crate::config::{Config, Root, SourceKind};
+use crate::graph;
+use crate::group;
+use crate::history;
+use crate::metadata;
+use crate::output;
+use crate::policy;
+use crate::prune::{Pattern, Prune};
+use crate::report::RunStats;
+use std::collections::HashSet;
+use std::fs;
+use std::thread;
+
+/// Путь к файлу вывода корня: `{crate}` и `{version}` в шаблоне заменяются его именем и версией
+pub fn output_path(template: &str, root: &Root) -> String {
+    template.replace("{crate}", &root.name).replace("{version}", &root.version)
+}
+
+/// Итог обработки одного корня
+struct Outcome {
+    /// Версия корня после разрешения "latest" и диапазонов
+    version: String,
+    path: String,
+    node_count: usize,
+    violations: usize,
+    /// Число пакетов, которые не удалось разрешить (keep_going)
+    unresolved: usize,
+}
+
+/// Строит графы всех корней из config.roots параллельно (по потоку на корень, с общим кэшем crates.io)
+/// и записывает вывод каждого в свой файл по шаблону output_filename.
+/// Возвращает сводку для stdout и признак того, что хотя бы один корень завершился ошибкой
+/// или нарушил политику
+pub fn run(config: &Config, prune_patterns: &[Pattern], group_patterns: &[Pattern]) -> Result<(String, bool), String> {
+    if !config.output_filename.contains("{crate}") {
+        return Err(format!(
+            "При нескольких корнях output_filename должен содержать {{crate}}, а не '{}'",
+            config.output_filename
+        ));
+    }
+    let shared = shared_cache(config)?;
+
+    let results: Vec<Result<Outcome, String>> = thread::scope(|scope| {
+        let handles: Vec<_> = config
+            .roots
+            .iter()
+            .map(|root| scope.spawn(|| run_root(config, root, shared.as_ref(), prune_patterns, group_patterns)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err("обход завершился аварийно".to_string())))
+            .collect()
+    });
+
+    let mut failed = false;
+    let mut out = format!("Графы для корней: {}\n", config.roots.len());
+    for (root, result) in config.roots.iter().zip(results) {
+        match result {
+            Ok(outcome) => {
+                out.push_str(&format!("{} v{}: {} (пакетов: {})", root.name, outcome.version, outcome.path, outcome.node_count));
+                if outcome.violations > 0 {
+                    out.push_str(&format!(", нарушений политики: {}", outcome.violations));
+                    failed = true;
+                }
+                if outcome.unresolved > 0 {
+                    out.push_str(&format!(", не разрешено пакетов: {}", outcome.unresolved));
+                    failed = true;
+                }
+                out.push('\n');
+            }
+            Err(e) => {
+                out.push_str(&format!("{} v{}: ошибка: {}\n", root.name, root.version, e));
+                failed = true;
+            }
+        }
+    }
+    Ok((out, failed))
+}
+
+/// Общий кэш crates.io для параллельных обходов нескольких корней.
+/// Тестовому режиму и импорту JSON кэш не нужен
+pub fn shared_cache(config: &Config) -> Result<Option<SharedCache>, String> {
+    let needs_cache = config.source == SourceKind::Manifest
+        || (config.source == SourceKind::Crawl && config.test_repo_mode != "test");
+    Ok(if needs_cache { Some(SharedCache::new(cache::open(config)?)) } else { None })
+}
+
+/// Конфигурация обхода одного корня: общая конфигурация с его именем и версией (уже разрешённой)
+/// и без отчёта о запуске и контрольных точек
+pub fn root_config(config: &Config, root: &Root) -> Result<Config, String> {
+    let mut config = config.clone();
+    config.name = root.name.clone();
+    config.version = root.version.clone();
+    config.roots.clear();
+    config.report_path = None;
+    config.checkpoint_path = None;
+    config.resume = false;
+    graph::resolve_root_version(&mut config)?;
+    Ok(config)
+}
+
+/// Полный цикл для одного корня: обход, сведения о пакетах, история, удаление по шаблонам,
+/// вывод в файл и проверка политики. Отчёт о запуске и контрольные точки для отдельных корней не ведутся
+fn run_root(
+    config: &Config,
+    root: &Root,
+    shared: Option<&SharedCache>,
+    prune_patterns: &[Pattern],
+    group_patterns: &[Pattern],
+) -> Result<Outcome, String> {
+    let config = root_config(config, root)?;
+    let mut stats = RunStats::default();
+    let graph = graph::build_graph_with_cache(&config, shared, &mut stats, &mut |_| {})?;
+    let meta = metadata::collect(&config, &graph, &mut stats);
+    if let Some(path) = &config.history_path
+        && let Err(e) = history::record(path, &config, &graph, &stats, &meta, std::time::SystemTime::now())
+    {
+        stats.warn(e);
+    }
+    let mut prune_patterns = prune_patterns.to_vec();
+    prune_patterns.extend(metadata::tagged(&graph, &config.name, &meta, &config.exclude_tags).into_iter().map(Pattern::exact));
+    let graph = if prune_patterns.is_empty() {
+        graph
+    } else {
+        let pruned = graph.pruned(&config.name, &prune_patterns);
+        let remaining: HashSet<&str> = analysis::all_nodes(&pruned).into_iter().collect();
+        stats.truncated_by_depth.retain(|name| remaining.contains(name.as_str()));
+        stats.truncated_by_max_nodes.retain(|name| remaining.contains(name.as_str()));
+        stats.truncated_by_interrupt.retain(|name| remaining.contains(name.as_str()));
+        stats.unexpanded_in_source.retain(|name| remaining.contains(name.as_str()));
+        pruned
+    };
+
+    let path = output_path(&config.output_filename, &Root { name: config.name.clone(), version: config.version.clone() });
+    let rendered = if group_patterns.is_empty() {
+        output::render(&config, &graph, &stats, &meta, false)
+    } else {
+        let (grouped, grouped_meta) = group::grouped(&graph, &config.name, group_patterns, &meta);
+        output::render(&config, &grouped, &stats, &grouped_meta, false)
+    };
+    fs::write(&path, rendered).map_err(|e| format!("Ошибка записи '{}': {}", path, e))?;
+
+    let violations = if config.policy.is_empty() {
+        0
+    } else {
+        let violations = policy::check(&config.policy, &graph, &config.name, &meta);
+        if !violations.is_empty() {
+            eprint!("{}", policy::render_violations(&violations));
+        }
+        violations.len()
+    };
+    Ok(Outcome {
+        version: config.version,
+        path,
+        node_count: analysis::all_nodes(&graph).len(),
+        violations,
+        unresolved: stats.unresolved.len(),
+    })
+}
@@ -0,0 +1,123 @@
+use crate::policy::Violation;
+use serde::Serialize;
+use std::fs;
+
+/// Правила политики с краткими описаниями для раздела tool.driver.rules
+const RULES: &[(&str, &str)] = &[
+    ("denied-crate", "Пакет из списка запрещённых"),
+    ("denied-license", "Пакет под запрещённой лицензией"),
+    ("max-depth", "Пакет подключён глубже допустимого"),
+    ("duplicate-major", "Несовместимые по semver требования к одному пакету"),
+];
+
+/// Манифест, к которому привязываются результаты: путь к исправлению зависимостей
+const MANIFEST: &str = "Cargo.toml";
+
+#[derive(Serialize)]
+struct SarifLog<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run<'a>>,
+}
+
+#[derive(Serialize)]
+struct Run<'a> {
+    tool: Tool,
+    results: Vec<SarifResult<'a>>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+    rule_id: &'static str,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location<'a> {
+    physical_location: PhysicalLocation,
+    logical_locations: Vec<LogicalLocation<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: &'static str,
+}
+
+#[derive(Serialize)]
+struct LogicalLocation<'a> {
+    name: &'a str,
+    kind: &'static str,
+}
+
+/// Сериализует нарушения политики в SARIF 2.1.0 (GitHub code scanning и другие панели безопасности).
+/// Пакет-нарушитель записывается логическим расположением, физическое указывает на Cargo.toml
+pub fn to_sarif(violations: &[Violation]) -> String {
+    let results = violations
+        .iter()
+        .map(|v| SarifResult {
+            rule_id: v.rule,
+            level: "error",
+            message: Message { text: format!("{}: {}", v.package, v.message) },
+            locations: vec![Location {
+                physical_location: PhysicalLocation { artifact_location: ArtifactLocation { uri: MANIFEST } },
+                logical_locations: vec![LogicalLocation { name: &v.package, kind: "package" }],
+            }],
+        })
+        .collect();
+    let driver = Driver {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        rules: RULES
+            .iter()
+            .map(|(id, text)| Rule { id, short_description: Message { text: text.to_string() } })
+            .collect(),
+    };
+    let log = SarifLog {
+        schema: "https://json.schemastore.org/sarif-2.1.0.json",
+        version: "2.1.0",
+        runs: vec![Run { tool: Tool { driver }, results }],
+    };
+    serde_json::to_string_pretty(&log).expect("SARIF всегда сериализуется в JSON")
+}
+
+/// Записывает SARIF-файл; пустой список результатов тоже записывается,
+/// чтобы панель безопасности закрыла ранее найденные нарушения
+pub fn write_sarif(path: &str, violations: &[Violation]) -> Result<(), String> {
+    fs::write(path, to_sarif(violations) + "\n").map_err(|e| format!("Ошибка записи SARIF '{}': {}", path, e))
+}
@@ -0,0 +1,301 @@
+use crate::analysis;
+use crate::config::Config;
+use crate::export;
+use crate::graph::{self, Graph};
+use crate::metadata::{self, Metadata};
+use crate::output;
+use crate::report::RunStats;
+use crate::tree;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+/// Сколько времени построенный граф считается актуальным
+const GRAPH_TTL: Duration = Duration::from_secs(300);
+
+/// Страница с интерактивным деревом; данные загружаются с /graph.json
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="ru">
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+  body { font-family: sans-serif; margin: 2em; }
+  details { margin-left: 1.2em; }
+  summary { cursor: pointer; }
+  .leaf { margin-left: 2.4em; }
+  .cycle { color: #a00; }
+  .meta { color: #666; font-size: 90%; }
+  .scaled { border-radius: 3px; padding: 0 2px; }
+</style>
+</head>
+<body>
+<h1>{{title}}</h1>
+<p><a href="/graph.json">JSON</a> · <a href="/tree.txt">ASCII-дерево</a> · <a href="/?refresh=1" id="refresh">Перестроить</a></p>
+<div id="tree">Загрузка…</div>
+<div id="legend"></div>
+{{footer}}
+<script>
+// Подпись корня вместо его имени (root_label)
+const ROOT_LABEL = {{root_label}};
+// Легенда отметок под деревом (legend)
+const LEGEND = {{legend}};
+// Описание, репозиторий и дата релиза (если запрошены) выводятся рядом с именем
+function describe(node) {
+  const span = document.createElement('span');
+  span.className = 'meta';
+  if (!node) return span;
+  if (node.description) span.append(' — ' + node.description);
+  if (node.latest_release) span.append(' (релиз ' + node.latest_release.slice(0, 10) + ')');
+  if (node.dependents !== undefined) span.append(' (зависимых: ' + node.dependents + ')');
+  if (node.repository) {
+    const a = document.createElement('a');
+    a.href = node.repository;
+    a.textContent = 'репозиторий';
+    span.append(' ', a);
+  }
+  return span;
+}
+// Масштаб узла по выбранной метрике (0..1): крупнее шрифт и насыщеннее фон
+let scale = {};
+function emphasize(el, name) {
+  const s = scale[name];
+  if (s === undefined) return;
+  el.classList.add('scaled');
+  el.style.fontSize = (90 + 60 * s) + '%';
+  el.style.backgroundColor = 'rgba(220, 0, 0, ' + (0.4 * s).toFixed(3) + ')';
+}
+function render(nodes, name, edge, path) {
+  let label = edge && edge.alias ? name + ' (как ' + edge.alias + ')' : name;
+  if (!edge && ROOT_LABEL) label = ROOT_LABEL;
+  if (edge && edge.kind !== 'normal') label += ' [' + edge.kind + ']';
+  if (edge && edge.optional) label += ' (optional)';
+  if (edge && edge.local) label += ' (local)';
+  const node = nodes[name];
+  const deps = node ? node.dependencies : [];
+  if (path.has(name)) {
+    const el = document.createElement('div');
+    el.className = 'leaf cycle';
+    el.textContent = label + ' (цикл)';
+    return el;
+  }
+  if (deps.length === 0) {
+    const el = document.createElement('div');
+    el.className = 'leaf';
+    el.textContent = label;
+    emphasize(el, name);
+    el.appendChild(describe(node));
+    return el;
+  }
+  const el = document.createElement('details');
+  const summary = document.createElement('summary');
+  summary.textContent = label + ' (' + deps.length + ')';
+  emphasize(summary, name);
+  summary.appendChild(describe(node));
+  el.appendChild(summary);
+  el.addEventListener('toggle', () => {
+    if (!el.open || el.dataset.loaded) return;
+    el.dataset.loaded = '1';
+    const next = new Set(path);
+    next.add(name);
+    for (const dep of deps) el.appendChild(render(nodes, dep.name, dep, next));
+  });
+  return el;
+}
+const refresh = new URLSearchParams(location.search).has('refresh') ? '?refresh=1' : '';
+Promise.all([
+  fetch('/graph.json' + refresh).then(r => r.json()),
+  fetch('/node_scale.json').then(r => r.json()),
+]).then(([doc, nodeScale]) => {
+  scale = nodeScale;
+  const nodes = {};
+  for (const n of doc.nodes) nodes[n.name] = n;
+  const root = render(nodes, doc.root, null, new Set());
+  if (root.tagName === 'DETAILS') root.open = true;
+  const container = document.getElementById('tree');
+  container.textContent = '';
+  container.appendChild(root);
+  if (LEGEND) fetch('/legend.html').then(r => r.text()).then(html => { document.getElementById('legend').innerHTML = html; });
+}).catch(e => { document.getElementById('tree').textContent = 'Ошибка: ' + e; });
+</script>
+</body>
+</html>
+"#;
+
+/// Страница с заголовком, подписью корня и подвалом из конфигурации
+fn index_html(config: &Config) -> String {
+    let footer = config.footer.as_ref().map(|f| format!("<p class=\"meta\">{}</p>", escape_html(f))).unwrap_or_default();
+    // "</" внутри строки JS закрыл бы тег script
+    let root_label = serde_json::to_string(&config.root_label).expect("строка сериализуется в JSON").replace("</", "<\\/");
+    INDEX_HTML
+        .replace("{{title}}", &escape_html(&output::heading(config)))
+        .replace("{{footer}}", &footer)
+        .replace("{{root_label}}", &root_label)
+        .replace("{{legend}}", if config.legend { "true" } else { "false" })
+}
+
+/// Легенда для веб-страницы: таблица отметок интерактивного дерева и итог по циклам и нераскрытым ветвям
+fn legend_html(legend: &tree::Legend) -> String {
+    let mut out = String::from("<div class=\"meta\">\n");
+    if !legend.marks.is_empty() {
+        out.push_str("<p>Обозначения:</p>\n<table>\n");
+        for (symbol, meaning) in &legend.marks {
+            out.push_str(&format!("<tr><td><code>{}</code></td><td>{}</td></tr>\n", escape_html(symbol), escape_html(meaning)));
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str(&format!("<p>{}</p>\n</div>\n", escape_html(&legend.summary())));
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Лениво построенный граф вместе с метаданными и статистикой
+struct BuiltGraph {
+    graph: Graph,
+    meta: Metadata,
+    stats: RunStats,
+    built_at: Instant,
+}
+
+/// Кэш последнего построенного графа
+struct GraphCache {
+    graph: Option<BuiltGraph>,
+}
+
+impl GraphCache {
+    /// Возвращает граф, перестраивая его, если он устарел или запрошено обновление
+    fn get(&mut self, config: &Config, refresh: bool) -> Result<&BuiltGraph, String> {
+        let stale = match &self.graph {
+            Some(built) => refresh || built.built_at.elapsed() > GRAPH_TTL,
+            None => true,
+        };
+        if stale {
+            let mut stats = RunStats::default();
+            let graph = graph::build_graph(config, &mut stats, &mut |_| {})?;
+            let meta = metadata::collect(config, &graph, &mut stats);
+            self.graph = Some(BuiltGraph { graph, meta, stats, built_at: Instant::now() });
+        }
+        Ok(self.graph.as_ref().expect("граф построен выше"))
+    }
+}
+
+/// Запускает HTTP-сервер с интерактивным графом зависимостей.
+/// Эндпоинты:
+/// - GET /           — страница с интерактивным деревом
+/// - GET /graph.json — граф в формате JSON
+/// - GET /tree.txt   — ASCII-дерево
+/// - GET /legend.html — легенда отметок дерева (фрагмент страницы, при legend)
+/// - GET /node_scale.json — масштаб узлов по метрике node_size_metric (пустой объект, если она не задана)
+///
+/// Параметр `?refresh=1` принудительно перестраивает граф.
+pub fn serve(config: &Config, port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("Не удалось открыть порт {}: {}", port, e))?;
+    eprintln!("Сервер запущен: http://localhost:{}/", port);
+
+    let mut cache = GraphCache { graph: None };
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_connection(stream, config, &mut cache) {
+                    eprintln!("Предупреждение: ошибка обработки запроса: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Предупреждение: ошибка соединения: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Обрабатывает один HTTP-запрос
+fn handle_connection(mut stream: TcpStream, config: &Config, cache: &mut GraphCache) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Заголовки не нужны, но их надо дочитать до пустой строки
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let refresh = query.split('&').any(|p| p == "refresh=1");
+
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain; charset=utf-8", "Метод не поддерживается\n");
+    }
+
+    match path {
+        "/" => respond(&mut stream, "200 OK", "text/html; charset=utf-8", &index_html(config)),
+        "/graph.json" => match cache.get(config, refresh) {
+            Ok(built) => {
+                let body = export::to_json(
+                    &built.graph,
+                    &config.name,
+                    &config.version,
+                    &built.stats,
+                    &built.meta,
+                    None,
+                    config.path_samples.map(|limit| analysis::sample_paths(&built.graph, &config.name, limit)).as_ref(),
+                );
+                respond(&mut stream, "200 OK", "application/json", &body)
+            }
+            Err(e) => respond(&mut stream, "500 Internal Server Error", "text/plain; charset=utf-8", &e),
+        },
+        "/tree.txt" => match cache.get(config, refresh) {
+            Ok(built) => {
+                let body = format!(
+                    "{}:\n{}{}{}{}",
+                    output::heading(config),
+                    tree::render_configured(&built.graph, config, &built.meta, &built.stats, tree::TreeStyle::Ascii, false),
+                    tree::node_limit_note(&built.stats, config.max_nodes).unwrap_or_default(),
+                    if config.legend {
+                        format!("\n{}", tree::Legend::new(&built.graph, &built.stats, config.show_weights, false).render(tree::TreeStyle::Ascii))
+                    } else {
+                        String::new()
+                    },
+                    config.footer.as_ref().map(|f| format!("\n{}\n", f)).unwrap_or_default()
+                );
+                respond(&mut stream, "200 OK", "text/plain; charset=utf-8", &body)
+            }
+            Err(e) => respond(&mut stream, "500 Internal Server Error", "text/plain; charset=utf-8", &e),
+        },
+        "/legend.html" => match cache.get(config, refresh) {
+            Ok(built) => {
+                let body = legend_html(&tree::Legend::new(&built.graph, &built.stats, config.show_weights, true));
+                respond(&mut stream, "200 OK", "text/html; charset=utf-8", &body)
+            }
+            Err(e) => respond(&mut stream, "500 Internal Server Error", "text/plain; charset=utf-8", &e),
+        },
+        "/node_scale.json" => match cache.get(config, refresh) {
+            Ok(built) => {
+                let scale = config
+                    .node_size_metric
+                    .map(|metric| export::node_scale(&built.graph, &config.name, &built.meta, metric))
+                    .unwrap_or_default();
+                let body = serde_json::to_string(&scale).expect("масштаб узлов всегда сериализуется в JSON");
+                respond(&mut stream, "200 OK", "application/json", &body)
+            }
+            Err(e) => respond(&mut stream, "500 Internal Server Error", "text/plain; charset=utf-8", &e),
+        },
+        _ => respond(&mut stream, "404 Not Found", "text/plain; charset=utf-8", "Не найдено\n"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
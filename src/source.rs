@@ -0,0 +1,317 @@
+use crate::graph::{self, Dep, Graph};
+use crate::metadata::{Metadata, NodeMeta};
+use crate::report::SkippedNode;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Read};
+use std::sync::OnceLock;
+
+/// Путь к тестовому или JSON-графу, означающий стандартный ввод
+pub const STDIN_PATH: &str = "-";
+
+/// Содержимое файла графа; путь "-" — стандартный ввод. Ввод читается один раз и запоминается:
+/// за запуск граф может загружаться несколько раз (версия корня, обход, сведения о пакетах, веб-сервер)
+fn read_input(path: &str) -> io::Result<String> {
+    static STDIN: OnceLock<Result<String, String>> = OnceLock::new();
+    if path != STDIN_PATH {
+        return fs::read_to_string(path);
+    }
+    STDIN
+        .get_or_init(|| {
+            let mut raw = String::new();
+            io::stdin().read_to_string(&mut raw).map(|_| raw).map_err(|e| e.to_string())
+        })
+        .clone()
+        .map_err(io::Error::other)
+}
+
+/// Источник прямых зависимостей пакетов для построения графа
+pub trait Source {
+    /// Прямые зависимости пакета name версии version (None — версия неизвестна)
+    fn dependencies(&mut self, name: &str, version: Option<&str>) -> Result<Vec<Dep>, String>;
+
+    /// Версия, которая будет использоваться для зависимости name
+    fn resolve_version(&mut self, name: &str) -> Result<Option<String>, String>;
+
+    /// Подсказка: вскоре понадобятся версии и зависимости пакетов names.
+    /// Источник может получить их заранее (например, параллельно); по умолчанию ничего не делает
+    fn prefetch(&mut self, _names: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Является ли пакет name участником рабочего пространства (его dev-зависимости включаются
+    /// наравне с dev-зависимостями корня). По умолчанию участников нет
+    fn is_member(&self, _name: &str) -> bool {
+        false
+    }
+
+    /// Сохраняет накопленный кэш в постоянное хранилище (перед записью контрольной точки)
+    fn persist(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Все известные версии пакета name, из которых выбирает resolve_version (для --explain).
+    /// По умолчанию единственный кандидат — сама выбранная версия
+    fn candidates(&mut self, name: &str) -> Result<Vec<Candidate>, String> {
+        Ok(self.resolve_version(name)?.into_iter().map(|num| Candidate { num, yanked: false }).collect())
+    }
+}
+
+/// Версия пакета, из которых выбирает источник
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub num: String,
+    /// Версия отозвана автором (yanked)
+    pub yanked: bool,
+}
+
+/// Таблица features пакета: feature -> что она включает ("dep:foo", "foo/bar", другие features)
+pub type Features = BTreeMap<String, Vec<String>>;
+
+/// Тестовый репозиторий: граф, версии и features пакетов, загруженные из файла.
+/// Ключи — канонические имена пакетов (см. graph::canonical_name)
+pub struct TestSource {
+    pub graph: Graph,
+    /// Версии пакетов, указанные в расширенном формате
+    pub versions: HashMap<String, String>,
+    /// Features пакетов, указанные строками `A/feature: ...`
+    pub features: HashMap<String, Features>,
+}
+
+impl Source for TestSource {
+    fn dependencies(&mut self, name: &str, _version: Option<&str>) -> Result<Vec<Dep>, String> {
+        // Пакет без строки в файле считается пакетом без зависимостей
+        Ok(self.graph.get(&graph::canonical_name(name)).cloned().unwrap_or_default())
+    }
+
+    fn resolve_version(&mut self, name: &str) -> Result<Option<String>, String> {
+        // Версия есть только у пакетов, для которых она указана в файле
+        Ok(self.versions.get(&graph::canonical_name(name)).cloned())
+    }
+}
+
+/// Граф, ранее экспортированный в JSON (output_format = "json")
+pub struct JsonSource {
+    pub graph: Graph,
+    pub root: String,
+    pub version: String,
+    /// Сведения о пакетах, сохранённые при экспорте
+    pub meta: Metadata,
+    /// Узлы, не раскрытые при исходном обходе из-за лимита max_nodes
+    pub unexpanded: Vec<String>,
+    /// Узлы, не раскрытые при исходном обходе из-за прерывания (Ctrl-C)
+    pub interrupted: Vec<String>,
+    /// Узлы, не раскрытые ещё до импорта, из которого был построен сохранённый граф
+    pub unexpanded_in_source: Vec<String>,
+    /// Пакеты, не разрешённые при исходном обходе (keep_going), с ошибкой
+    pub unresolved: Vec<SkippedNode>,
+    /// Каноническое имя -> имя узла в graph
+    keys: HashMap<String, String>,
+}
+
+impl JsonSource {
+    /// Есть ли пакет в графе (с точностью до написания имени)
+    pub fn contains(&self, name: &str) -> bool {
+        self.node(name).is_some()
+    }
+
+    fn node(&self, name: &str) -> Option<&Vec<Dep>> {
+        self.keys.get(&graph::canonical_name(name)).and_then(|key| self.graph.get(key))
+    }
+}
+
+impl Source for JsonSource {
+    fn dependencies(&mut self, name: &str, _version: Option<&str>) -> Result<Vec<Dep>, String> {
+        Ok(self.node(name).cloned().unwrap_or_default())
+    }
+
+    fn resolve_version(&mut self, name: &str) -> Result<Option<String>, String> {
+        // Экспорт хранит только версию корня
+        Ok((graph::canonical_name(name) == graph::canonical_name(&self.root)).then(|| self.version.clone()))
+    }
+}
+
+/// Структуры для разбора JSON-экспорта (см. export::JsonGraph)
+#[derive(Deserialize)]
+struct ImportedGraph {
+    root: String,
+    version: String,
+    nodes: Vec<ImportedNode>,
+    #[serde(default)]
+    unexpanded: Vec<String>,
+    #[serde(default)]
+    interrupted: Vec<String>,
+    #[serde(default)]
+    unexpanded_in_source: Vec<String>,
+    #[serde(default)]
+    unresolved: Vec<SkippedNode>,
+}
+
+#[derive(Deserialize)]
+struct ImportedNode {
+    name: String,
+    dependencies: Vec<Dep>,
+    #[serde(flatten)]
+    meta: NodeMeta,
+}
+
+/// Загружает граф, ранее экспортированный в JSON
+pub fn load_json_graph(path: &str) -> Result<JsonSource, String> {
+    let raw = read_input(path)
+        .map_err(|e| format!("Ошибка чтения JSON-графа '{}': {}", path, e))?;
+    parse_json_graph(&raw, path)
+}
+
+/// Разбирает JSON-экспорт графа; origin описывает источник для сообщений об ошибках
+pub fn parse_json_graph(raw: &str, origin: &str) -> Result<JsonSource, String> {
+    let doc: ImportedGraph = serde_json::from_str(raw)
+        .map_err(|e| format!("Ошибка разбора JSON-графа '{}': {}", origin, e))?;
+    let mut source = JsonSource {
+        graph: Graph::new(),
+        root: doc.root,
+        version: doc.version,
+        meta: Metadata::new(),
+        unexpanded: doc.unexpanded,
+        interrupted: doc.interrupted,
+        unexpanded_in_source: doc.unexpanded_in_source,
+        unresolved: doc.unresolved,
+        keys: HashMap::new(),
+    };
+    for node in doc.nodes {
+        if !node.meta.is_empty() {
+            source.meta.insert(node.name.clone(), node.meta);
+        }
+        source.keys.insert(graph::canonical_name(&node.name), node.name.clone());
+        source.graph.insert(node.name, node.dependencies);
+    }
+    Ok(source)
+}
+
+/// Загружает тестовый граф из файла.
+/// Простой формат: `A: B C`. Расширенный формат добавляет версию пакета,
+/// требования к версиям и пометки зависимостей: `A 1.2.0: B@^1 [dev] C@2 [optional]`,
+/// а также переименования: `my_b=B@^1` — зависимость от пакета B, объявленная как my_b.
+/// Строка `A/full: default dep:D` задаёт feature full пакета A в синтаксисе Cargo.
+/// Пометки `[build]`, `[dev]` и `[optional]` относятся к предшествующей зависимости
+/// и могут объединяться через запятую: `D [build,optional]`
+pub fn load_test_graph(path: &str) -> Result<TestSource, String> {
+    let raw = read_input(path)
+        .map_err(|e| format!("Ошибка чтения тестового графа '{}': {}", path, e))?;
+    parse_test_graph(&raw)
+}
+
+/// Разбирает тестовый граф в формате load_test_graph
+pub fn parse_test_graph(raw: &str) -> Result<TestSource, String> {
+    let mut source = TestSource { graph: Graph::new(), versions: HashMap::new(), features: HashMap::new() };
+
+    for (lineno, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() { continue; }
+        let format_error = |what: &str| format!("Ошибка формата в строке {}: {}: {}", lineno + 1, what, line);
+        let Some((head, deps)) = line.split_once(':') else {
+            return Err(format!("Ошибка формата в строке {}: {}", lineno + 1, line));
+        };
+
+        let mut head = head.split_whitespace();
+        let pkg = head.next().ok_or_else(|| format_error("не указано имя пакета"))?.to_string();
+        if let Some((pkg, feature)) = pkg.split_once('/') {
+            if pkg.is_empty() || feature.is_empty() || head.next().is_some() {
+                return Err(format_error("ожидается 'пакет/feature:'"));
+            }
+            let enables = deps.split_whitespace().map(str::to_string).collect();
+            source.features.entry(graph::canonical_name(pkg)).or_default().insert(feature.to_string(), enables);
+            continue;
+        }
+        if let Some(version) = head.next() {
+            source.versions.insert(graph::canonical_name(&pkg), version.to_string());
+        }
+        if head.next().is_some() {
+            return Err(format_error("после версии пакета ожидается ':'"));
+        }
+
+        let mut parsed: Vec<Dep> = Vec::new();
+        for token in deps.split_whitespace() {
+            if let Some(flags) = token.strip_prefix('[') {
+                let flags = flags.strip_suffix(']').ok_or_else(|| format_error("незакрытая скобка пометки"))?;
+                let dep = parsed
+                    .last_mut()
+                    .ok_or_else(|| format_error(&format!("пометка [{}] без зависимости", flags)))?;
+                for flag in flags.split(',').map(str::trim) {
+                    match flag {
+                        "build" | "dev" => dep.kind = flag.to_string(),
+                        "optional" => dep.optional = true,
+                        other => return Err(format_error(&format!("неизвестная пометка '{}'", other))),
+                    }
+                }
+            } else {
+                let (name, req) = match token.split_once('@') {
+                    Some((name, req)) if !name.is_empty() && !req.is_empty() => (name, Some(req.to_string())),
+                    Some(_) => return Err(format_error(&format!("неверная зависимость '{}'", token))),
+                    None => (token, None),
+                };
+                let (alias, name) = match name.split_once('=') {
+                    Some((alias, name)) if !alias.is_empty() && !name.is_empty() => (Some(alias.to_string()), name),
+                    Some(_) => return Err(format_error(&format!("неверная зависимость '{}'", token))),
+                    None => (None, name),
+                };
+                parsed.push(Dep { req, alias, ..Dep::normal(name) });
+            }
+        }
+        source.graph.insert(graph::canonical_name(&pkg), parsed);
+    }
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_extended_test_format() {
+        let raw = "app 1.0: log@^1 cc [build,optional] rand_alias=rand@^2 [dev]\nlog: \napp/std: log/std cc\nRand_Core 0.6:\n";
+        let source = parse_test_graph(raw).expect("граф разбирается");
+        assert_eq!(
+            source.graph["app"],
+            vec![
+                Dep { req: Some("^1".to_string()), ..Dep::normal("log") },
+                Dep { kind: "build".to_string(), optional: true, ..Dep::normal("cc") },
+                Dep {
+                    kind: "dev".to_string(),
+                    req: Some("^2".to_string()),
+                    alias: Some("rand_alias".to_string()),
+                    ..Dep::normal("rand")
+                },
+            ]
+        );
+        assert!(source.graph["log"].is_empty());
+        // Узлы и версии хранятся под каноническим именем
+        assert!(source.graph["rand-core"].is_empty());
+        assert_eq!(source.versions.get("app").map(String::as_str), Some("1.0"));
+        assert_eq!(source.versions.get("rand-core").map(String::as_str), Some("0.6"));
+        assert!(!source.versions.contains_key("log"));
+        // Строка features не добавляет узел в граф
+        assert_eq!(source.features["app"]["std"], vec!["log/std".to_string(), "cc".to_string()]);
+        assert_eq!(source.graph.len(), 3);
+    }
+
+    #[test]
+    fn reports_format_errors_with_line_numbers() {
+        let error = |raw: &str| match parse_test_graph(raw) {
+            Ok(_) => panic!("ожидалась ошибка разбора: {}", raw),
+            Err(e) => e,
+        };
+        assert_eq!(error("A: B\nC D"), "Ошибка формата в строке 2: C D");
+        assert_eq!(
+            error("\nA: B [build"),
+            "Ошибка формата в строке 2: незакрытая скобка пометки: A: B [build"
+        );
+        assert_eq!(error("A: B [test]"), "Ошибка формата в строке 1: неизвестная пометка 'test': A: B [test]");
+        assert_eq!(error("A: [dev] B"), "Ошибка формата в строке 1: пометка [dev] без зависимости: A: [dev] B");
+        assert_eq!(error("A: B@"), "Ошибка формата в строке 1: неверная зависимость 'B@': A: B@");
+        assert_eq!(error("A: =B"), "Ошибка формата в строке 1: неверная зависимость '=B': A: =B");
+        assert_eq!(error("A/: B"), "Ошибка формата в строке 1: ожидается 'пакет/feature:': A/: B");
+        assert_eq!(error("A/std 1.0: B"), "Ошибка формата в строке 1: ожидается 'пакет/feature:': A/std 1.0: B");
+        assert_eq!(error("A 1.0 B: C"), "Ошибка формата в строке 1: после версии пакета ожидается ':': A 1.0 B: C");
+    }
+}
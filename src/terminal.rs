@@ -0,0 +1,75 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Оборачивает текст в гиперссылку OSC 8
+pub fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Поддерживает ли терминал на stdout гиперссылки OSC 8.
+/// Переменная FORCE_HYPERLINK=1/0 позволяет переопределить определение
+pub fn supports_hyperlinks() -> bool {
+    if let Ok(force) = env::var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+    if env::var("TERM").is_ok_and(|t| t == "dumb") {
+        return false;
+    }
+
+    // Эмуляторы терминала, о которых известно, что они поддерживают OSC 8
+    let known_env = ["WT_SESSION", "KITTY_WINDOW_ID", "KONSOLE_VERSION", "WEZTERM_EXECUTABLE"];
+    if known_env.iter().any(|v| env::var_os(v).is_some()) {
+        return true;
+    }
+    if env::var("TERM_PROGRAM")
+        .is_ok_and(|p| matches!(p.as_str(), "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty"))
+    {
+        return true;
+    }
+    if env::var("TERM").is_ok_and(|t| t.contains("kitty") || t.contains("alacritty") || t.contains("foot")) {
+        return true;
+    }
+    // GNOME Terminal и другие терминалы на VTE поддерживают OSC 8 начиная с 0.50
+    env::var("VTE_VERSION")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .is_some_and(|v| v >= 5000)
+}
+
+/// Высота терминала в строках: переменная LINES или `stty size`; None, если определить не удалось
+pub fn height() -> Option<usize> {
+    if let Some(lines) = env::var("LINES").ok().and_then(|l| l.parse().ok()) {
+        return Some(lines);
+    }
+    let tty = File::open("/dev/tty").ok()?;
+    let out = Command::new("stty").arg("size").stdin(tty).stderr(Stdio::null()).output().ok()?;
+    String::from_utf8(out.stdout).ok()?.split_whitespace().next()?.parse().ok()
+}
+
+/// Показывает text через пейджер, как git: только если stdout — терминал, а текст не помещается на экран.
+/// Пейджер берётся из CARGO_ROUTES_PAGER или PAGER (по умолчанию `less -R`, чтобы сохранить цвета и ссылки);
+/// пустое значение или `cat` отключают его. Возвращает false, если текст нужно вывести самостоятельно
+pub fn page(text: &str) -> bool {
+    if !io::stdout().is_terminal() || height().is_none_or(|h| text.lines().count() < h) {
+        return false;
+    }
+    let pager = env::var("CARGO_ROUTES_PAGER").or_else(|_| env::var("PAGER")).unwrap_or_else(|_| "less -R".to_string());
+    if pager.trim().is_empty() || pager.trim() == "cat" {
+        return false;
+    }
+    // Через оболочку: в переменной могут быть аргументы
+    let Ok(mut child) = Command::new("sh").arg("-c").arg(&pager).stdin(Stdio::piped()).spawn() else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Пользователь мог выйти из пейджера, не дочитав: обрыв канала не ошибка
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let _ = child.wait();
+    true
+}
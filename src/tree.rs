@@ -0,0 +1,564 @@
+use crate::analysis::{self, Reach};
+use crate::config::Config;
+use crate::graph::{Dep, Graph, ResolvedNode};
+use crate::metadata::{self, Metadata, Stale};
+use crate::report::RunStats;
+use crate::terminal;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// Оформление дерева
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum TreeStyle {
+    /// ASCII-дерево с псевдографикой
+    #[default]
+    Ascii,
+    /// Вложенный маркированный список Markdown
+    Markdown,
+}
+
+/// Параметры рендеринга дерева
+#[derive(Default)]
+pub struct TreeOptions<'a> {
+    pub style: TreeStyle,
+    /// Ограничение глубины печати
+    pub max_depth: Option<usize>,
+    /// Ограничивать глубину по длине пути в каждой ветви, а не по кратчайшему расстоянию от корня
+    pub depth_per_branch: bool,
+    /// Глубина отображения: поддеревья глубже неё сворачиваются в строку `... ещё пакетов: N`.
+    /// В отличие от max_depth, граф при этом остаётся полным
+    pub display_depth: Option<usize>,
+    /// Размеры транзитивных замыканий узлов (см. analysis::transitive_weights)
+    pub weights: Option<&'a HashMap<String, usize>>,
+    /// Печатать вес рядом с именем: `clap (+34)`
+    pub show_weights: bool,
+    /// Сортировать детей по убыванию веса
+    pub sort_by_weight: bool,
+    /// Делать имена пакетов ссылками на crates.io: OSC 8 в ASCII, ссылки на crates.io и docs.rs в Markdown
+    pub hyperlinks: bool,
+    /// Сведения о пакетах: число зависимых пакетов печатается рядом с именем
+    pub meta: Option<&'a Metadata>,
+    /// Глубины и числа путей от корня: печатаются рядом с именем (см. analysis::reach)
+    pub reach: Option<&'a HashMap<String, Reach>>,
+    /// Давно не обновлявшиеся пакеты: помечаются датой последнего релиза (см. metadata::stale_crates)
+    pub stale: Option<&'a BTreeMap<String, Stale>>,
+    /// Категории и ключевые слова, пакеты с которыми помечаются в дереве (нужны сведения о пакетах)
+    pub highlight_tags: &'a [String],
+    /// Категории и ключевые слова, пакеты с которыми печатаются без поддеревьев
+    pub fold_tags: &'a [String],
+    /// Печатать требование родителя к версии и разрешённую версию (нужны сведения о пакетах)
+    pub show_requirements: bool,
+    /// Подпись корня вместо его имени (root_label)
+    pub root_label: Option<&'a str>,
+    /// Ссылки на документацию версий пакетов на docs.rs для Markdown; без ссылки — на последнюю версию
+    pub docs_links: Option<&'a HashMap<String, String>>,
+}
+
+/// Рендерит дерево с параметрами из конфигурации.
+/// hyperlinks — выводить ли имена пакетов ссылками, meta — сведения о пакетах,
+/// stats — версии пакетов, выбранные при обходе (для ссылок на docs.rs)
+pub fn render_configured(
+    graph: &Graph,
+    config: &Config,
+    meta: &Metadata,
+    stats: &RunStats,
+    style: TreeStyle,
+    hyperlinks: bool,
+) -> String {
+    let weights = (config.show_weights || config.sort_by_weight).then(|| analysis::transitive_weights(graph));
+    let reach = config.show_paths.then(|| analysis::reach(graph, &config.name));
+    let stale = config.stale_after_days.map(|days| metadata::stale_crates(graph, meta, days, metadata::stale_reference(config)));
+    let docs_links = (style == TreeStyle::Markdown && hyperlinks).then(|| {
+        let docs_link = |name: &str| metadata::docs_url(name, metadata::docs_version(config, stats, name));
+        analysis::all_nodes(graph).into_iter().map(|name| (name.to_string(), docs_link(name))).collect()
+    });
+    let opts = TreeOptions {
+        meta: Some(meta),
+        reach: reach.as_ref(),
+        stale: stale.as_ref(),
+        docs_links: docs_links.as_ref(),
+        ..configured_options(config, weights.as_ref(), style, hyperlinks)
+    };
+    render_tree(graph, &config.name, &opts)
+}
+
+/// Рендерит граф в виде дерева (учитывает max_depth).
+/// Глубина пакета, как и при обходе, — длина кратчайшего пути от корня: пакет раскрывается, если она
+/// меньше max_depth, где бы в дереве он ни встретился. При depth_per_branch считается длина пути в ветви
+pub fn render_tree(graph: &Graph, root: &str, opts: &TreeOptions) -> String {
+    let depths = (opts.max_depth.is_some() && !opts.depth_per_branch).then(|| analysis::node_depths(graph, root));
+    let mut writer = TreeWriter { out: String::new(), graph, opts, seen: HashSet::new(), depths, printed_edges: HashSet::new(), truncated: false };
+    writer.write_node(root, None, "", true, 0);
+    if writer.truncated {
+        let (nodes, edges) = writer.hidden_total();
+        let text = format!("Скрыто при печати: пакетов: {}, рёбер: {}", nodes, edges);
+        let _ = match opts.style {
+            TreeStyle::Ascii => writeln!(writer.out, "{}", text),
+            TreeStyle::Markdown => writeln!(writer.out, "\n_{}_", text),
+        };
+    }
+    writer.out
+}
+
+/// Рендерит поддеревья зависимостей deps так, как они выглядели бы под общим родителем
+pub fn render_children(graph: &Graph, deps: &[&Dep], opts: &TreeOptions) -> String {
+    let mut writer = TreeWriter { out: String::new(), graph, opts, seen: HashSet::new(), depths: None, printed_edges: HashSet::new(), truncated: false };
+    for (i, dep) in deps.iter().enumerate() {
+        writer.write_node(&dep.name, Some(dep), "", i == deps.len() - 1, 1);
+    }
+    writer.out
+}
+
+/// Параметры дерева из конфигурации; weights — размеры замыканий, если они нужны
+pub fn configured_options<'a>(
+    config: &'a Config,
+    weights: Option<&'a HashMap<String, usize>>,
+    style: TreeStyle,
+    hyperlinks: bool,
+) -> TreeOptions<'a> {
+    TreeOptions {
+        style,
+        max_depth: config.max_depth,
+        depth_per_branch: config.depth_per_branch,
+        display_depth: config.display_depth,
+        weights,
+        show_weights: config.show_weights,
+        sort_by_weight: config.sort_by_weight,
+        hyperlinks,
+        meta: None,
+        reach: None,
+        stale: None,
+        highlight_tags: &config.highlight_tags,
+        fold_tags: &config.fold_tags,
+        show_requirements: config.show_requirements,
+        root_label: config.root_label.as_deref(),
+        docs_links: None,
+    }
+}
+
+/// Состояние печати дерева.
+/// - seen предотвращает бесконечные циклы при печати
+/// - depths — кратчайшие расстояния от корня; без них глубина считается по пути в ветви
+/// - printed_edges и truncated нужны для итога по скрытой при печати части графа
+struct TreeWriter<'a> {
+    out: String,
+    graph: &'a Graph,
+    opts: &'a TreeOptions<'a>,
+    seen: HashSet<String>,
+    depths: Option<HashMap<&'a str, usize>>,
+    printed_edges: HashSet<(String, String)>,
+    truncated: bool,
+}
+
+impl TreeWriter<'_> {
+    /// Печать узла графа и его поддерева.
+    /// - dep — ребро, по которому достигнут узел (None для корня): вид зависимости (не-normal
+    ///   помечаются как [build]), опциональность, локальность и имя, под которым она объявлена в Cargo.toml
+    /// - prefix и last задают отступы ASCII-дерева
+    /// - current_depth и max_depth контролируют глубину печати
+    fn write_node(&mut self, node: &str, dep: Option<&Dep>, prefix: &str, last: bool, current_depth: usize) {
+        let kind_mark = match dep {
+            Some(dep) if dep.kind != "normal" => format!(" [{}]", dep.kind),
+            _ => String::new(),
+        };
+        let optional_mark = if dep.is_some_and(|d| d.optional) { " (optional)" } else { "" };
+        let local_mark = if dep.is_some_and(|d| d.local) { " (local)" } else { "" };
+        let alias_mark = dep.and_then(|d| d.alias.as_ref()).map(|a| format!(" (как {})", a)).unwrap_or_default();
+        // Требование родителя и версия, до которой оно разрешилось: `serde (^1.0.150) -> v1.0.210`
+        let requirement_mark = if self.opts.show_requirements {
+            let req = dep.and_then(|d| d.req.as_ref()).map(|r| format!(" ({})", r)).unwrap_or_default();
+            match self.opts.meta.and_then(|m| m.get(node)?.version.as_ref()) {
+                Some(version) if req.is_empty() => format!(" v{}", version),
+                Some(version) => format!("{} -> v{}", req, version),
+                None => req,
+            }
+        } else {
+            String::new()
+        };
+        let weight_mark = match self.opts.weights.and_then(|w| w.get(node)) {
+            Some(weight) if self.opts.show_weights => format!(" (+{})", weight),
+            _ => String::new(),
+        };
+        let reach_mark = match self.opts.reach.and_then(|r| r.get(node)) {
+            Some(reach) => format!(" (глубина {}, путей: {})", reach.depth, reach.paths),
+            None => String::new(),
+        };
+        let members_mark = match self.opts.meta.and_then(|m| m.get(node)?.members.as_ref()) {
+            Some(members) => format!(" (группа, пакетов: {})", members.len()),
+            None => String::new(),
+        };
+        // В объединённом графе (merge) отмечаются пакеты, общие для нескольких источников
+        let sources_mark = match self.opts.meta.and_then(|m| m.get(node)?.sources.as_ref()) {
+            Some(sources) if sources.len() > 1 => format!(" (источники: {})", sources.join(", ")),
+            _ => String::new(),
+        };
+        let dependents_mark = match self.opts.meta.and_then(|m| m.get(node)?.dependents) {
+            Some(count) => format!(" (зависимых: {})", count),
+            None => String::new(),
+        };
+        let docs_mark = match self.opts.meta.and_then(|m| m.get(node)?.docs_built) {
+            Some(false) => " (документация не собрана)",
+            _ => "",
+        };
+        let stale_mark = match self.opts.stale.and_then(|s| s.get(node)) {
+            Some(stale) => format!(" (нет релизов с {})", stale.released),
+            None => String::new(),
+        };
+        let tags_mark = match self.opts.meta.and_then(|m| m.get(node)) {
+            Some(m) if !self.opts.highlight_tags.is_empty() => match m.matching_tags(self.opts.highlight_tags).as_slice() {
+                [] => String::new(),
+                tags => format!(" [{}]", tags.join(", ")),
+            },
+            _ => String::new(),
+        };
+        let note_mark = match self.opts.meta.and_then(|m| m.get(node)?.note.as_ref()) {
+            Some(note) => format!(" — {}", note),
+            None => String::new(),
+        };
+        // Узел, которого нет в графе, не раскрывался при обходе (лимит узлов или ошибка)
+        let unexpanded_mark = match self.opts.meta.and_then(|m| m.get(node)?.unresolved.as_ref()) {
+            Some(error) => format!(" (не разрешён: {})", error),
+            None if !self.graph.contains_key(node) => " (не раскрыт)".to_string(),
+            None => String::new(),
+        };
+        let name = match (dep, self.opts.root_label) {
+            (None, Some(label)) => label.to_string(),
+            _ => self.format_name(node),
+        };
+        let line = format!(
+            "{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}",
+            name,
+            requirement_mark,
+            tags_mark,
+            members_mark,
+            alias_mark,
+            weight_mark,
+            reach_mark,
+            sources_mark,
+            dependents_mark,
+            stale_mark,
+            docs_mark,
+            kind_mark,
+            optional_mark,
+            local_mark,
+            unexpanded_mark,
+            note_mark
+        );
+        self.emit_node(prefix, last, current_depth, &line);
+
+        // Если узел уже встречался — помечаем цикл и не углубляемся
+        if !self.seen.insert(node.to_string()) {
+            self.emit_note(prefix, current_depth, &format!("(цикл: узел {})", node));
+            return;
+        }
+
+        // Проверяем ограничение глубины для печати
+        let depth = self.depths.as_ref().and_then(|d| d.get(node).copied()).unwrap_or(current_depth);
+        if self.opts.max_depth.is_some_and(|max| depth >= max) {
+            // показываем, что дальше есть дети, но не раскрываем их
+            if self.graph.get(node).is_some_and(|children| !children.is_empty()) {
+                let (nodes, edges) = self.hidden_below(node);
+                self.emit_note(
+                    prefix,
+                    current_depth,
+                    &format!("... (ограничение глубины: скрыто пакетов: {}, рёбер: {})", nodes, edges),
+                );
+                self.truncated = true;
+            }
+            return;
+        }
+
+        // Ниже глубины отображения и у пакетов с метками из fold_tags поддерево сворачивается в одну строку
+        let folded_by_tag = current_depth > 0
+            && !self.opts.fold_tags.is_empty()
+            && self.opts.meta.and_then(|m| m.get(node)).is_some_and(|m| !m.matching_tags(self.opts.fold_tags).is_empty());
+        if folded_by_tag || self.opts.display_depth.is_some_and(|display| current_depth >= display) {
+            let (nodes, edges) = self.hidden_below(node);
+            if edges > 0 {
+                let new_prefix = if last { format!("{}    ", prefix) } else { format!("{}│   ", prefix) };
+                self.emit_node(&new_prefix, true, current_depth + 1, &format!("... ещё пакетов: {}, рёбер: {}", nodes, edges));
+                self.truncated = true;
+            }
+            return;
+        }
+
+        if let Some(children) = self.graph.get(node) {
+            let mut children: Vec<&Dep> = children.iter().collect();
+            if let (true, Some(weights)) = (self.opts.sort_by_weight, self.opts.weights) {
+                // Стабильная сортировка: при равном весе сохраняется исходный порядок
+                children.sort_by_key(|c| std::cmp::Reverse(weights.get(&c.name).copied().unwrap_or(0)));
+            }
+            let new_prefix = if last { format!("{}    ", prefix) } else { format!("{}│   ", prefix) };
+            for (i, child) in children.iter().enumerate() {
+                self.printed_edges.insert((node.to_string(), child.name.clone()));
+                let is_last = i == children.len() - 1;
+                self.write_node(&child.name, Some(child), &new_prefix, is_last, current_depth + 1);
+            }
+        }
+    }
+
+    /// Размер поддерева, скрытого под узлом: пакеты его замыкания, которые ещё не напечатаны выше,
+    /// и рёбра, исходящие из самого узла и из этих пакетов
+    fn hidden_below(&self, node: &str) -> (usize, usize) {
+        let hidden: Vec<&str> = analysis::closure(self.graph, node).into_iter().filter(|name| !self.seen.contains(*name)).collect();
+        let edges = std::iter::once(node).chain(hidden.iter().copied()).map(|name| self.graph.get(name).map_or(0, Vec::len)).sum();
+        (hidden.len(), edges)
+    }
+
+    /// Сколько пакетов и рёбер графа так и не попало в напечатанное дерево.
+    /// Пакет, скрытый в одной ветви и напечатанный в другой, скрытым не считается
+    fn hidden_total(&self) -> (usize, usize) {
+        let nodes = analysis::all_nodes(self.graph).into_iter().filter(|name| !self.seen.contains(*name)).count();
+        let edges: HashSet<(&str, &str)> =
+            self.graph.iter().flat_map(|(from, deps)| deps.iter().map(move |dep| (from.as_str(), dep.name.as_str()))).collect();
+        let printed = edges.iter().filter(|(from, to)| self.printed_edges.contains(&(from.to_string(), to.to_string()))).count();
+        (nodes, edges.len() - printed)
+    }
+
+    /// Имя пакета, при необходимости оформленное ссылкой
+    fn format_name(&self, node: &str) -> String {
+        let crates_io = format!("https://crates.io/crates/{}", node);
+        // У группового узла нет страницы на crates.io
+        let group = self.opts.meta.is_some_and(|m| m.get(node).is_some_and(|m| m.members.is_some()));
+        match (self.opts.style, self.opts.hyperlinks && !group) {
+            (TreeStyle::Ascii, true) => terminal::hyperlink(&crates_io, node),
+            (TreeStyle::Ascii, false) => node.to_string(),
+            (TreeStyle::Markdown, true) => {
+                let docs = self.opts.docs_links.and_then(|links| links.get(node).cloned());
+                let docs = docs.unwrap_or_else(|| metadata::docs_url(node, "latest"));
+                format!("[`{}`]({}) ([docs]({}))", node, crates_io, docs)
+            }
+            (TreeStyle::Markdown, false) => format!("`{}`", node),
+        }
+    }
+
+    /// Строка узла
+    fn emit_node(&mut self, prefix: &str, last: bool, depth: usize, text: &str) {
+        let _ = match self.opts.style {
+            TreeStyle::Ascii => {
+                let connector = if last { "└── " } else { "├── " };
+                writeln!(self.out, "{}{}{}", prefix, connector, text)
+            }
+            TreeStyle::Markdown => writeln!(self.out, "{}- {}", "  ".repeat(depth), text),
+        };
+    }
+
+    /// Пояснение под узлом (цикл, ограничение глубины)
+    fn emit_note(&mut self, prefix: &str, depth: usize, text: &str) {
+        let _ = match self.opts.style {
+            TreeStyle::Ascii => writeln!(self.out, "{}    {}", prefix, text),
+            TreeStyle::Markdown => writeln!(self.out, "{}- _{}_", "  ".repeat(depth + 1), text),
+        };
+    }
+}
+
+/// Строка предварительного дерева для узла, только что разрешённого при обходе.
+/// Отступ задаётся глубиной: при обходе в глубину поддеревья печатаются подряд и строки складываются в дерево
+pub fn stream_line(node: &ResolvedNode) -> String {
+    let version = node.version.map(|v| format!(" v{}", v)).unwrap_or_default();
+    let deps = match node.dependencies.len() {
+        0 => String::new(),
+        n => format!(" (зависимостей: {})", n),
+    };
+    format!("{}{}{}{}", "    ".repeat(node.depth), node.name, version, deps)
+}
+
+/// Примечание для вывода, если обход был остановлен лимитом max_nodes или прерван по Ctrl-C,
+/// либо импортированный граф был неполным ещё при исходном обходе
+pub fn node_limit_note(stats: &RunStats, max_nodes: Option<usize>) -> Option<String> {
+    if stats.interrupted {
+        let hidden = stats.truncated_by_interrupt.len();
+        return Some(format!("... граф неполный: обход прерван (Ctrl-C), не раскрыто узлов: {}\n", hidden));
+    }
+    let mut note = String::new();
+    let hidden = stats.truncated_by_max_nodes.len();
+    if hidden > 0 {
+        note.push_str(&match max_nodes {
+            Some(max) => format!("... граф усечён: достигнут лимит max_nodes = {}, не раскрыто узлов: {}\n", max, hidden),
+            // history show: лимит исходного запуска неизвестен
+            None => format!("... граф усечён: не раскрыто узлов: {}\n", hidden),
+        });
+    }
+    let hidden = stats.unexpanded_in_source.len();
+    if hidden > 0 {
+        note.push_str(&format!("... граф неполный: в импортированном графе не раскрыто узлов: {}\n", hidden));
+    }
+    (!note.is_empty()).then_some(note)
+}
+
+/// Легенда под графом (legend): отметки, которые встречаются в выводе, и их значения,
+/// число циклов в графе и число ветвей, оставшихся нераскрытыми
+pub struct Legend {
+    /// Отметка и её значение
+    pub marks: Vec<(&'static str, &'static str)>,
+    pub cycles: usize,
+    pub truncated: usize,
+}
+
+impl Legend {
+    /// Легенда графа. Отметки берутся из рёбер и статистики обхода, поэтому ASCII, Markdown
+    /// и веб-страница описывают одно и то же. show_weights — печатаются ли размеры замыканий,
+    /// html — отметки интерактивного дерева веб-страницы, которое показывает повтор пакета только на настоящем цикле
+    pub fn new(graph: &Graph, stats: &RunStats, show_weights: bool, html: bool) -> Legend {
+        let deps = || graph.values().flatten();
+        let cycles = analysis::strongly_connected_components(graph).iter().filter(|c| analysis::is_cycle(graph, c)).count();
+        let mut parents: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for (name, children) in graph {
+            for dep in children {
+                parents.entry(dep.name.as_str()).or_default().insert(name.as_str());
+            }
+        }
+        let repeats = parents.values().any(|p| p.len() > 1);
+
+        let mut marks = Vec::new();
+        let mut mark = |present: bool, symbol, meaning| {
+            if present {
+                marks.push((symbol, meaning));
+            }
+        };
+        mark(deps().any(|d| d.kind == "build"), "[build]", "зависимость сборочного скрипта (build-dependencies)");
+        mark(deps().any(|d| d.kind == "dev"), "[dev]", "зависимость тестов, примеров и бенчмарков (dev-dependencies)");
+        mark(deps().any(|d| d.optional), "(optional)", "опциональная зависимость, подключается через features");
+        mark(deps().any(|d| d.local), "(local)", "локальный пакет (path-зависимость)");
+        mark(deps().any(|d| d.alias.is_some()), "(как имя)", "зависимость переименована в Cargo.toml");
+        mark(!html && show_weights, "(+N)", "пакетов в транзитивном замыкании");
+        if html {
+            mark(cycles > 0, "(цикл)", "зависимость замыкает цикл и дальше не раскрывается");
+        } else {
+            mark(repeats || cycles > 0, "(цикл: узел …)", "пакет уже напечатан выше, его поддерево не повторяется");
+            mark(!stats.truncated_by_depth.is_empty(), "... (ограничение глубины …)", "зависимости глубже max_depth не раскрывались");
+        }
+        mark(!stats.truncated_by_max_nodes.is_empty(), "(не раскрыт)", "зависимости пакета не получены (max_nodes)");
+        mark(!stats.truncated_by_interrupt.is_empty(), "(не раскрыт)", "зависимости пакета не получены: обход прерван (Ctrl-C)");
+        mark(!stats.unexpanded_in_source.is_empty(), "(не раскрыт)", "зависимости пакета отсутствуют в импортированном графе");
+        mark(!stats.unresolved.is_empty(), "(не разрешён: …)", "пакет не удалось получить (keep_going)");
+
+        let truncated: HashSet<&str> = stats
+            .truncated_by_depth
+            .iter()
+            .chain(&stats.truncated_by_max_nodes)
+            .chain(&stats.truncated_by_interrupt)
+            .chain(&stats.unexpanded_in_source)
+            .map(String::as_str)
+            .chain(stats.unresolved.iter().map(|node| node.name.as_str()))
+            .collect();
+        Legend { marks, cycles, truncated: truncated.len() }
+    }
+
+    /// Итоговая строка: циклы и нераскрытые ветви
+    pub fn summary(&self) -> String {
+        format!("Циклов в графе: {}, нераскрытых ветвей: {}", self.cycles, self.truncated)
+    }
+
+    /// Легенда для ASCII-дерева или Markdown
+    pub fn render(&self, style: TreeStyle) -> String {
+        let mut out = String::new();
+        match style {
+            TreeStyle::Ascii => {
+                if !self.marks.is_empty() {
+                    out.push_str("Обозначения:\n");
+                }
+                let width = self.marks.iter().map(|(symbol, _)| symbol.chars().count()).max().unwrap_or(0);
+                for (symbol, meaning) in &self.marks {
+                    let pad = width - symbol.chars().count();
+                    let _ = writeln!(out, "  {}{} — {}", symbol, " ".repeat(pad), meaning);
+                }
+                let _ = writeln!(out, "{}", self.summary());
+            }
+            TreeStyle::Markdown => {
+                if !self.marks.is_empty() {
+                    out.push_str("**Обозначения**\n\n| Отметка | Значение |\n|---|---|\n");
+                    for (symbol, meaning) in &self.marks {
+                        let _ = writeln!(out, "| `{}` | {} |", symbol, meaning);
+                    }
+                    out.push('\n');
+                }
+                let _ = writeln!(out, "{}", self.summary());
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A -> B -> C и A -> C: при печати C встречается впервые под B, на глубине 2 по пути,
+    /// хотя кратчайшее расстояние до него от корня — 1
+    fn diamond() -> Graph {
+        Graph::from([
+            ("A".to_string(), vec![Dep::normal("B"), Dep::normal("C")]),
+            ("B".to_string(), vec![Dep::normal("C")]),
+            ("C".to_string(), vec![Dep::normal("D")]),
+            ("D".to_string(), vec![]),
+        ])
+    }
+
+    fn render(max_depth: Option<usize>, depth_per_branch: bool) -> String {
+        render_tree(&diamond(), "A", &TreeOptions { max_depth, depth_per_branch, ..TreeOptions::default() })
+    }
+
+    #[test]
+    fn rendering_uses_minimum_distance() {
+        let out = render(Some(2), false);
+        assert_eq!(
+            out,
+            "└── A\n    ├── B\n    │   └── C\n    │       └── D\n    └── C\n        (цикл: узел C)\n"
+        );
+    }
+
+    #[test]
+    fn rendering_per_branch_cuts_by_path_length() {
+        let out = render(Some(2), true);
+        assert!(out.contains("│   └── C\n    │       ... (ограничение глубины: скрыто пакетов: 1, рёбер: 1)\n"), "{}", out);
+        assert!(!out.contains("D"));
+    }
+
+    #[test]
+    fn zero_depth_prints_only_root() {
+        assert_eq!(
+            render(Some(0), false),
+            "└── A\n    ... (ограничение глубины: скрыто пакетов: 3, рёбер: 4)\nСкрыто при печати: пакетов: 3, рёбер: 4\n"
+        );
+    }
+
+    #[test]
+    fn folded_counts_exclude_printed_nodes() {
+        // A -> B -> D и A -> C -> B: B уже напечатан, под C скрыт только D
+        let graph = Graph::from([
+            ("A".to_string(), vec![Dep::normal("B"), Dep::normal("C")]),
+            ("B".to_string(), vec![Dep::normal("D")]),
+            ("C".to_string(), vec![Dep::normal("B")]),
+            ("D".to_string(), vec![]),
+        ]);
+        let out = render_tree(&graph, "A", &TreeOptions { display_depth: Some(1), ..TreeOptions::default() });
+        assert_eq!(
+            out,
+            "└── A\n    ├── B\n    │   └── ... ещё пакетов: 1, рёбер: 1\n    └── C\n        └── ... ещё пакетов: 1, рёбер: 1\n\
+             Скрыто при печати: пакетов: 1, рёбер: 2\n"
+        );
+    }
+
+    #[test]
+    fn legend_lists_present_marks_and_counts() {
+        let mut graph = diamond();
+        graph.insert("D".to_string(), vec![Dep { kind: "build".to_string(), ..Dep::normal("B") }]);
+        let stats = RunStats { truncated_by_max_nodes: vec!["E".to_string()], ..RunStats::default() };
+        let legend = Legend::new(&graph, &stats, false, false);
+        let symbols: Vec<&str> = legend.marks.iter().map(|(symbol, _)| *symbol).collect();
+        assert_eq!(symbols, ["[build]", "(цикл: узел …)", "(не раскрыт)"]);
+        assert_eq!((legend.cycles, legend.truncated), (1, 1));
+        assert!(legend.render(TreeStyle::Ascii).ends_with("Циклов в графе: 1, нераскрытых ветвей: 1\n"));
+
+        let html = Legend::new(&diamond(), &RunStats::default(), false, true);
+        assert!(html.marks.is_empty());
+        assert_eq!(html.render(TreeStyle::Markdown), "Циклов в графе: 0, нераскрытых ветвей: 0\n");
+    }
+
+    #[test]
+    fn unlimited_depth_prints_everything() {
+        assert_eq!(render(None, false), render(None, true));
+        assert!(render(None, false).contains("D"));
+    }
+}
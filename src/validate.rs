@@ -0,0 +1,342 @@
+use crate::config::{CacheKind, Config, NodeMetric, RegistryProtocol, SourceKind, Strategy};
+use crate::output::Exporters;
+use crate::registry;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Значения test_repo_mode: "test" — тестовый граф из файла repository, "remote" — crates.io
+const TEST_REPO_MODES: &[&str] = &["test", "remote"];
+
+const HYPERLINK_MODES: &[&str] = &["auto", "always", "never"];
+
+/// Параметры, которым нужен crates.io: в тестовом режиме они молча не действовали бы
+const REMOTE_ONLY: &[&str] =
+    &["verify_checksums", "fetch_owners", "fetch_dependents", "fetch_metadata", "fetch_docs_status", "as_of"];
+
+/// Замечание к конфигурации: путь к полю ("policy.deny", "merge[1].source") и описание
+struct Diagnostic {
+    path: String,
+    message: String,
+}
+
+/// Проверяет конфигурацию raw до разбора в Config: неизвестные поля (с подсказкой похожего имени),
+/// недопустимые значения перечислимых полей и несовместимые параметры. Все замечания собираются
+/// в одну ошибку с путями к полям и номерами строк, чтобы опечатка не проходила незамеченной
+pub fn load(raw: &str, path: &str) -> Result<Config, String> {
+    let value: Value = serde_json::from_str(raw).map_err(|e| format!("Ошибка разбора JSON: {}", e))?;
+    let mut diagnostics = Vec::new();
+    check_values(&value, &mut diagnostics);
+    let parsed = serde_json::from_str::<Config>(raw);
+    match &parsed {
+        Ok(config) => {
+            let mut known = serde_json::to_value(config).map_err(|e| format!("Ошибка разбора JSON: {}", e))?;
+            // headers не сериализуются, чтобы токены не попадали в отчёт и историю
+            if let (Some(headers), Some(known)) = (value.get("headers"), known.as_object_mut()) {
+                known.insert("headers".to_string(), headers.clone());
+            }
+            unknown_fields(&value, &known, "", &mut diagnostics);
+            conflicts(config, &mut diagnostics);
+        }
+        // Недопустимое значение перечисления уже описано в замечаниях, с подсказкой
+        Err(e) if !diagnostics.is_empty() && e.to_string().starts_with("unknown variant") => {}
+        Err(e) => return Err(render(&diagnostics, raw, path, Some(e))),
+    }
+    if diagnostics.is_empty() {
+        parsed.map_err(|e| format!("Ошибка разбора JSON: {}", e))
+    } else {
+        Err(render(&diagnostics, raw, path, None))
+    }
+}
+
+/// Собирает замечания в сообщение об ошибке: по строке на замечание, в порядке строк файла
+fn render(diagnostics: &[Diagnostic], raw: &str, path: &str, parse_error: Option<&serde_json::Error>) -> String {
+    let lines = key_lines(raw);
+    let mut located: Vec<(usize, String)> = diagnostics
+        .iter()
+        .map(|d| {
+            let line = lines.get(&d.path).copied().unwrap_or(0);
+            let place = if line > 0 { format!("строка {}, {}", line, d.path) } else { d.path.clone() };
+            (line, format!("  {}: {}", place, d.message))
+        })
+        .collect();
+    located.sort();
+    let mut out = format!("Ошибка в конфигурации '{}':", path);
+    for (_, message) in located {
+        out.push('\n');
+        out.push_str(&message);
+    }
+    if let Some(e) = parse_error {
+        out.push_str(&format!("\n  {}", e));
+    }
+    out
+}
+
+/// Значения перечислимых полей: строки с фиксированным набором значений и перечисления Config
+fn check_values(value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    let formats = Exporters::builtin();
+    check_one_of(value, "test_repo_mode", "test_repo_mode", TEST_REPO_MODES, diagnostics);
+    check_one_of(value, "hyperlinks", "hyperlinks", HYPERLINK_MODES, diagnostics);
+    check_one_of(value, "output_format", "output_format", &formats.names(), diagnostics);
+    check_enum::<SourceKind>(value, "source", "source", diagnostics);
+    check_enum::<Strategy>(value, "traversal", "traversal", diagnostics);
+    check_enum::<CacheKind>(value, "cache", "cache", diagnostics);
+    check_enum::<RegistryProtocol>(value, "registry_protocol", "registry_protocol", diagnostics);
+    check_enum::<NodeMetric>(value, "node_size_metric", "node_size_metric", diagnostics);
+    for (i, source) in value.get("merge").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        check_one_of(source, "test_repo_mode", &format!("merge[{}].test_repo_mode", i), TEST_REPO_MODES, diagnostics);
+        check_enum::<SourceKind>(source, "source", &format!("merge[{}].source", i), diagnostics);
+    }
+}
+
+/// Строковое поле field объекта value должно принимать одно из значений allowed
+fn check_one_of(value: &Value, field: &str, path: &str, allowed: &[&str], diagnostics: &mut Vec<Diagnostic>) {
+    let Some(Value::String(actual)) = value.get(field) else { return };
+    if !allowed.contains(&actual.as_str()) {
+        diagnostics.push(Diagnostic { path: path.to_string(), message: bad_value(actual, allowed) });
+    }
+}
+
+/// Поле field должно разбираться как перечисление T; допустимые значения берутся из ошибки serde
+fn check_enum<T: DeserializeOwned>(value: &Value, field: &str, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(Value::String(actual)) = value.get(field) else { return };
+    let Err(e) = serde_json::from_value::<T>(Value::String(actual.clone())) else { return };
+    // "unknown variant `x`, expected one of `a`, `b`" или "expected `a` or `b`"
+    let message = e.to_string();
+    let expected = message.split_once("expected").map_or("", |(_, rest)| rest);
+    let allowed: Vec<&str> = expected.split('`').skip(1).step_by(2).collect();
+    diagnostics.push(Diagnostic { path: path.to_string(), message: bad_value(actual, &allowed) });
+}
+
+fn bad_value(actual: &str, allowed: &[&str]) -> String {
+    let quoted: Vec<String> = allowed.iter().map(|v| format!("\"{}\"", v)).collect();
+    let mut message = format!("недопустимое значение \"{}\" (ожидается {})", actual, quoted.join(", "));
+    if let Some(suggestion) = closest(actual, allowed.iter().copied()) {
+        message.push_str(&format!("; возможно, \"{}\"", suggestion));
+    }
+    message
+}
+
+/// Поля raw, которых нет в разобранной конфигурации known: Config сериализуется со всеми полями,
+/// поэтому всё, что не попало в known, serde пропустил как неизвестное. Словари (headers,
+/// policy.max_depth) сериализуются с ключами пользователя и ложных срабатываний не дают
+fn unknown_fields(raw: &Value, known: &Value, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            for (key, value) in raw {
+                let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match known.get(key) {
+                    Some(known) => unknown_fields(value, known, &field, diagnostics),
+                    None => {
+                        let mut message = "неизвестное поле, оно не действует".to_string();
+                        if let Some(suggestion) = closest(key, known.keys().map(String::as_str)) {
+                            message.push_str(&format!("; возможно, {}", suggestion));
+                        }
+                        diagnostics.push(Diagnostic { path: field, message });
+                    }
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(known)) => {
+            for (i, (raw, known)) in raw.iter().zip(known).enumerate() {
+                unknown_fields(raw, known, &format!("{}[{}]", path, i), diagnostics);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Параметры, которые вместе не имеют смысла или молча не действуют
+fn conflicts(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let mut conflict = |path: &str, message: String| diagnostics.push(Diagnostic { path: path.to_string(), message });
+    if !config.roots.is_empty() && !config.merge.is_empty() {
+        conflict("merge", "roots и merge нельзя задавать вместе".to_string());
+    }
+    if config.verify_checksums && config.registry_protocol != RegistryProtocol::Sparse {
+        conflict("verify_checksums", "поддерживается только при registry_protocol: \"sparse\"".to_string());
+    }
+    if config.resume && config.checkpoint_path.is_none() {
+        conflict("resume", "для продолжения обхода нужен checkpoint_path".to_string());
+    }
+    if config.source != SourceKind::Manifest {
+        for (field, set) in [("target", config.target.is_some()), ("unify_features", config.unify_features)] {
+            if set {
+                conflict(field, "применяется только к локальному манифесту (source: \"manifest\")".to_string());
+            }
+        }
+    }
+    // Тестовый граф читается из файла: параметры crates.io в нём ничего не меняют
+    if config.source == SourceKind::Crawl && config.test_repo_mode == "test" {
+        let fields = serde_json::to_value(config).unwrap_or_default();
+        for field in REMOTE_ONLY {
+            if fields.get(field).is_some_and(|v| !v.is_null() && *v != Value::Bool(false)) {
+                conflict(field, "нужен crates.io, а test_repo_mode: \"test\" читает граф из файла".to_string());
+            }
+        }
+    }
+}
+
+/// Самое похожее на value из вариантов, если оно достаточно близко (опечатка, а не другое слово)
+fn closest<'a>(value: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let value = value.to_lowercase();
+    candidates
+        .map(|candidate| (registry::edit_distance(&value, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.chars().count() / 3).max(2))
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Номера строк (с 1) ключей JSON-документа по путям в том же виде, что у замечаний:
+/// "name", "policy.deny", "merge[1].source". Документ уже разобран, поэтому разметка корректна
+fn key_lines(raw: &str) -> HashMap<String, usize> {
+    enum Frame {
+        /// Путь объекта и последний прочитанный ключ
+        Object(String, Option<String>),
+        /// Путь массива и индекс текущего элемента
+        Array(String, usize),
+    }
+    fn current(stack: &[Frame]) -> String {
+        match stack.last() {
+            None => String::new(),
+            Some(Frame::Object(path, key)) => {
+                let key = key.as_deref().unwrap_or_default();
+                if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+            }
+            Some(Frame::Array(path, index)) => format!("{}[{}]", path, index),
+        }
+    }
+
+    let mut lines = HashMap::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut expect_key = false;
+    let mut line = 1;
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '"' => {
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        c => text.push(c),
+                    }
+                }
+                if expect_key && let Some(Frame::Object(_, key)) = stack.last_mut() {
+                    *key = Some(text);
+                    expect_key = false;
+                    lines.insert(current(&stack), line);
+                }
+            }
+            '{' => {
+                stack.push(Frame::Object(current(&stack), None));
+                expect_key = true;
+            }
+            '[' => stack.push(Frame::Array(current(&stack), 0)),
+            ',' => match stack.last_mut() {
+                Some(Frame::Array(_, index)) => *index += 1,
+                Some(Frame::Object(..)) => expect_key = true,
+                None => {}
+            },
+            '}' | ']' => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Проверяет конфигурацию с обязательными полями (строки 2–6) и полями fields, начиная со строки 7
+    fn check(mode: &str, fields: &str) -> Result<Config, String> {
+        let raw = format!(
+            "{{\n\"name\": \"A\",\n\"version\": \"1.0\",\n\"repository\": \"deps.txt\",\n\"test_repo_mode\": \"{}\",\n\"output_filename\": \"out.txt\", \"ascii_tree_mode\": true,\n{}\n}}",
+            mode, fields
+        );
+        load(&raw, "config.json")
+    }
+
+    fn error(mode: &str, fields: &str) -> String {
+        check(mode, fields).expect_err("ожидалась ошибка конфигурации")
+    }
+
+    #[test]
+    fn unknown_fields_are_reported_with_line_and_suggestion() {
+        let message = error("test", "\"max_dpeth\": 3,\n\"colour\": true");
+        assert!(message.starts_with("Ошибка в конфигурации 'config.json':"), "{}", message);
+        assert!(message.contains("строка 7, max_dpeth: неизвестное поле, оно не действует; возможно, max_depth"), "{}", message);
+        // Слишком непохожее имя остаётся без подсказки
+        assert!(message.ends_with("строка 8, colour: неизвестное поле, оно не действует"), "{}", message);
+    }
+
+    #[test]
+    fn nested_paths_point_to_fields_in_objects_and_arrays() {
+        let fields = "\"policy\": {\n  \"deny\": [\"x\"],\n  \"deni\": [\"y\"]\n},\n\"merge\": [\n  {\"name\": \"B\"},\n  {\"name\": \"C\",\n   \"sorce\": \"json\"}\n]";
+        let message = error("test", fields);
+        assert!(message.contains("строка 9, policy.deni: неизвестное поле, оно не действует; возможно, deny"), "{}", message);
+        assert!(message.contains("строка 14, merge[1].sorce: неизвестное поле, оно не действует; возможно, source"), "{}", message);
+        // Замечания идут в порядке строк файла
+        assert!(message.find("policy.deni") < message.find("merge[1].sorce"), "{}", message);
+    }
+
+    #[test]
+    fn invalid_enum_values_suggest_allowed_ones() {
+        let message = error("test", "\"traversal\": \"bsf\",\n\"merge\": [{\"name\": \"B\", \"source\": \"jsn\"}]");
+        assert!(message.contains("строка 7, traversal: недопустимое значение \"bsf\""), "{}", message);
+        assert!(message.contains("возможно, \"bfs\""), "{}", message);
+        assert!(message.contains("строка 8, merge[0].source: недопустимое значение \"jsn\""), "{}", message);
+        assert!(message.contains("возможно, \"json\""), "{}", message);
+    }
+
+    #[test]
+    fn key_lines_skip_escaped_quotes_and_markup_inside_strings() {
+        let raw = "{\n\"title\": \"say \\\"hi\\\", {\\\"fake\\\": [1,\\n 2]}\",\n\"items\": [\n  {\"a\": 1}, {\"b\": \"]\"},\n  {\"c\": 2}\n]\n}";
+        let lines = key_lines(raw);
+        assert_eq!(lines.get("title"), Some(&2));
+        assert_eq!(lines.get("items"), Some(&3));
+        assert_eq!(lines.get("items[0].a"), Some(&4));
+        assert_eq!(lines.get("items[1].b"), Some(&4));
+        assert_eq!(lines.get("items[2].c"), Some(&5));
+        assert!(!lines.contains_key("fake"));
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn conflicting_options_are_reported() {
+        let message = error("remote", "\"roots\": [{\"name\": \"B\", \"version\": \"1.0\"}],\n\"merge\": [{\"name\": \"C\"}]");
+        assert!(message.contains("строка 8, merge: roots и merge нельзя задавать вместе"), "{}", message);
+
+        let message = error("remote", "\"verify_checksums\": true");
+        assert!(message.contains("строка 7, verify_checksums: поддерживается только при registry_protocol: \"sparse\""), "{}", message);
+        assert!(check("remote", "\"verify_checksums\": true,\n\"registry_protocol\": \"sparse\"").is_ok());
+
+        let message = error("remote", "\"resume\": true");
+        assert!(message.contains("строка 7, resume: для продолжения обхода нужен checkpoint_path"), "{}", message);
+        assert!(check("remote", "\"resume\": true,\n\"checkpoint_path\": \"walk.ckpt\"").is_ok());
+
+        let message = error("remote", "\"target\": \"x86_64-unknown-linux-gnu\",\n\"unify_features\": true");
+        let manifest_only = "применяется только к локальному манифесту (source: \"manifest\")";
+        assert!(message.contains(&format!("строка 7, target: {}", manifest_only)), "{}", message);
+        assert!(message.contains(&format!("строка 8, unify_features: {}", manifest_only)), "{}", message);
+        assert!(check("remote", "\"source\": \"manifest\",\n\"target\": \"x86_64-unknown-linux-gnu\",\n\"unify_features\": true").is_ok());
+    }
+
+    #[test]
+    fn remote_only_options_are_rejected_in_test_mode() {
+        let fields = "\"fetch_metadata\": true,\n\"as_of\": \"2024-01-01\",\n\"fetch_owners\": false";
+        let message = error("test", fields);
+        let remote_only = "нужен crates.io, а test_repo_mode: \"test\" читает граф из файла";
+        assert!(message.contains(&format!("строка 7, fetch_metadata: {}", remote_only)), "{}", message);
+        assert!(message.contains(&format!("строка 8, as_of: {}", remote_only)), "{}", message);
+        // Явно выключенный параметр не мешает
+        assert!(!message.contains("fetch_owners"), "{}", message);
+        assert!(check("remote", fields).is_ok());
+    }
+}